@@ -0,0 +1,266 @@
+// Client for the Instagram scraping backend (instagram-api).
+//
+// One pooled `reqwest::Client` is shared by every call; clones of
+// `InstagramApiClient` share it too. Query parameters are always encoded by
+// the URL builder, never spliced in with `format!`.
+
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+use crate::error::InstagrapError;
+
+pub const API_BASE: &str = "https://instagram-api-672383441505.europe-west1.run.app";
+
+#[derive(Clone, Debug)]
+pub struct ApiTimeouts {
+    pub connect: Duration,
+    /// Quick calls: login status, status polling, artifact deletion
+    pub request: Duration,
+    /// Registering the login state
+    pub register: Duration,
+    /// Starting a scrape, which may run to completion before answering
+    pub scrape: Duration,
+}
+
+impl Default for ApiTimeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            request: Duration::from_secs(10),
+            register: Duration::from_secs(30),
+            scrape: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Body of `POST /remote-scrape`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoteScrapeRequest {
+    pub target: String,
+    pub target_yes: u32,
+    pub batch_size: u32,
+    pub num_bio_pages: u32,
+    pub criteria_preset_id: Option<String>,
+    pub criteria_text: Option<String>,
+}
+
+// Phrases in backend failure messages that mark a transient failure
+const TRANSIENT_FAILURE_HINTS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "rate limit",
+    "too many requests",
+    "temporarily",
+    "unavailable",
+    "connection",
+    "try again",
+];
+
+/// Answer of `/remote-scrape`, `/cancel-scrape` and the status endpoints.
+/// `status` is one of queued, running, completed, failed or cancelled;
+/// fields we don't model are kept in `extra` so the frontend still sees them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ScrapeStatus {
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ScrapeStatus {
+    /// Failure reason, whichever field the backend used for it.
+    pub fn failure_message(&self) -> Option<&str> {
+        self.message.as_deref().or(self.error_message.as_deref())
+    }
+
+    /// Whether a failed scrape may succeed if started again: the backend
+    /// says so in `retryable`, or its message reads like a timeout, rate
+    /// limit or outage rather than a problem with the target.
+    pub fn is_transient_failure(&self) -> bool {
+        if let Some(retryable) = self.extra.get("retryable").and_then(Value::as_bool) {
+            return retryable;
+        }
+        let message = self.failure_message().unwrap_or_default().to_lowercase();
+        TRANSIENT_FAILURE_HINTS.iter().any(|hint| message.contains(hint))
+    }
+}
+
+/// Answer of `GET /login-status`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LoginStatus {
+    /// Empty when the backend only answers with `ok`
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ok: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InstagramApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    timeouts: ApiTimeouts,
+}
+
+impl Default for InstagramApiClient {
+    fn default() -> Self {
+        Self::new(API_BASE).expect("default API base URL is valid")
+    }
+}
+
+impl InstagramApiClient {
+    pub fn new(base_url: &str) -> Result<Self, InstagrapError> {
+        Self::with_timeouts(base_url, ApiTimeouts::default())
+    }
+
+    pub fn with_timeouts(base_url: &str, timeouts: ApiTimeouts) -> Result<Self, InstagrapError> {
+        let base_url = Url::parse(base_url)
+            .map_err(|e| InstagrapError::Validation(format!("Invalid API base URL {}: {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() {
+            return Err(InstagrapError::Validation(format!("Invalid API base URL {}", base_url)));
+        }
+        let http = reqwest::Client::builder()
+            .connect_timeout(timeouts.connect)
+            .user_agent("InstaGrap/1.0")
+            .build()
+            .map_err(|e| InstagrapError::Network(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(Self { http, base_url, timeouts })
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    /// `base_url` + `path`, with every query pair percent-encoded.
+    pub fn url(&self, path: &str, query: &[(&str, &str)]) -> Url {
+        let mut url = self.base_url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(path.split('/').filter(|s| !s.is_empty()));
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    pub async fn register_state(&self, gcs_uri: &str) -> Result<(), InstagrapError> {
+        eprintln!("🔍 [DEBUG] Attempting to register state with URI: {}", gcs_uri);
+        if !gcs_uri.starts_with("gs://") {
+            return Err(InstagrapError::validation("Invalid GCS URI: must start with gs://"));
+        }
+
+        let request = self
+            .http
+            .post(self.url("register-state", &[]))
+            .timeout(self.timeouts.register)
+            .json(&json!({ "gcs_uri": gcs_uri }));
+        let response = send(request).await?;
+
+        // The body is informational only; a success status is what matters
+        match response.json::<Value>().await {
+            Ok(body) => eprintln!("✅ [DEBUG] Registration successful: {:?}", body),
+            Err(e) => eprintln!("⚠️ [DEBUG] Response received but couldn't parse JSON: {}", e),
+        }
+        Ok(())
+    }
+
+    pub async fn login_status(&self) -> Result<LoginStatus, InstagrapError> {
+        let request = self.http.get(self.url("login-status", &[])).timeout(self.timeouts.request);
+        match send(request).await {
+            Ok(response) => parse_json(response).await,
+            // Treat timeout as no login state so UI can proceed
+            Err(InstagrapError::Timeout(_)) => Ok(LoginStatus {
+                status: "none".into(),
+                ok: Some(false),
+                message: Some("timeout".into()),
+                extra: Map::new(),
+            }),
+            Err(e) => {
+                eprintln!("DBG login-status error: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Status of a scrape, by GCS `exec_id` first and by the legacy operation
+    /// id when the exec lookup answers 404 or there is no exec id.
+    pub async fn scrape_status(&self, exec_id: &str, target: &str, legacy_operation: Option<&str>) -> Result<ScrapeStatus, InstagrapError> {
+        if !exec_id.is_empty() {
+            let url = self.url("scrape-status", &[("target", target), ("exec_id", exec_id)]);
+            match send(self.http.get(url).timeout(self.timeouts.request)).await {
+                Ok(response) => return parse_json(response).await,
+                // Only a missing status object means the job may be a legacy one
+                Err(InstagrapError::BackendHttp { status: 404, .. }) if legacy_operation.is_some() => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if let Some(operation) = legacy_operation {
+            let url = self.url("legacy-scrape-status", &[("operation", operation)]);
+            let response = send(self.http.get(url).timeout(self.timeouts.request)).await?;
+            return parse_json(response).await;
+        }
+
+        Err(InstagrapError::validation("No valid identifier to check status"))
+    }
+
+    /// Starts a scrape on the backend.
+    pub async fn remote_scrape(&self, req: &RemoteScrapeRequest) -> Result<ScrapeStatus, InstagrapError> {
+        eprintln!("🔍 [DEBUG] Making request to backend with body: {}", serde_json::to_string(req).unwrap_or_default());
+        let request = self.http.post(self.url("remote-scrape", &[])).timeout(self.timeouts.scrape).json(req);
+        let result: ScrapeStatus = parse_json(send(request).await?).await?;
+        eprintln!("🔍 [DEBUG] Backend response: {:?}", result);
+        Ok(result)
+    }
+
+    /// Asks the backend to stop a scrape. The answer carries whatever results
+    /// were classified before it stopped.
+    pub async fn cancel_scrape(&self, target: &str, exec_id: Option<&str>, legacy_operation: &str) -> Result<ScrapeStatus, InstagrapError> {
+        let body = json!({ "target": target, "exec_id": exec_id, "operation": legacy_operation });
+        let request = self.http.post(self.url("cancel-scrape", &[])).timeout(self.timeouts.request).json(&body);
+        parse_json(send(request).await?).await
+    }
+
+    pub async fn delete_scrape_artifacts(&self, target: &str, exec_id: &str) -> Result<(), InstagrapError> {
+        let url = self.url("scrape-artifacts", &[("target", target), ("exec_id", exec_id)]);
+        send(self.http.delete(url).timeout(self.timeouts.request)).await?;
+        Ok(())
+    }
+}
+
+// Sends the request and turns a non-success status into an error
+async fn send(request: RequestBuilder) -> Result<reqwest::Response, InstagrapError> {
+    let response = request.send().await?;
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    // The backend answers 401/403 when the registered login state is stale
+    match InstagrapError::from_response(response).await {
+        InstagrapError::BackendHttp { status: 401 | 403, .. } => Err(InstagrapError::LoginExpired),
+        e => Err(e),
+    }
+}
+
+// Reads the body first so a parse failure can show what we got
+async fn parse_json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, InstagrapError> {
+    let body = response.text().await?;
+    serde_json::from_str(&body).map_err(|e| {
+        let preview: String = body.chars().take(500).collect();
+        InstagrapError::InvalidResponse(format!("{} | body_preview={}", e, preview))
+    })
+}
@@ -0,0 +1,497 @@
+// Opt-in localhost REST API so other tools can push targets into InstaGrap and
+// pull results out.
+//
+// The server only binds to 127.0.0.1 and every route except `/api/health`
+// requires the token, sent as `Authorization: Bearer <token>` or
+// `X-Instagrap-Token`. Requests are handled one at a time on a dedicated
+// thread, against the same shared state as the Tauri commands.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use tiny_http::{Header, Method, Request, Response, Server};
+use uuid::Uuid;
+
+use crate::export;
+use crate::operations::{self, operation_json, ScrapeRequest};
+use crate::queue;
+use crate::results::ProfileResult;
+use crate::error::InstagrapError;
+use crate::state::AppStateManager;
+use crate::status::TodoStatus;
+use crate::timeline::timeline_json;
+use crate::todos::{self, todo_json, CreateTodoRequest};
+
+pub const DEFAULT_PORT: u16 = 48620;
+
+// Request bodies are small JSON documents; anything bigger is a mistake
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl AutomationSettings {
+    /// Saved settings, or disabled defaults with a fresh token (saved right away
+    /// so the token stays stable once shown to the user).
+    pub fn load(state: &AppStateManager) -> Result<Self, InstagrapError> {
+        let app_state = state.lock()?;
+        if let Some(settings) = app_state.store.load_automation_settings()? {
+            return Ok(settings);
+        }
+        let settings = Self { enabled: false, port: DEFAULT_PORT, token: new_token() };
+        app_state.store.save_automation_settings(&settings)?;
+        Ok(settings)
+    }
+
+    pub fn save(&self, state: &AppStateManager) -> Result<(), InstagrapError> {
+        state.lock()?.store.save_automation_settings(self)
+    }
+}
+
+/// Stops the server in `slot` (if any) and starts a new one when `settings`
+/// has it enabled.
+pub fn apply_settings(slot: &mut Option<AutomationServer>, state: &AppStateManager, settings: &AutomationSettings) -> Result<(), InstagrapError> {
+    // Drop first so a restart on the same port can bind again
+    slot.take();
+    if settings.enabled {
+        *slot = Some(AutomationServer::start(state.clone(), settings.port, settings.token.clone())?);
+    }
+    Ok(())
+}
+
+pub fn status_json(settings: &AutomationSettings, server: Option<&AutomationServer>) -> Value {
+    json!({
+        "enabled": settings.enabled,
+        "port": settings.port,
+        "token": settings.token,
+        "running": server.is_some(),
+        "url": server.map(|s| format!("http://127.0.0.1:{}/api", s.port())),
+    })
+}
+
+pub fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// A running server; dropping it stops the server.
+pub struct AutomationServer {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+}
+
+impl AutomationServer {
+    /// Binds 127.0.0.1:`port` (0 picks a free port) and starts serving.
+    pub fn start(state: AppStateManager, port: u16, token: String) -> Result<Self, InstagrapError> {
+        if token.trim().is_empty() {
+            return Err(InstagrapError::validation("Automation token must not be empty"));
+        }
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| InstagrapError::Network(format!("Failed to start automation server on port {}: {}", port, e)))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .ok_or_else(|| InstagrapError::Internal("Automation server is not listening on a TCP port".to_string()))?;
+        let server = Arc::new(server);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| InstagrapError::Internal(format!("Failed to create automation runtime: {}", e)))?;
+        let serving = server.clone();
+        let thread = std::thread::Builder::new()
+            .name("automation-api".into())
+            .spawn(move || {
+                for request in serving.incoming_requests() {
+                    handle(&state, &runtime, &token, request);
+                }
+            })
+            .map_err(|e| InstagrapError::Internal(format!("Failed to spawn automation server thread: {}", e)))?;
+
+        eprintln!("✅ Automation API listening on http://127.0.0.1:{}", port);
+        Ok(Self { server, thread: Some(thread), port })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Blocks until the server is stopped from another thread.
+    pub fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl Drop for AutomationServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+        eprintln!("ℹ️ Automation API on port {} stopped", self.port);
+    }
+}
+
+// ===== Request handling =====
+
+enum Reply {
+    Json(u16, Value),
+    Csv(String),
+}
+
+type ApiResult = Result<Reply, InstagrapError>;
+
+// HTTP status for an error; the body is always the serialized InstagrapError
+fn http_status(e: &InstagrapError) -> u16 {
+    match e {
+        InstagrapError::Validation(_) => 400,
+        InstagrapError::NotFound(_) => 404,
+        InstagrapError::InvalidTransition(_) | InstagrapError::Conflict(_) => 409,
+        InstagrapError::Timeout(_) => 504,
+        InstagrapError::Network(_)
+        | InstagrapError::BackendHttp { .. }
+        | InstagrapError::InvalidResponse(_)
+        | InstagrapError::LoginExpired => 502,
+        InstagrapError::Persistence(_) | InstagrapError::Browser(_) | InstagrapError::Internal(_) => 500,
+    }
+}
+
+fn handle(state: &AppStateManager, runtime: &tokio::runtime::Runtime, token: &str, mut request: Request) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let reply = if segments == ["api", "health"] {
+        Ok(Reply::Json(200, json!({ "ok": true })))
+    } else if !authorized(&request, token) {
+        let error = InstagrapError::validation("Missing or invalid token");
+        request.respond(json_response(401, &json!({ "error": error }))).ok();
+        return;
+    } else {
+        read_body(&mut request).and_then(|body| {
+            let call = Call { state, runtime, query: parse_query(query), body };
+            route(&call, &method, &segments)
+        })
+    };
+
+    let response = match reply {
+        Ok(Reply::Json(status, body)) => json_response(status, &body),
+        Ok(Reply::Csv(body)) => Response::from_string(body)
+            .with_header(header("Content-Type", "text/csv; charset=utf-8")),
+        Err(e) => {
+            let status = http_status(&e);
+            if status >= 500 {
+                eprintln!("❌ Automation API {} {}: {}", method, path, e);
+            }
+            json_response(status, &json!({ "error": e }))
+        }
+    };
+    request.respond(response).ok();
+}
+
+struct Call<'a> {
+    state: &'a AppStateManager,
+    runtime: &'a tokio::runtime::Runtime,
+    query: Vec<(String, String)>,
+    body: String,
+}
+
+impl Call<'_> {
+    fn param(&self, key: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, InstagrapError> {
+        let body = if self.body.trim().is_empty() { "{}" } else { &self.body };
+        serde_json::from_str(body).map_err(|e| InstagrapError::Validation(format!("Invalid request body: {}", e)))
+    }
+}
+
+fn route(call: &Call, method: &Method, segments: &[&str]) -> ApiResult {
+    let state = call.state;
+    match (method, segments) {
+        // ----- Todos -----
+        (Method::Get, ["api", "todos"]) => {
+            let status: Option<TodoStatus> = call
+                .param("status")
+                .map(|s| s.parse())
+                .transpose()
+                .map_err(InstagrapError::Validation)?;
+            let todos: Vec<Value> = state
+                .lock()?
+                .get_todos()
+                .iter()
+                .filter(|t| status.is_none_or(|s| t.status == s))
+                .map(todo_json)
+                .collect();
+            Ok(Reply::Json(200, json!({ "todos": todos })))
+        }
+        (Method::Post, ["api", "todos"]) => {
+            let req: CreateTodoRequest = call.json()?;
+            if req.target_account.trim().is_empty() {
+                return Err(InstagrapError::validation("target_account is required"));
+            }
+            let mut app_state = state.lock()?;
+            let id = app_state.create_todo(req)?;
+            let todo = app_state.get_todo(&id).as_ref().map(todo_json).unwrap_or(Value::Null);
+            Ok(Reply::Json(201, todo))
+        }
+        (Method::Get, ["api", "todos", id]) => {
+            let app_state = state.lock()?;
+            let todo = app_state.get_todo(id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            Ok(Reply::Json(200, todo_json(&todo)))
+        }
+        (Method::Delete, ["api", "todos", id]) => {
+            let mut app_state = state.lock()?;
+            if app_state.get_todo(id).is_none() {
+                return Err(InstagrapError::not_found("Todo"));
+            }
+            app_state.delete_todo(id)?;
+            Ok(Reply::Json(200, json!({ "deleted": id })))
+        }
+        (Method::Post, ["api", "todos", id, "run"]) => {
+            #[derive(Deserialize)]
+            struct RunBody {
+                criteria_preset_id: Option<String>,
+            }
+            let body: RunBody = call.json()?;
+            require_todo(state, id)?;
+            let response = call.runtime.block_on(todos::run_todo(state, id, body.criteria_preset_id))?;
+            Ok(Reply::Json(200, response))
+        }
+        (Method::Post, ["api", "todos", id, "refresh"]) => {
+            require_todo(state, id)?;
+            call.runtime.block_on(todos::refresh_todo(state, id))?;
+            let app_state = state.lock()?;
+            let todo = app_state.get_todo(id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            Ok(Reply::Json(200, todo_json(&todo)))
+        }
+        (Method::Post, ["api", "todos", id, "retry"]) => {
+            state.lock()?.retry_todo(id)?;
+            call.runtime.block_on(queue::fill_slots(state))?;
+            let app_state = state.lock()?;
+            let todo = app_state.get_todo(id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            Ok(Reply::Json(200, todo_json(&todo)))
+        }
+        (Method::Get, ["api", "todos", id, "results"]) => {
+            let results = state.lock()?.get_todo(id).map(|t| t.results.unwrap_or_default());
+            results_reply(call, results.ok_or_else(|| InstagrapError::not_found("Todo"))?)
+        }
+
+        // ----- Todo queue -----
+        (Method::Get, ["api", "queue"]) => Ok(Reply::Json(200, state.lock()?.queue_json())),
+        (Method::Post, ["api", "queue", "run-all"]) => Ok(Reply::Json(200, call.runtime.block_on(queue::run_all(state))?)),
+        (Method::Post, ["api", "queue", "run-next"]) => Ok(Reply::Json(200, call.runtime.block_on(queue::run_next(state))?)),
+        (Method::Put, ["api", "queue"]) => {
+            #[derive(Deserialize)]
+            struct QueueBody {
+                max_concurrent: u32,
+            }
+            let body: QueueBody = call.json()?;
+            let mut app_state = state.lock()?;
+            app_state.set_max_concurrent(body.max_concurrent)?;
+            Ok(Reply::Json(200, app_state.queue_json()))
+        }
+
+        // ----- Scrapes and operations -----
+        (Method::Post, ["api", "scrapes"]) => {
+            let req: ScrapeRequest = call.json()?;
+            if req.target.trim().is_empty() {
+                return Err(InstagrapError::validation("target is required"));
+            }
+            let response = call.runtime.block_on(operations::start_remote_scrape(state, req))?;
+            Ok(Reply::Json(200, response))
+        }
+        (Method::Get, ["api", "operations"]) => {
+            let operations: Vec<Value> = state.lock()?.operations().iter().map(operation_json).collect();
+            Ok(Reply::Json(200, json!({ "operations": operations })))
+        }
+        (Method::Get, ["api", "operations", id]) => {
+            if state.lock()?.get_operation(id).is_none() {
+                return Err(InstagrapError::not_found("Operation"));
+            }
+            let status = call.runtime.block_on(operations::check_operation_status(state, id))?;
+            Ok(Reply::Json(200, status))
+        }
+        (Method::Post, ["api", "operations", id, "cancel"]) => {
+            let delete_artifacts = call.param("delete_artifacts").is_some_and(|v| v == "true" || v == "1");
+            let response = call.runtime.block_on(operations::cancel_operation(state, id, delete_artifacts))?;
+            Ok(Reply::Json(200, response))
+        }
+        (Method::Get, ["api", "operations", id, "timeline"]) => {
+            let app_state = state.lock()?;
+            let operation = app_state.get_operation(id).ok_or_else(|| InstagrapError::not_found("Operation"))?;
+            Ok(Reply::Json(200, timeline_json(&operation)))
+        }
+        (Method::Get, ["api", "operations", id, "results"]) => {
+            let results = state.lock()?.get_operation(id).map(|op| op.results.unwrap_or_default());
+            results_reply(call, results.ok_or_else(|| InstagrapError::not_found("Operation"))?)
+        }
+
+        // ----- Criteria presets -----
+        (Method::Get, ["api", "criteria"]) => Ok(Reply::Json(200, state.lock()?.saved_criteria_json())),
+        (Method::Post, ["api", "criteria"]) => {
+            #[derive(Deserialize)]
+            struct CreateBody {
+                name: String,
+                criteria: String,
+            }
+            let body: CreateBody = call.json()?;
+            let id = state.lock()?.add_criteria_preset(body.name, body.criteria)?;
+            Ok(Reply::Json(201, json!({ "id": id })))
+        }
+        (Method::Put, ["api", "criteria", "active"]) => {
+            #[derive(Deserialize)]
+            struct ActiveBody {
+                id: Option<String>,
+            }
+            let body: ActiveBody = call.json()?;
+            state.lock()?.set_active_criteria(body.id.clone())?;
+            Ok(Reply::Json(200, json!({ "active_id": body.id })))
+        }
+        (Method::Put, ["api", "criteria", id]) => {
+            #[derive(Deserialize)]
+            struct UpdateBody {
+                name: Option<String>,
+                criteria: Option<String>,
+            }
+            let body: UpdateBody = call.json()?;
+            let mut app_state = state.lock()?;
+            if app_state.get_criteria_preset(id).is_none() {
+                return Err(InstagrapError::not_found("Criteria preset"));
+            }
+            if let Some(name) = body.name {
+                app_state.rename_criteria_preset(id, name)?;
+            }
+            if let Some(criteria) = body.criteria {
+                app_state.update_criteria_preset(id, criteria)?;
+            }
+            Ok(Reply::Json(200, app_state.saved_criteria_json()))
+        }
+        (Method::Delete, ["api", "criteria", id]) => {
+            let mut app_state = state.lock()?;
+            if app_state.get_criteria_preset(id).is_none() {
+                return Err(InstagrapError::not_found("Criteria preset"));
+            }
+            app_state.delete_criteria_preset(id)?;
+            Ok(Reply::Json(200, json!({ "deleted": id })))
+        }
+
+        // ----- Profile store -----
+        (Method::Get, ["api", "profiles"]) => {
+            let number = |key: &str, default: u32| -> Result<u32, InstagrapError> {
+                call.param(key)
+                    .map(|v| v.parse().map_err(|_| InstagrapError::Validation(format!("Invalid {}", key))))
+                    .unwrap_or(Ok(default))
+            };
+            let (profiles, total) = state.lock()?.store.search_profiles(
+                call.param("query"),
+                call.param("target").filter(|t| !t.trim().is_empty()),
+                number("limit", 50)?.min(500),
+                number("offset", 0)?,
+            )?;
+            Ok(Reply::Json(200, json!({ "profiles": profiles, "total": total })))
+        }
+
+        _ => Err(InstagrapError::not_found("Route")),
+    }
+}
+
+fn require_todo(state: &AppStateManager, id: &str) -> Result<(), InstagrapError> {
+    match state.lock()?.get_todo(id) {
+        Some(_) => Ok(()),
+        None => Err(InstagrapError::not_found("Todo")),
+    }
+}
+
+fn results_reply(call: &Call, results: Vec<ProfileResult>) -> ApiResult {
+    match call.param("format").unwrap_or("json") {
+        "csv" => Ok(Reply::Csv(export::to_csv(&results))),
+        "json" => Ok(Reply::Json(200, json!({ "results": results, "count": results.len() }))),
+        other => Err(InstagrapError::Validation(format!("Unknown format: {}", other))),
+    }
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    request.headers().iter().any(|h| {
+        let value = h.value.as_str().trim();
+        if h.field.equiv("Authorization") {
+            value.strip_prefix("Bearer ").is_some_and(|v| constant_time_eq(v.trim(), token))
+        } else if h.field.equiv("X-Instagrap-Token") {
+            constant_time_eq(value, token)
+        } else {
+            false
+        }
+    })
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn read_body(request: &mut Request) -> Result<String, InstagrapError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| InstagrapError::Validation(format!("Failed to read request body: {}", e)))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(InstagrapError::validation("Request body too large"));
+    }
+    Ok(body)
+}
+
+pub(crate) fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+pub(crate) fn json_response(status: u16, body: &Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
@@ -0,0 +1,140 @@
+// Timestamped backups, atomic file replacement and quarantine of corrupt files
+// for the SQLite state store.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::InstagrapError;
+
+/// Number of timestamped backups kept before the oldest ones are pruned.
+pub const MAX_BACKUPS: usize = 10;
+
+/// Minimum age of the newest backup before opening the store takes another,
+/// so that frequent CLI runs don't rotate the older backups out.
+pub const AUTO_BACKUP_INTERVAL_MINUTES: i64 = 60;
+
+const BACKUP_DIR_NAME: &str = "instagram_scraper_backups";
+const BACKUP_PREFIX: &str = "instagram_scraper_state-";
+const BACKUP_EXTENSION: &str = ".db";
+
+#[derive(Serialize, Clone)]
+pub struct BackupInfo {
+    pub name: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+}
+
+pub fn backup_dir(config_dir: &Path) -> Result<PathBuf, InstagrapError> {
+    let dir = config_dir.join(BACKUP_DIR_NAME);
+    fs::create_dir_all(&dir).map_err(|e| InstagrapError::Persistence(format!("Failed to create backup directory: {}", e)))?;
+    Ok(dir)
+}
+
+/// Returns a fresh, not-yet-existing path for a new backup in `dir`.
+pub fn new_backup_path(dir: &Path) -> PathBuf {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%3fZ");
+    dir.join(format!("{}{}{}", BACKUP_PREFIX, stamp, BACKUP_EXTENSION))
+}
+
+/// Lists backups newest first.
+pub fn list_backups(config_dir: &Path) -> Result<Vec<BackupInfo>, InstagrapError> {
+    let dir = backup_dir(config_dir)?;
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(&dir).map_err(|e| InstagrapError::Persistence(format!("Failed to read backup directory: {}", e)))? {
+        let entry = entry.map_err(|e| InstagrapError::Persistence(format!("Failed to read backup directory: {}", e)))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_backup_name(&name) {
+            continue;
+        }
+        let metadata = entry.metadata().map_err(|e| InstagrapError::Persistence(format!("Failed to read backup metadata: {}", e)))?;
+        let created_at = metadata
+            .modified()
+            .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+            .unwrap_or_default();
+        backups.push(BackupInfo { name, created_at, size_bytes: metadata.len() });
+    }
+    // Names embed a sortable UTC timestamp
+    backups.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(backups)
+}
+
+/// Deletes all but the newest `keep` backups.
+pub fn prune_backups(config_dir: &Path, keep: usize) -> Result<(), InstagrapError> {
+    let dir = backup_dir(config_dir)?;
+    for old in list_backups(config_dir)?.into_iter().skip(keep) {
+        fs::remove_file(dir.join(&old.name))
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to remove old backup {}: {}", old.name, e)))?;
+    }
+    Ok(())
+}
+
+/// Maps a backup name coming from the frontend to its path, rejecting anything
+/// that is not a plain backup file name inside the backup directory.
+pub fn resolve_backup(config_dir: &Path, name: &str) -> Result<PathBuf, InstagrapError> {
+    if !is_backup_name(name) || name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err(InstagrapError::Persistence(format!("Invalid backup name: {}", name)));
+    }
+    let path = backup_dir(config_dir)?.join(name);
+    if !path.is_file() {
+        return Err(InstagrapError::Persistence(format!("Backup not found: {}", name)));
+    }
+    Ok(path)
+}
+
+/// Moves a corrupt file (and any SQLite sidecar files) aside so it can be inspected later.
+pub fn quarantine(path: &Path) -> Result<PathBuf, InstagrapError> {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let quarantined = with_suffix(path, &format!(".corrupt-{}", stamp));
+    fs::rename(path, &quarantined)
+        .map_err(|e| InstagrapError::Persistence(format!("Failed to quarantine {}: {}", path.display(), e)))?;
+    for sidecar in ["-wal", "-shm"] {
+        let side = with_suffix(path, sidecar);
+        if side.exists() {
+            fs::rename(&side, with_suffix(&quarantined, sidecar)).ok();
+        }
+    }
+    eprintln!("⚠️ Quarantined corrupt state file to {}", quarantined.display());
+    Ok(quarantined)
+}
+
+/// Replaces the database at `db_path` with a copy of `source`. The copy is
+/// written next to the target first and then renamed into place.
+pub fn replace_database(source: &Path, db_path: &Path) -> Result<(), InstagrapError> {
+    let data = fs::read(source).map_err(|e| InstagrapError::Persistence(format!("Failed to read {}: {}", source.display(), e)))?;
+    // A leftover WAL would be replayed on top of the restored file
+    for sidecar in ["-wal", "-shm"] {
+        let side = with_suffix(db_path, sidecar);
+        if side.exists() {
+            fs::remove_file(&side).map_err(|e| InstagrapError::Persistence(format!("Failed to remove {}: {}", side.display(), e)))?;
+        }
+    }
+    write_atomic(db_path, &data)
+}
+
+/// Writes `data` to a temp file next to `path`, syncs it and renames it over `path`.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), InstagrapError> {
+    let tmp_path = with_suffix(path, ".tmp");
+    let mut file = File::create(&tmp_path)
+        .map_err(|e| InstagrapError::Persistence(format!("Failed to create {}: {}", tmp_path.display(), e)))?;
+    file.write_all(data)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| InstagrapError::Persistence(format!("Failed to write {}: {}", tmp_path.display(), e)))?;
+    drop(file);
+    fs::rename(&tmp_path, path).map_err(|e| {
+        fs::remove_file(&tmp_path).ok();
+        InstagrapError::Persistence(format!("Failed to move {} into place: {}", path.display(), e))
+    })
+}
+
+pub fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(suffix);
+    PathBuf::from(s)
+}
+
+fn is_backup_name(name: &str) -> bool {
+    name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION)
+}
@@ -0,0 +1,531 @@
+// Headless command line interface to the same state database and backend as
+// the desktop app, for cron jobs and shell scripts. Commands refuse to run
+// while the desktop app has the database open; the automation API covers that
+// case.
+//
+// Results go to stdout (as JSON with --json); logs and errors go to stderr.
+// Exit codes: 0 success, 1 error, 2 a waited-for scrape finished as failed.
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use instagrap_lib::error::InstagrapError;
+use instagrap_lib::export;
+use instagrap_lib::mock::{MockBackend, MockScenario};
+use instagrap_lib::operations::{self, operation_json, ConflictResolution, ScrapeRequest};
+use instagrap_lib::queue;
+use instagrap_lib::reconcile;
+use instagrap_lib::results::ProfileResult;
+use instagrap_lib::state::AppStateManager;
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::store::WhenLocked;
+use instagrap_lib::timeline::{timeline_json, OperationEvent};
+use instagrap_lib::todos::{self, todo_json, CreateTodoRequest};
+
+#[derive(Parser)]
+#[command(name = "instagrap-cli", version, about = "Queue and monitor Instagram scrapes without the desktop window")]
+struct Cli {
+    /// Print machine-readable JSON on stdout
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Run against an in-process mock backend with throwaway state
+    #[arg(long, global = true)]
+    demo: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage queued scrape todos
+    Todo {
+        #[command(subcommand)]
+        command: TodoCommand,
+    },
+    /// Start a scrape of a target account right away
+    Scrape(ScrapeArgs),
+    /// Show the status of an operation, refreshing it from the backend if running
+    Status { operation: String },
+    /// Show what happened to an operation so far
+    Timeline { operation: String },
+    /// Check every running operation and todo against the backend once,
+    /// marking the ones it no longer knows as lost
+    Reconcile,
+    /// Stop a running operation, keeping the results it has so far
+    Cancel {
+        operation: String,
+        /// Also delete the run's artifacts on the backend
+        #[arg(long)]
+        delete_artifacts: bool,
+    },
+    /// Manage saved classifier criteria presets
+    Criteria {
+        #[command(subcommand)]
+        command: CriteriaCommand,
+    },
+    /// Write results as CSV or JSON
+    Export(ExportArgs),
+}
+
+#[derive(Subcommand)]
+enum TodoCommand {
+    /// Queue a new todo
+    Add {
+        target: String,
+        /// Number of matching profiles to collect
+        #[arg(long, default_value_t = 50)]
+        count: u32,
+        #[arg(long, default_value_t = 1)]
+        bio_agents: u32,
+        #[arg(long, default_value_t = 10)]
+        batch_size: u32,
+        /// Criteria preset id; the backend default is used when omitted
+        #[arg(long)]
+        preset: Option<String>,
+    },
+    /// List todos
+    List {
+        /// Only show todos with this status
+        #[arg(long)]
+        status: Option<String>,
+        /// Check running todos against the backend first
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Start the scrape for a todo
+    Run {
+        id: String,
+        /// Use this criteria preset instead of the todo's own
+        #[arg(long)]
+        preset: Option<String>,
+        #[command(flatten)]
+        wait: WaitArgs,
+    },
+    /// Queue every pending todo and start as many as the concurrency limit
+    /// allows; the rest stay queued for the next run-all or the desktop app
+    RunAll,
+    /// Queue a failed or cancelled todo for another attempt
+    Retry { id: String },
+    /// Delete a todo
+    Delete { id: String },
+}
+
+#[derive(Args)]
+struct ScrapeArgs {
+    target: String,
+    #[arg(long, default_value_t = 50)]
+    count: u32,
+    #[arg(long, default_value_t = 10)]
+    batch_size: u32,
+    #[arg(long, default_value_t = 1)]
+    bio_pages: u32,
+    /// Criteria preset id
+    #[arg(long)]
+    preset: Option<String>,
+    /// Raw criteria text; overrides --preset
+    #[arg(long)]
+    criteria: Option<String>,
+    /// If the target already has a scrape running: attach, cancel or force
+    #[arg(long)]
+    on_conflict: Option<ConflictResolution>,
+    #[command(flatten)]
+    wait: WaitArgs,
+}
+
+#[derive(Args)]
+struct WaitArgs {
+    /// Block until the scrape has finished
+    #[arg(long)]
+    wait: bool,
+    /// Seconds between status checks while waiting
+    #[arg(long, default_value_t = 15)]
+    interval: u64,
+    /// Give up waiting after this many seconds
+    #[arg(long)]
+    timeout: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum CriteriaCommand {
+    /// List saved presets
+    List,
+    /// Save a new preset and print its id
+    Create {
+        name: String,
+        /// Criteria text
+        #[arg(long, conflicts_with = "file", required_unless_present = "file")]
+        text: Option<String>,
+        /// Read the criteria text from a file
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Make a preset the active one, or go back to the backend default with --none
+    Activate {
+        #[arg(required_unless_present = "none")]
+        id: Option<String>,
+        #[arg(long, conflicts_with = "id")]
+        none: bool,
+    },
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    /// Results of one operation
+    #[arg(long, group = "source")]
+    operation: Option<String>,
+    /// Results of one todo
+    #[arg(long, group = "source")]
+    todo: Option<String>,
+    /// Every stored profile seen while scraping this target
+    #[arg(long, group = "source")]
+    target: Option<String>,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+    format: ExportFormat,
+    /// Write to this file instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// What a subcommand prints: `json` with --json, `text` otherwise.
+struct Output {
+    json: Value,
+    text: String,
+    failed: bool,
+}
+
+impl Output {
+    fn new(json: Value, text: impl Into<String>) -> Self {
+        Self { json, text: text.into(), failed: false }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let (state, _mock) = if cli.demo {
+        let demo = MockBackend::start(MockScenario::QueuedThenCompleted { polls: 0 })
+            .and_then(|mock| Ok((mock.demo_state()?, mock)));
+        match demo {
+            Ok((demo_state, mock)) => (AppStateManager::with_state(demo_state), Some(mock)),
+            Err(e) => {
+                eprintln!("❌ Demo mode unavailable: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        match AppStateManager::new(WhenLocked::Fail) {
+            Ok(state) => (state, None),
+            Err(e) => {
+                eprintln!("❌ Failed to load app state: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+
+    match run(cli.command, &state).await {
+        Ok(output) => {
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&output.json).unwrap_or_default());
+            } else if !output.text.is_empty() {
+                println!("{}", output.text);
+            }
+            if output.failed { ExitCode::from(2) } else { ExitCode::SUCCESS }
+        }
+        Err(e) => {
+            if cli.json {
+                println!("{}", json!({ "error": e }));
+            }
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, state: &AppStateManager) -> Result<Output, InstagrapError> {
+    match command {
+        Command::Todo { command } => run_todo_command(command, state).await,
+        Command::Scrape(args) => scrape(args, state).await,
+        Command::Status { operation } => {
+            let status = operations::check_operation_status(state, &operation).await?;
+            let text = describe_status(&operation, &status);
+            Ok(Output::new(status, text))
+        }
+        Command::Timeline { operation } => {
+            let app_state = state.lock()?;
+            let operation = app_state.get_operation(&operation).ok_or_else(|| InstagrapError::not_found("Operation"))?;
+            let mut lines = vec![format!("{}  {} (last activity {})", operation.operation_id, operation.status, operation.last_activity_at())];
+            lines.extend(operation.events.iter().map(describe_event));
+            Ok(Output::new(timeline_json(&operation), lines.join("\n")))
+        }
+        Command::Reconcile => {
+            let summary = reconcile::reconcile(state).await?;
+            let text = summary.message();
+            Ok(Output::new(json!(summary), text))
+        }
+        Command::Cancel { operation, delete_artifacts } => {
+            let response = operations::cancel_operation(state, &operation, delete_artifacts).await?;
+            let text = format!("{}  {} ({} results kept)", operation, response["status"].as_str().unwrap_or("cancelled"), response["count"]);
+            Ok(Output::new(response, text))
+        }
+        Command::Criteria { command } => run_criteria_command(command, state),
+        Command::Export(args) => export_results(args, state),
+    }
+}
+
+// ===== Todos =====
+
+async fn run_todo_command(command: TodoCommand, state: &AppStateManager) -> Result<Output, InstagrapError> {
+    match command {
+        TodoCommand::Add { target, count, bio_agents, batch_size, preset } => {
+            let req = CreateTodoRequest {
+                target_account: target,
+                target_count: count,
+                bio_agents,
+                batch_size,
+                criteria_preset_id: preset,
+            };
+            let mut app_state = state.lock()?;
+            let id = app_state.create_todo(req)?;
+            let todo = app_state.get_todo(&id).as_ref().map(todo_json).unwrap_or(Value::Null);
+            Ok(Output::new(todo, id))
+        }
+        TodoCommand::List { status, refresh } => {
+            let status: Option<TodoStatus> = status.map(|s| s.parse()).transpose().map_err(InstagrapError::Validation)?;
+            if refresh {
+                let running: Vec<String> = state
+                    .lock()?
+                    .get_todos()
+                    .into_iter()
+                    .filter(|t| t.status == TodoStatus::Running)
+                    .map(|t| t.id)
+                    .collect();
+                for id in running {
+                    if let Err(e) = todos::refresh_todo(state, &id).await {
+                        eprintln!("⚠️ Could not refresh todo {}: {}", id, String::from(e));
+                    }
+                }
+            }
+            let todos: Vec<_> = state
+                .lock()?
+                .get_todos()
+                .into_iter()
+                .filter(|t| status.is_none_or(|s| t.status == s))
+                .collect();
+            let text = todos
+                .iter()
+                .map(|t| {
+                    let results = t.results.as_ref().map(|r| format!(" ({} results)", r.len())).unwrap_or_default();
+                    format!("{}  {:<10} {} x{}{}", t.id, t.status, t.target_account, t.target_count, results)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output::new(json!({ "todos": todos.iter().map(todo_json).collect::<Vec<_>>() }), text))
+        }
+        TodoCommand::Run { id, preset, wait } => {
+            let response = todos::run_todo(state, &id, preset).await?;
+            if !wait.wait {
+                let text = describe_status(&id, &response);
+                return Ok(Output::new(response, text));
+            }
+            let deadline = wait.timeout.map(|t| Instant::now() + Duration::from_secs(t));
+            loop {
+                let status = todos::refresh_todo(state, &id).await?;
+                if status.is_terminal() {
+                    break;
+                }
+                sleep_or_timeout(wait.interval, deadline).await?;
+            }
+            let app_state = state.lock()?;
+            let todo = app_state.get_todo(&id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            let mut output = Output::new(todo_json(&todo), format!("Todo {} {}", todo.id, todo.status));
+            output.failed = matches!(todo.status, TodoStatus::Failed | TodoStatus::Lost);
+            Ok(output)
+        }
+        TodoCommand::RunAll => {
+            let queue = queue::run_all(state).await?;
+            let started = queue["started"].as_array().map_or(0, |s| s.len());
+            let failed = queue["failed"].as_array().map_or(0, |f| f.len());
+            let waiting = queue["queued"].as_array().map_or(0, |q| q.len());
+            let text = format!("Started {} todos, {} failed to start, {} waiting in the queue", started, failed, waiting);
+            Ok(Output { failed: failed > 0, ..Output::new(queue, text) })
+        }
+        TodoCommand::Retry { id } => {
+            state.lock()?.retry_todo(&id)?;
+            queue::fill_slots(state).await?;
+            let app_state = state.lock()?;
+            let todo = app_state.get_todo(&id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            let text = format!("Todo {} {} (attempt {})", todo.id, todo.status, todo.attempts.len() + 1);
+            Ok(Output::new(todo_json(&todo), text))
+        }
+        TodoCommand::Delete { id } => {
+            let mut app_state = state.lock()?;
+            if app_state.get_todo(&id).is_none() {
+                return Err(InstagrapError::not_found("Todo"));
+            }
+            app_state.delete_todo(&id)?;
+            Ok(Output::new(json!({ "deleted": id }), format!("Deleted todo {}", id)))
+        }
+    }
+}
+
+// ===== Scrapes =====
+
+async fn scrape(args: ScrapeArgs, state: &AppStateManager) -> Result<Output, InstagrapError> {
+    let req = ScrapeRequest {
+        target: args.target,
+        target_yes: args.count,
+        batch_size: args.batch_size,
+        num_bio_pages: args.bio_pages,
+        criteria_preset_id: args.preset,
+        criteria_text: args.criteria,
+        on_conflict: args.on_conflict,
+    };
+    let response = operations::start_remote_scrape(state, req).await?;
+    let operation_id = response.get("operation").and_then(|o| o.as_str()).map(|s| s.to_string());
+
+    let (Some(operation_id), true) = (operation_id, args.wait.wait) else {
+        let text = describe_status(response.get("operation").and_then(|o| o.as_str()).unwrap_or("-"), &response);
+        let failed = response.get("status").and_then(|s| s.as_str()) == Some("failed");
+        return Ok(Output { failed, ..Output::new(response, text) });
+    };
+
+    let deadline = args.wait.timeout.map(|t| Instant::now() + Duration::from_secs(t));
+    loop {
+        let status = operations::check_operation_status(state, &operation_id).await?;
+        match status.get("status").and_then(|s| s.as_str()) {
+            Some("completed") | Some("failed") | Some("cancelled") | Some("lost") => break,
+            _ => sleep_or_timeout(args.wait.interval, deadline).await?,
+        }
+    }
+    let app_state = state.lock()?;
+    let operation = app_state.get_operation(&operation_id).ok_or_else(|| InstagrapError::not_found("Operation"))?;
+    let failed = matches!(operation.status.as_str(), "failed" | "lost");
+    let text = describe_status(&operation_id, &operation_json(&operation));
+    Ok(Output { failed, ..Output::new(operation_json(&operation), text) })
+}
+
+async fn sleep_or_timeout(interval: u64, deadline: Option<Instant>) -> Result<(), InstagrapError> {
+    let interval = Duration::from_secs(interval.max(1));
+    if deadline.is_some_and(|d| Instant::now() + interval > d) {
+        return Err(InstagrapError::Timeout("waiting for the scrape to finish".to_string()));
+    }
+    tokio::time::sleep(interval).await;
+    Ok(())
+}
+
+fn describe_status(id: &str, response: &Value) -> String {
+    let status = response.get("status").and_then(|s| s.as_str()).unwrap_or("unknown");
+    let mut text = format!("{}  {}", id, status);
+    if let Some(results) = response.get("results").and_then(|r| r.as_array()) {
+        text.push_str(&format!(" ({} results)", results.len()));
+    }
+    if let Some(message) = response
+        .get("error_message")
+        .or_else(|| response.get("message"))
+        .and_then(|m| m.as_str())
+    {
+        text.push_str(&format!(": {}", message));
+    }
+    text
+}
+
+fn describe_event(event: &OperationEvent) -> String {
+    let mut text = format!("{}  {}", event.at, event.kind.as_str());
+    if let Some(status) = &event.status {
+        text.push_str(&format!(" {}", status));
+    }
+    if let Some(progress) = &event.progress {
+        let counters = [("scanned", progress.scanned), ("classified", progress.classified), ("yes", progress.yes_count)];
+        for (name, value) in counters.iter().filter_map(|(name, value)| Some((name, (*value)?))) {
+            text.push_str(&format!(" {}={}", name, value));
+        }
+    }
+    if event.polls > 1 {
+        text.push_str(&format!(" (x{})", event.polls));
+    }
+    if let Some(message) = &event.message {
+        text.push_str(&format!(": {}", message));
+    }
+    text
+}
+
+// ===== Criteria presets =====
+
+fn run_criteria_command(command: CriteriaCommand, state: &AppStateManager) -> Result<Output, InstagrapError> {
+    let mut app_state = state.lock()?;
+    match command {
+        CriteriaCommand::List => {
+            let text = app_state
+                .saved_criteria
+                .iter()
+                .map(|p| {
+                    let active = if app_state.active_criteria_id.as_deref() == Some(p.id.as_str()) { "*" } else { " " };
+                    format!("{} {}  {}", active, p.id, p.name)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(Output::new(app_state.saved_criteria_json(), text))
+        }
+        CriteriaCommand::Create { name, text, file } => {
+            let criteria = match (text, file) {
+                (Some(text), _) => text,
+                (None, Some(path)) => fs::read_to_string(&path)
+                    .map_err(|e| InstagrapError::Validation(format!("Failed to read {}: {}", path.display(), e)))?,
+                (None, None) => return Err(InstagrapError::validation("Pass --text or --file")),
+            };
+            let id = app_state.add_criteria_preset(name, criteria)?;
+            Ok(Output::new(json!({ "id": id }), id))
+        }
+        CriteriaCommand::Activate { id, none: _ } => {
+            app_state.set_active_criteria(id.clone())?;
+            let text = match &id {
+                Some(id) => format!("Active criteria preset: {}", id),
+                None => "Using the backend default criteria".to_string(),
+            };
+            Ok(Output::new(json!({ "active_id": id }), text))
+        }
+    }
+}
+
+// ===== Export =====
+
+fn export_results(args: ExportArgs, state: &AppStateManager) -> Result<Output, InstagrapError> {
+    let app_state = state.lock()?;
+    let profiles: Vec<ProfileResult> = if let Some(id) = &args.operation {
+        let operation = app_state.get_operation(id).ok_or_else(|| InstagrapError::not_found("Operation"))?;
+        operation.results.unwrap_or_default()
+    } else if let Some(id) = &args.todo {
+        let todo = app_state.get_todo(id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+        todo.results.unwrap_or_default()
+    } else {
+        let target = args.target.as_deref();
+        let (stored, _) = app_state.store.search_profiles(None, target, u32::MAX, 0)?;
+        stored.into_iter().map(|p| p.data).collect()
+    };
+
+    let content = match args.format {
+        ExportFormat::Csv => export::to_csv(&profiles),
+        ExportFormat::Json => export::to_json(&profiles)?,
+    };
+    match args.output {
+        Some(path) => {
+            fs::write(&path, content).map_err(|e| InstagrapError::Persistence(format!("Failed to write {}: {}", path.display(), e)))?;
+            let text = format!("Exported {} profiles to {}", profiles.len(), path.display());
+            Ok(Output::new(json!({ "path": path, "count": profiles.len() }), text))
+        }
+        // With --json the profiles are printed as JSON whatever --format says
+        None => Ok(Output::new(serde_json::from_str(&export::to_json(&profiles)?).unwrap_or(Value::Null), content)),
+    }
+}
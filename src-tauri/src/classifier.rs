@@ -0,0 +1,192 @@
+// Client for the bio classifier service.
+//
+// Commands go through the `ClassifierClient` trait so they can run against
+// `FakeClassifierClient` without network access.
+
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::InstagrapError;
+
+pub const CLASSIFY_API_BASE: &str = "https://bio-classifier-672383441505.us-central1.run.app";
+
+/// Answer of `GET /criteria`: only the user-editable part of the prompt.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClassificationCriteria {
+    #[serde(default)]
+    pub criteria: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Answer of the prompt update and reset endpoints: the full composed prompt.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClassificationPrompt {
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+pub trait ClassifierClient: Send + Sync {
+    fn criteria(&self) -> impl Future<Output = Result<ClassificationCriteria, InstagrapError>> + Send;
+
+    /// Replaces the criteria; the service composes the header and footer.
+    fn update_prompt(&self, criteria: &str) -> impl Future<Output = Result<ClassificationPrompt, InstagrapError>> + Send;
+
+    fn reset_prompt(&self) -> impl Future<Output = Result<ClassificationPrompt, InstagrapError>> + Send;
+}
+
+// ===== Commands =====
+
+pub async fn get_classification_criteria(client: &impl ClassifierClient) -> Result<ClassificationCriteria, InstagrapError> {
+    eprintln!("🔍 [DEBUG] Getting classification criteria");
+    client.criteria().await
+}
+
+pub async fn update_classification_prompt(client: &impl ClassifierClient, criteria: &str) -> Result<ClassificationPrompt, InstagrapError> {
+    let criteria = criteria.trim();
+    if criteria.is_empty() {
+        return Err(InstagrapError::validation("Criteria must not be empty; reset the prompt to use the default"));
+    }
+    eprintln!("🔍 [DEBUG] Updating classification criteria...");
+    let prompt = client.update_prompt(criteria).await?;
+    eprintln!("✅ [DEBUG] Classification criteria updated successfully");
+    Ok(prompt)
+}
+
+pub async fn reset_classification_prompt(client: &impl ClassifierClient) -> Result<ClassificationPrompt, InstagrapError> {
+    eprintln!("🔍 [DEBUG] Resetting classification prompt to default...");
+    let prompt = client.reset_prompt().await?;
+    eprintln!("✅ [DEBUG] Classification prompt reset successfully");
+    Ok(prompt)
+}
+
+// ===== HTTP client =====
+
+#[derive(Clone, Debug)]
+pub struct HttpClassifierClient {
+    http: reqwest::Client,
+    base_url: Url,
+}
+
+impl Default for HttpClassifierClient {
+    fn default() -> Self {
+        Self::new(CLASSIFY_API_BASE).expect("default classifier URL is valid")
+    }
+}
+
+impl HttpClassifierClient {
+    pub fn new(base_url: &str) -> Result<Self, InstagrapError> {
+        let base_url = Url::parse(base_url)
+            .map_err(|e| InstagrapError::Validation(format!("Invalid classifier URL {}: {}", base_url, e)))?;
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("InstaGrap/1.0")
+            .build()
+            .map_err(|e| InstagrapError::Network(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(Self { http, base_url })
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    fn url(&self, path: &str) -> Url {
+        let mut url = self.base_url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().extend(path.split('/'));
+        }
+        url
+    }
+}
+
+impl ClassifierClient for HttpClassifierClient {
+    async fn criteria(&self) -> Result<ClassificationCriteria, InstagrapError> {
+        send(self.http.get(self.url("criteria"))).await
+    }
+
+    async fn update_prompt(&self, criteria: &str) -> Result<ClassificationPrompt, InstagrapError> {
+        send(self.http.put(self.url("prompt")).json(&json!({ "criteria": criteria }))).await
+    }
+
+    async fn reset_prompt(&self) -> Result<ClassificationPrompt, InstagrapError> {
+        // Empty JSON body to satisfy Content-Length requirement
+        send(self.http.post(self.url("prompt/reset")).json(&json!({}))).await
+    }
+}
+
+async fn send<T: serde::de::DeserializeOwned>(request: RequestBuilder) -> Result<T, InstagrapError> {
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(InstagrapError::from_response(response).await);
+    }
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| InstagrapError::InvalidResponse(format!("Failed to parse JSON response: {}", e)))
+}
+
+// ===== In-memory fake =====
+
+/// Stand-in for the classifier service that keeps the criteria in memory and
+/// composes prompts like the real one. `fail_next` makes the next call fail.
+pub struct FakeClassifierClient {
+    default_criteria: String,
+    inner: Mutex<FakeClassifier>,
+}
+
+struct FakeClassifier {
+    criteria: String,
+    fail_next: Option<InstagrapError>,
+}
+
+impl FakeClassifierClient {
+    pub fn new(default_criteria: &str) -> Self {
+        Self {
+            default_criteria: default_criteria.to_string(),
+            inner: Mutex::new(FakeClassifier { criteria: default_criteria.to_string(), fail_next: None }),
+        }
+    }
+
+    pub fn fail_next(&self, error: InstagrapError) {
+        self.inner.lock().unwrap().fail_next = Some(error);
+    }
+
+    /// The prompt the fake service would send to the model for `criteria`.
+    pub fn compose_prompt(criteria: &str) -> String {
+        format!("Classify the Instagram bio below.\n\nCriteria:\n{}\n\nAnswer with a verdict and a reason.", criteria)
+    }
+
+    fn call<T>(&self, f: impl FnOnce(&mut FakeClassifier, &str) -> T) -> Result<T, InstagrapError> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(error) = inner.fail_next.take() {
+            return Err(error);
+        }
+        Ok(f(&mut inner, &self.default_criteria))
+    }
+}
+
+impl ClassifierClient for FakeClassifierClient {
+    async fn criteria(&self) -> Result<ClassificationCriteria, InstagrapError> {
+        self.call(|inner, _| ClassificationCriteria { criteria: inner.criteria.clone(), extra: Map::new() })
+    }
+
+    async fn update_prompt(&self, criteria: &str) -> Result<ClassificationPrompt, InstagrapError> {
+        self.call(|inner, _| {
+            inner.criteria = criteria.to_string();
+            ClassificationPrompt { prompt: Self::compose_prompt(criteria), extra: Map::new() }
+        })
+    }
+
+    async fn reset_prompt(&self) -> Result<ClassificationPrompt, InstagrapError> {
+        self.call(|inner, default| {
+            inner.criteria = default.to_string();
+            ClassificationPrompt { prompt: Self::compose_prompt(default), extra: Map::new() }
+        })
+    }
+}
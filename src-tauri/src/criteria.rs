@@ -0,0 +1,122 @@
+// Saved classifier criteria presets and per-job criteria selection.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::error::InstagrapError;
+use crate::state::AppState;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedCriteriaPreset {
+    pub id: String,
+    pub name: String,
+    pub criteria: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl AppState {
+    pub fn saved_criteria_json(&self) -> serde_json::Value {
+        let presets: Vec<serde_json::Value> = self.saved_criteria.iter().map(|p| json!({
+            "id": p.id,
+            "name": p.name,
+            "criteria": p.criteria,
+            "created_at": p.created_at,
+            "updated_at": p.updated_at,
+        })).collect();
+        json!({
+            "presets": presets,
+            "active_id": self.active_criteria_id,
+        })
+    }
+
+    pub fn get_criteria_preset(&self, id: &str) -> Option<&SavedCriteriaPreset> {
+        self.saved_criteria.iter().find(|p| p.id == id)
+    }
+
+    pub fn add_criteria_preset(&mut self, name: String, criteria: String) -> Result<String, InstagrapError> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        let preset = SavedCriteriaPreset { id: id.clone(), name, criteria, created_at: now.clone(), updated_at: now };
+        self.store.save_criteria_preset(&preset)?;
+        self.saved_criteria.push(preset);
+        Ok(id)
+    }
+
+    pub fn rename_criteria_preset(&mut self, id: &str, new_name: String) -> Result<(), InstagrapError> {
+        if let Some(p) = self.saved_criteria.iter_mut().find(|p| p.id == id) {
+            p.name = new_name;
+            p.updated_at = Utc::now().to_rfc3339();
+            self.store.save_criteria_preset(p)?;
+        }
+        Ok(())
+    }
+
+    pub fn update_criteria_preset(&mut self, id: &str, new_criteria: String) -> Result<(), InstagrapError> {
+        if let Some(p) = self.saved_criteria.iter_mut().find(|p| p.id == id) {
+            p.criteria = new_criteria;
+            p.updated_at = Utc::now().to_rfc3339();
+            self.store.save_criteria_preset(p)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_criteria_preset(&mut self, id: &str) -> Result<(), InstagrapError> {
+        self.store.delete_criteria_preset(id)?;
+        self.saved_criteria.retain(|p| p.id != id);
+        if let Some(active_id) = &self.active_criteria_id {
+            if active_id == id {
+                self.active_criteria_id = None; // fall back to default
+                self.store.save_active_criteria_id(None)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_active_criteria(&mut self, id: Option<String>) -> Result<(), InstagrapError> {
+        // Validate id if provided
+        if let Some(ref some_id) = id {
+            if !self.saved_criteria.iter().any(|p| &p.id == some_id) {
+                return Err(InstagrapError::not_found("Criteria preset"));
+            }
+        }
+        self.store.save_active_criteria_id(id.as_deref())?;
+        self.active_criteria_id = id;
+        Ok(())
+    }
+
+    /// Selects a preset for a todo, caching its human-friendly name.
+    pub fn set_todo_criteria_preset(&mut self, todo_id: &str, preset_id: Option<String>) -> Result<(), InstagrapError> {
+        let name = preset_id
+            .as_deref()
+            .and_then(|pid| self.get_criteria_preset(pid))
+            .map(|p| p.name.clone());
+        self.set_todo_preset(todo_id, preset_id, name)
+    }
+
+    /// Resolves the criteria a single scrape job should use without touching the
+    /// backend-global prompt. Returns `(preset_id, criteria_text)`; both `None`
+    /// means the backend default.
+    pub fn criteria_for_job(&self, criteria_preset_id: Option<String>, criteria_text: Option<String>) -> (Option<String>, Option<String>) {
+        eprintln!("🔧 [DEBUG] Per-job criteria selection: preset_id={:?} text_present={}", criteria_preset_id, criteria_text.as_ref().map(|s| !s.is_empty()).unwrap_or(false));
+        if let Some(text) = criteria_text {
+            // If raw text is provided, use it regardless of preset id
+            (criteria_preset_id, Some(text))
+        } else if let Some(ref provided_id) = criteria_preset_id {
+            // Resolve provided preset id to criteria text; if not found, fall back to default
+            if let Some(preset) = self.get_criteria_preset(provided_id) {
+                eprintln!("🔧 [DEBUG] Using provided preset id: id={} name={} ({} chars)", preset.id, preset.name, preset.criteria.len());
+                (Some(preset.id.clone()), Some(preset.criteria.clone()))
+            } else {
+                eprintln!("⚠️ [DEBUG] Provided preset id not found; proceeding with backend default criteria");
+                (None, None)
+            }
+        } else {
+            // No preset id (null) -> use backend default (no custom criteria)
+            eprintln!("ℹ️ [DEBUG] No preset id provided; proceeding with backend default criteria");
+            (None, None)
+        }
+    }
+}
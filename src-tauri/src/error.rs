@@ -0,0 +1,149 @@
+// Error type shared by the library, the Tauri commands, the CLI and the
+// automation API.
+//
+// It serializes to a stable `{code, message, retryable, details}` object so the
+// frontend can branch on `code` and offer a retry only when it makes sense,
+// instead of showing whatever text the failing call produced.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::json;
+use std::fmt;
+
+use crate::operations::ScrapeConflict;
+use crate::status::TransitionError;
+
+#[derive(Debug, Clone)]
+pub enum InstagrapError {
+    /// The request never got a response (DNS, refused or dropped connection)
+    Network(String),
+    Timeout(String),
+    /// The backend answered with a non-success status
+    BackendHttp { status: u16, body: String },
+    /// The backend answered but the body was not what we expected
+    InvalidResponse(String),
+    /// The saved Instagram session is missing or no longer accepted
+    LoginExpired,
+    NotFound(String),
+    Validation(String),
+    InvalidTransition(TransitionError),
+    /// A scrape of the same target is already running
+    Conflict(Box<ScrapeConflict>),
+    Persistence(String),
+    Browser(String),
+    /// A bug or broken invariant, e.g. a poisoned lock
+    Internal(String),
+}
+
+impl InstagrapError {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound(what.into())
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+
+    pub fn persistence(message: impl Into<String>) -> Self {
+        Self::Persistence(message.into())
+    }
+
+    /// Reads the body of a failed response into a `BackendHttp` error.
+    pub async fn from_response(response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        Self::BackendHttp { status, body }
+    }
+
+    /// Stable machine-readable code, part of the frontend contract.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Network(_) => "network",
+            Self::Timeout(_) => "timeout",
+            Self::BackendHttp { .. } => "backend_http",
+            Self::InvalidResponse(_) => "invalid_response",
+            Self::LoginExpired => "login_expired",
+            Self::NotFound(_) => "not_found",
+            Self::Validation(_) => "validation",
+            Self::InvalidTransition(_) => "invalid_transition",
+            Self::Conflict(_) => "conflict",
+            Self::Persistence(_) => "persistence",
+            Self::Browser(_) => "browser",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// Whether repeating the same call unchanged may succeed.
+    pub fn retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Timeout(_) => true,
+            Self::BackendHttp { status, .. } => *status == 408 || *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            Self::BackendHttp { status, body } => json!({ "status": status, "body": body }),
+            Self::InvalidTransition(e) => json!(e),
+            Self::Conflict(conflict) => json!(conflict),
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for InstagrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(message) => write!(f, "Network error: {}", message),
+            Self::Timeout(message) => write!(f, "Request timed out: {}", message),
+            Self::BackendHttp { status, body } => write!(f, "HTTP error {}: {}", status, body),
+            Self::InvalidResponse(message) => write!(f, "Invalid response from backend: {}", message),
+            Self::LoginExpired => write!(f, "Instagram login expired, please log in again"),
+            Self::NotFound(what) => write!(f, "{} not found", what),
+            Self::Validation(message) | Self::Persistence(message) | Self::Internal(message) => write!(f, "{}", message),
+            Self::InvalidTransition(e) => write!(f, "{}", e),
+            Self::Conflict(conflict) => write!(f, "{}", conflict),
+            Self::Browser(message) => write!(f, "Browser error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for InstagrapError {}
+
+impl Serialize for InstagrapError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("InstagrapError", 4)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("retryable", &self.retryable())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
+
+impl From<TransitionError> for InstagrapError {
+    fn from(e: TransitionError) -> Self {
+        Self::InvalidTransition(e)
+    }
+}
+
+impl From<reqwest::Error> for InstagrapError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout(e.to_string())
+        } else if e.is_decode() {
+            Self::InvalidResponse(e.to_string())
+        } else if let Some(status) = e.status() {
+            Self::BackendHttp { status: status.as_u16(), body: e.to_string() }
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+}
+
+// For callers that only print the error (CLI text output, logs)
+impl From<InstagrapError> for String {
+    fn from(e: InstagrapError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,57 @@
+// CSV and JSON rendering of scrape results for downloads and scripts.
+
+use crate::error::InstagrapError;
+use crate::results::ProfileResult;
+
+/// Column headers of the CSV export; the first two match the desktop download.
+pub const CSV_HEADERS: [&str; 11] = [
+    "Username",
+    "Profile URL",
+    "Full Name",
+    "Bio",
+    "Followers",
+    "Following",
+    "Private",
+    "Verified",
+    "Verdict",
+    "Reason",
+    "Confidence",
+];
+
+pub fn to_csv(profiles: &[ProfileResult]) -> String {
+    let mut lines = vec![CSV_HEADERS.join(",")];
+    for p in profiles {
+        let row = [
+            p.username.clone(),
+            p.profile_url(),
+            p.full_name.clone().unwrap_or_default(),
+            p.bio.clone().unwrap_or_default(),
+            optional(p.follower_count),
+            optional(p.following_count),
+            optional(p.is_private),
+            optional(p.is_verified),
+            p.verdict.clone().unwrap_or_default(),
+            p.reason.clone().unwrap_or_default(),
+            optional(p.confidence),
+        ];
+        lines.push(row.iter().map(|v| escape(v)).collect::<Vec<_>>().join(","));
+    }
+    lines.join("\n")
+}
+
+pub fn to_json(profiles: &[ProfileResult]) -> Result<String, InstagrapError> {
+    serde_json::to_string_pretty(profiles).map_err(|e| InstagrapError::Internal(format!("Failed to serialize results: {}", e)))
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+// Quote values containing commas, quotes or newlines, doubling inner quotes
+fn escape(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,365 @@
+// Scrape jobs: the one record behind todos and operations.
+//
+// A job is a scrape of one target with fixed parameters. Todos are the jobs
+// the user planned (`listed`); a scrape started straight from the scrape form
+// is an unlisted job. Every run of a job is a `JobRun` holding its operation
+// id, status, results and timeline, so a todo's attempts and the operation
+// history are two views of the same runs instead of copies kept in sync.
+// `Todo`, `ScrapingOperation` and `TodoAttempt` are built from jobs on the way
+// out and never stored. A job's status follows its current run: when the run
+// ends, the job takes its outcome.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::InstagrapError;
+use crate::operations::ScrapingOperation;
+use crate::results::ProfileResult;
+use crate::retry::TodoAttempt;
+use crate::state::AppState;
+use crate::status::{OperationStatus, TodoStatus};
+use crate::timeline::{EventKind, OperationEvent};
+use crate::todos::Todo;
+
+// Not known for scrapes recorded from an operation alone
+const DEFAULT_BIO_AGENTS: u32 = 1;
+const DEFAULT_BATCH_SIZE: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Job {
+    pub id: String,
+    /// Shown in the todo list; false for scrapes started directly
+    pub listed: bool,
+    pub target_account: String,
+    pub target_count: u32,
+    pub bio_agents: u32,
+    pub batch_size: u32,
+    pub status: TodoStatus,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+    pub manually_completed: bool,
+    pub criteria_preset_id: Option<String>,
+    pub criteria_preset_name: Option<String>,
+    // When a failed job is retried automatically (RFC 3339), see crate::retry
+    pub next_retry_at: Option<String>,
+    /// The run the job's status and results come from; None while pending
+    pub current_attempt: Option<u32>,
+    /// Every run, oldest first
+    pub runs: Vec<JobRun>,
+}
+
+/// One run of a job, with the remote operation it started.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobRun {
+    /// 1-based, in run order
+    pub attempt: u32,
+    /// None until the backend accepted the scrape
+    pub operation_id: Option<String>,
+    pub exec_id: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub status: OperationStatus,
+    pub results: Option<Vec<ProfileResult>>,
+    pub error_message: Option<String>,
+    /// The run failed on a transient backend error and may be retried automatically
+    pub retryable: bool,
+    // What happened to the run so far, see crate::timeline
+    pub events: Vec<OperationEvent>,
+}
+
+impl JobRun {
+    fn new(attempt: u32, now: DateTime<Utc>) -> Self {
+        Self {
+            attempt,
+            operation_id: None,
+            exec_id: None,
+            started_at: now.to_rfc3339(),
+            finished_at: None,
+            status: OperationStatus::Running,
+            results: None,
+            error_message: None,
+            retryable: false,
+            events: Vec::new(),
+        }
+    }
+
+    /// Moves the run to `status` with the given results and error. A run that
+    /// ends gets its finish time and closing event.
+    pub(crate) fn apply(
+        &mut self,
+        status: OperationStatus,
+        results: Option<Vec<ProfileResult>>,
+        error_message: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Result<(), InstagrapError> {
+        let previous = self.status;
+        self.status = self.status.transition(status)?;
+        self.results = results;
+        self.error_message = error_message;
+        if status.is_terminal() && status != previous {
+            self.finished_at = Some(now.to_rfc3339());
+            if let Some(kind) = EventKind::finished(status) {
+                self.push_event(kind, self.finished_message(), now);
+            }
+        }
+        Ok(())
+    }
+
+    // Shown on the event that closes a run
+    pub(crate) fn finished_message(&self) -> Option<String> {
+        match (&self.error_message, &self.results) {
+            (Some(error), _) => Some(error.clone()),
+            (None, Some(results)) => Some(format!("{} results", results.len())),
+            (None, None) => None,
+        }
+    }
+
+    /// The key results and events of this run are stored under.
+    pub fn key(&self, job_id: &str) -> String {
+        format!("{}:{}", job_id, self.attempt)
+    }
+}
+
+impl Job {
+    /// A new pending todo job.
+    pub(crate) fn new(target_account: String, target_count: u32, bio_agents: u32, batch_size: u32) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            listed: true,
+            target_account,
+            target_count,
+            bio_agents,
+            batch_size,
+            status: TodoStatus::Pending,
+            created_at: Utc::now().to_rfc3339(),
+            completed_at: None,
+            manually_completed: false,
+            criteria_preset_id: None,
+            criteria_preset_name: None,
+            next_retry_at: None,
+            current_attempt: None,
+            runs: Vec::new(),
+        }
+    }
+
+    /// An unlisted job for a scrape that only exists as an operation.
+    pub(crate) fn from_operation(operation: ScrapingOperation) -> Self {
+        let status = TodoStatus::from(operation.status);
+        let finished_at = status
+            .is_terminal()
+            .then(|| operation.events.last().map_or(operation.started_at.clone(), |e| e.at.clone()));
+        Self {
+            id: operation.operation_id.clone(),
+            listed: false,
+            status,
+            created_at: operation.started_at.clone(),
+            completed_at: finished_at.clone(),
+            current_attempt: Some(1),
+            runs: vec![JobRun {
+                attempt: 1,
+                operation_id: Some(operation.operation_id),
+                exec_id: operation.exec_id,
+                started_at: operation.started_at,
+                finished_at,
+                status: operation.status,
+                results: operation.results,
+                error_message: operation.error_message,
+                retryable: false,
+                events: operation.events,
+            }],
+            ..Self::new(operation.target_account, operation.target_count, DEFAULT_BIO_AGENTS, DEFAULT_BATCH_SIZE)
+        }
+    }
+
+    /// A listed job from a todo of a legacy JSON document, which has no
+    /// attempt history. A todo that ran gets one run, with status and results
+    /// from its operation when that is in `operations`.
+    pub(crate) fn from_todo(todo: Todo, operations: &[ScrapingOperation]) -> Self {
+        let op = todo.operation_id.as_deref().and_then(|id| operations.iter().find(|op| op.operation_id == id));
+        let started = todo.status != TodoStatus::Pending && (todo.started_at.is_some() || op.is_some());
+        let run = started.then(|| {
+            let started_at = todo.started_at.clone().or(op.map(|op| op.started_at.clone())).unwrap_or(todo.created_at.clone());
+            let status = match (todo.status, op) {
+                (TodoStatus::Running, Some(op)) => op.status,
+                (status, _) => status.into(),
+            };
+            JobRun {
+                attempt: 1,
+                operation_id: todo.operation_id.clone(),
+                exec_id: todo.exec_id.clone().or(op.and_then(|op| op.exec_id.clone())),
+                finished_at: status.is_terminal().then(|| todo.completed_at.clone().unwrap_or(started_at.clone())),
+                started_at,
+                status,
+                results: op.and_then(|op| op.results.clone()).or(todo.results.clone()),
+                error_message: todo.error_message.clone().or(op.and_then(|op| op.error_message.clone())),
+                retryable: false,
+                events: op.map(|op| op.events.clone()).unwrap_or_default(),
+            }
+        });
+
+        let mut job = Self {
+            id: todo.id,
+            status: todo.status,
+            created_at: todo.created_at,
+            completed_at: todo.completed_at,
+            manually_completed: todo.manually_completed,
+            criteria_preset_id: todo.criteria_preset_id,
+            criteria_preset_name: todo.criteria_preset_name,
+            current_attempt: run.as_ref().map(|run| run.attempt),
+            runs: run.into_iter().collect(),
+            ..Self::new(todo.target_account, todo.target_count, todo.bio_agents, todo.batch_size)
+        };
+        job.follow_current_run();
+        job
+    }
+
+    pub fn current_run(&self) -> Option<&JobRun> {
+        let attempt = self.current_attempt?;
+        self.runs.iter().find(|run| run.attempt == attempt)
+    }
+
+    pub(crate) fn current_run_mut(&mut self) -> Option<&mut JobRun> {
+        let attempt = self.current_attempt?;
+        self.runs.iter_mut().find(|run| run.attempt == attempt)
+    }
+
+    pub fn run_for_operation(&self, operation_id: &str) -> Option<&JobRun> {
+        self.runs.iter().find(|run| run.operation_id.as_deref() == Some(operation_id))
+    }
+
+    /// Adds a new running run and makes it the current one.
+    pub(crate) fn start_run(&mut self, now: DateTime<Utc>) -> &mut JobRun {
+        let attempt = self.runs.last().map_or(1, |run| run.attempt + 1);
+        self.runs.push(JobRun::new(attempt, now));
+        self.current_attempt = Some(attempt);
+        self.runs.last_mut().expect("run was just added")
+    }
+
+    /// Once the current run has ended, a running job takes its outcome.
+    pub(crate) fn follow_current_run(&mut self) {
+        let Some(run) = self.current_run() else { return };
+        let (status, finished_at) = (TodoStatus::from(run.status), run.finished_at.clone());
+        if self.status == TodoStatus::Running && status.is_terminal() {
+            self.status = status;
+            self.completed_at = self.completed_at.clone().or(finished_at);
+        }
+    }
+
+    /// Finished runs, as the todo's attempt history.
+    pub fn attempts(&self) -> Vec<TodoAttempt> {
+        self.runs
+            .iter()
+            .filter(|run| run.status.is_terminal())
+            .map(|run| TodoAttempt {
+                attempt: run.attempt,
+                started_at: Some(run.started_at.clone()),
+                finished_at: run.finished_at.clone().unwrap_or(run.started_at.clone()),
+                status: run.status.into(),
+                operation_id: run.operation_id.clone(),
+                exec_id: run.exec_id.clone(),
+                error_message: run.error_message.clone(),
+                retryable: run.retryable,
+            })
+            .collect()
+    }
+
+    /// The job as a todo: its parameters plus what its current run holds.
+    pub fn todo(&self) -> Todo {
+        let run = self.current_run();
+        Todo {
+            id: self.id.clone(),
+            target_account: self.target_account.clone(),
+            target_count: self.target_count,
+            bio_agents: self.bio_agents,
+            batch_size: self.batch_size,
+            status: self.status,
+            created_at: self.created_at.clone(),
+            started_at: run.map(|run| run.started_at.clone()),
+            completed_at: self.completed_at.clone(),
+            operation_id: run.and_then(|run| run.operation_id.clone()),
+            exec_id: run.and_then(|run| run.exec_id.clone()),
+            results: run.and_then(|run| run.results.clone()),
+            error_message: run.and_then(|run| run.error_message.clone()),
+            manually_completed: self.manually_completed,
+            criteria_preset_id: self.criteria_preset_id.clone(),
+            criteria_preset_name: self.criteria_preset_name.clone(),
+            attempts: self.attempts(),
+            next_retry_at: self.next_retry_at.clone(),
+        }
+    }
+
+    /// A run as an operation; runs the backend never accepted have none.
+    pub fn operation(&self, run: &JobRun) -> Option<ScrapingOperation> {
+        Some(ScrapingOperation {
+            operation_id: run.operation_id.clone()?,
+            target_account: self.target_account.clone(),
+            target_count: self.target_count,
+            started_at: run.started_at.clone(),
+            status: run.status,
+            results: run.results.clone(),
+            error_message: run.error_message.clone(),
+            exec_id: run.exec_id.clone(),
+            events: run.events.clone(),
+        })
+    }
+}
+
+impl AppState {
+    pub fn get_job(&self, job_id: &str) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == job_id)
+    }
+
+    /// The job and run behind an operation.
+    pub fn job_for_operation(&self, operation_id: &str) -> Option<(&Job, &JobRun)> {
+        self.jobs.iter().find_map(|job| job.run_for_operation(operation_id).map(|run| (job, run)))
+    }
+
+    /// Applies `change` to a job and saves it; `with_results` also rewrites
+    /// the results of its newest run. Returns what `change` returned.
+    pub(crate) fn update_job<T>(
+        &mut self,
+        job_id: &str,
+        with_results: bool,
+        change: impl FnOnce(&mut Job) -> Result<T, InstagrapError>,
+    ) -> Result<T, InstagrapError> {
+        let Some(index) = self.jobs.iter().position(|job| job.id == job_id) else {
+            return Err(InstagrapError::not_found("Job"));
+        };
+        // Changes are made on a copy so a failed one leaves memory and store alike
+        let mut job = self.jobs[index].clone();
+        let value = change(&mut job)?;
+        job.follow_current_run();
+        self.store.save_job(&job, with_results)?;
+        self.jobs[index] = job;
+        Ok(value)
+    }
+
+    /// Like `update_job`, for the job that ran `operation_id`; `change` gets
+    /// that run.
+    pub(crate) fn update_run<T>(
+        &mut self,
+        operation_id: &str,
+        with_results: bool,
+        change: impl FnOnce(&mut JobRun) -> Result<T, InstagrapError>,
+    ) -> Result<T, InstagrapError> {
+        let Some((job, _)) = self.job_for_operation(operation_id) else {
+            return Err(InstagrapError::not_found("Operation"));
+        };
+        let job_id = job.id.clone();
+        self.update_job(&job_id, with_results, |job| {
+            let run = job
+                .runs
+                .iter_mut()
+                .find(|run| run.operation_id.as_deref() == Some(operation_id))
+                .ok_or_else(|| InstagrapError::not_found("Operation"))?;
+            change(run)
+        })
+    }
+
+    pub(crate) fn add_job(&mut self, job: Job) -> Result<(), InstagrapError> {
+        self.store.save_job(&job, true)?;
+        self.jobs.push(job);
+        Ok(())
+    }
+}
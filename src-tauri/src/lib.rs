@@ -1,14 +1,29 @@
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
+// Core of the app without any Tauri dependency: the persisted state model, the
+// backend and classifier clients, criteria presets and todo logic. The desktop
+// binary exposes these as Tauri commands; anything else can use them directly.
+// Diagnostics are written to stderr so stdout stays free for callers' output.
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+pub mod api;
+pub mod automation;
+pub mod backup;
+pub mod classifier;
+pub mod criteria;
+pub mod error;
+pub mod export;
+pub mod jobs;
+pub mod login;
+pub mod migrations;
+pub mod mock;
+pub mod operations;
+pub mod poller;
+pub mod queue;
+pub mod reconcile;
+pub mod results;
+pub mod retry;
+pub mod schedule;
+pub mod settings;
+pub mod state;
+pub mod status;
+pub mod store;
+pub mod timeline;
+pub mod todos;
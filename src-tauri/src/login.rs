@@ -0,0 +1,180 @@
+// Interactive Instagram login in a real Chrome window and upload of the
+// resulting browser storage state to Google Cloud Storage.
+
+use chrono::Utc;
+use google_cloud_storage::client::{Client, ClientConfig};
+use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
+use playwright::api::{BrowserChannel, Playwright};
+use std::fs;
+
+use crate::error::InstagrapError;
+
+/// Opens Chrome for a manual Instagram login, then uploads the captured
+/// storage state to `bucket_url` (gs://bucket[/object]) in GCP project
+/// `project_id` and returns its URI.
+pub async fn login_and_upload(bucket_url: String, project_id: String) -> Result<String, InstagrapError> {
+    match login_and_upload_inner(bucket_url, project_id).await {
+        Ok(uri) => {
+            eprintln!("🚀 DONE – state uploaded to {}", uri);
+            Ok(uri)
+        }
+        Err(e) => {
+            eprintln!("❌ ERROR: {e}");
+            Err(e)
+        }
+    }
+}
+
+async fn login_and_upload_inner(bucket_url: String, project_id: String) -> Result<String, InstagrapError> {
+    // Avoid bundled browser downloads on unsupported mac15-arm64; we’ll launch system Chrome.
+    std::env::set_var("PLAYWRIGHT_SKIP_BROWSER_DOWNLOAD", "1");
+    std::env::set_var("PLAYWRIGHT_SKIP_BROWSER_VALIDATION", "1");
+
+    let pw = Playwright::initialize().await.map_err(|e| InstagrapError::Browser(e.to_string()))?;
+    // `prepare` is a synchronous method, so `.await` is removed.
+    pw.prepare().map_err(|e| InstagrapError::Browser(e.to_string()))?;
+
+    // Launch system Chrome (change to .executable(...) if you prefer an explicit path).
+    
+    let profile_dir = dirs::config_dir().unwrap().join("insta_profile");
+    let context = pw.chromium()
+    .persistent_context_launcher(&profile_dir)
+    .args(&[
+        "--disable-extensions".to_string(),
+        "--mute-audio".to_string(),
+        "--window-size=1280,900".to_string()
+    ])
+    .headless(false)
+    .channel(BrowserChannel::Chrome)
+    .timeout(120_000.0)
+    .launch()
+    .await
+    .map_err(|e| InstagrapError::Browser(e.to_string()))?;
+
+    let page = context.new_page().await.map_err(|e| InstagrapError::Browser(e.to_string()))?;
+
+
+    // FIX: Use `add_init_script` BEFORE navigating. This is more robust and ensures
+    // the script is injected on any page load, preventing race conditions.
+    page.add_init_script(
+        r#"
+        {
+            const createButton = () => {
+                if (document.getElementById('pw-done-btn')) return;
+                const btn = document.createElement('button');
+                btn.id = 'pw-done-btn';
+                btn.textContent = '✓ DONE – send cookies';
+                btn.style = 'position:fixed;top:1rem;right:1rem;z-index:999999;padding:.6rem 1.2rem;background:#38bdf8;color:#fff;border:none;border-radius:.5rem;cursor:pointer;';
+                btn.onclick = () => {
+                    btn.setAttribute('data-clicked','1');
+                    btn.textContent = '✓ Sending…';
+                    window.__pw_done = true;
+                };
+                document.body.appendChild(btn);
+            };
+
+            if (document.readyState === 'loading') {
+                window.addEventListener('DOMContentLoaded', createButton);
+            } else {
+                createButton();
+            }
+        }
+        "#,
+    )
+    .await
+    .map_err(|e| InstagrapError::Browser(e.to_string()))?;
+
+    page.goto_builder("https://www.instagram.com/")
+    .timeout(300000.0)
+    .goto()
+    .await
+    .map_err(|e| InstagrapError::Browser(e.to_string()))?;
+        
+    // Wait until the user clicks the button (flag becomes true in page context).
+    page
+    .wait_for_selector_builder("#pw-done-btn[data-clicked='1']")
+    .timeout(300000.0)
+    .wait_for_selector()
+    .await
+    .map_err(|e| InstagrapError::Browser(format!("wait cancelled or timed-out: {e}")))?;
+    eprintln!("✅ User clicked DONE");
+
+    // --- proceed to capture storage state & upload ---
+    let config_dir = dirs::config_dir().ok_or_else(|| InstagrapError::persistence("Could not find config directory"))?;
+    // Ensure the directory exists
+    fs::create_dir_all(&config_dir).map_err(|e| InstagrapError::Persistence(e.to_string()))?;
+    let state_path = config_dir.join("insta_state.json");
+
+    let state = context
+        .storage_state()
+        .await
+        .map_err(|e| InstagrapError::Browser(e.to_string()))?;
+    let state_json = serde_json::to_string(&state).map_err(|e| InstagrapError::Persistence(e.to_string()))?;
+    fs::write(&state_path, state_json).map_err(|e| InstagrapError::Persistence(e.to_string()))?;
+
+    let browser_opt = context.browser().map_err(|e| InstagrapError::Browser(e.to_string()))?;
+    if let Some(browser) = browser_opt {
+        browser.close().await.ok();
+    }
+    eprintln!("✅ State file written to {}", state_path.display());
+
+    let config = ClientConfig {
+        project_id: Some(project_id),
+        ..Default::default()
+    };
+    let config = config
+    .with_auth()
+    .await
+    .map_err(|e| InstagrapError::Network(format!("Google Cloud authentication failed: {}", e)))?;
+
+    eprintln!("🔍 [DEBUG] Google Cloud authentication configured successfully");
+
+    let client = Client::new(config);
+    
+    let trimmed_url = bucket_url
+        .strip_prefix("gs://")
+        .ok_or_else(|| InstagrapError::validation("Invalid GCS URL: must start with gs://"))?;
+
+    let (bucket, object) = match trimmed_url.split_once('/') {
+            Some((b, o)) if !o.is_empty() => (b.to_string(), o.to_string()),
+            Some((b, _)) => {
+                let default_name = format!(
+                    "insta_state_{}.json",
+                    Utc::now().format("%Y%m%dT%H%M%SZ")
+                );
+                (b.to_string(), default_name)
+            }
+            None => {
+                let default_name = format!(
+                    "insta_state_{}.json",
+                    Utc::now().format("%Y%m%dT%H%M%SZ")
+                );
+                (trimmed_url.to_string(), default_name)
+            }
+    };
+    
+    let data = fs::read(&state_path).map_err(|e| InstagrapError::Persistence(e.to_string()))?;
+
+    let upload_request = UploadObjectRequest {
+        bucket: bucket.to_string(),
+        ..Default::default()
+    };
+    let media = Media {
+        name: object.to_string().into(),
+        content_type: "application/json".into(),
+        content_length: Some(data.len() as u64),
+    };
+
+    let upload_type = UploadType::Simple(media);
+
+    client
+        .upload_object(&upload_request, data, &upload_type)
+        .await
+        .map_err(|e| InstagrapError::Network(format!("Upload to {} failed: {}", bucket, e)))?;
+    
+    // Construct the full GCS URI with the object name
+    let full_gcs_uri = format!("gs://{}/{}", bucket, object);
+    eprintln!("✅ Upload successful to {}", full_gcs_uri);
+    fs::remove_file(state_path).ok();
+    Ok(full_gcs_uri)
+}
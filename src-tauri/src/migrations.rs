@@ -0,0 +1,120 @@
+// Schema migrations for persisted AppState JSON documents.
+//
+// Documents written before versioning have no `schema_version` and are treated
+// as version 0. Each step upgrades a document by exactly one version and only
+// fills in what is missing, so partially upgraded files are handled too. The
+// app has stored its state in SQLite since, so a migrated document is only
+// read once, as a `LegacyState`, and imported into the store.
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::criteria::SavedCriteriaPreset;
+use crate::error::InstagrapError;
+use crate::operations::ScrapingOperation;
+use crate::todos::Todo;
+
+/// Newest shape of the JSON documents; the last one written had this version.
+pub const CURRENT_SCHEMA_VERSION: u32 = 3;
+
+/// A JSON state document after `migrate`, with todos and operations still
+/// stored separately.
+#[derive(Deserialize)]
+pub struct LegacyState {
+    pub scraping_operations: Vec<ScrapingOperation>,
+    pub last_login_gcs_uri: Option<String>,
+    pub todos: Vec<Todo>,
+    pub saved_criteria: Vec<SavedCriteriaPreset>,
+    pub active_criteria_id: Option<String>,
+}
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), InstagrapError>;
+
+// MIGRATIONS[n] upgrades a document from version n to n + 1
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [
+    v0_add_todos_and_exec_ids,
+    v1_add_manual_completion,
+    v2_add_criteria_presets,
+];
+
+/// Reads the schema version of a document; unversioned documents are version 0.
+pub fn schema_version(doc: &Value) -> Result<u32, InstagrapError> {
+    match doc.get("schema_version") {
+        None | Some(Value::Null) => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .map(|v| v as u32)
+            .ok_or_else(|| InstagrapError::Persistence(format!("Invalid schema_version: {}", v))),
+    }
+}
+
+/// Upgrades a document step by step to `CURRENT_SCHEMA_VERSION`.
+pub fn migrate(mut doc: Value) -> Result<Value, InstagrapError> {
+    let from = schema_version(&doc)?;
+    if from > CURRENT_SCHEMA_VERSION {
+        return Err(InstagrapError::Persistence(format!(
+            "State was written by a newer version of the app (schema {}, supported up to {})",
+            from, CURRENT_SCHEMA_VERSION
+        )));
+    }
+
+    let root = doc
+        .as_object_mut()
+        .ok_or_else(|| InstagrapError::Persistence("State document is not a JSON object".to_string()))?;
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        step(root).map_err(|e| InstagrapError::Persistence(format!("Migration from schema {} failed: {}", version, e)))?;
+        root.insert("schema_version".to_string(), json!(version + 1));
+    }
+    Ok(doc)
+}
+
+// v0 -> v1: todos were introduced and operations gained the GCS `exec_id`.
+fn v0_add_todos_and_exec_ids(root: &mut Map<String, Value>) -> Result<(), InstagrapError> {
+    root.entry("scraping_operations").or_insert_with(|| json!([]));
+    root.entry("last_login_gcs_uri").or_insert(Value::Null);
+    root.entry("todos").or_insert_with(|| json!([]));
+    for op in array_items(root, "scraping_operations")? {
+        op.entry("exec_id").or_insert(Value::Null);
+        op.entry("results").or_insert(Value::Null);
+        op.entry("error_message").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+// v1 -> v2: todos gained the manual completion override and their own `exec_id`.
+fn v1_add_manual_completion(root: &mut Map<String, Value>) -> Result<(), InstagrapError> {
+    for todo in array_items(root, "todos")? {
+        todo.entry("manually_completed").or_insert(json!(false));
+        todo.entry("exec_id").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+// v2 -> v3: saved classifier criteria presets, selectable per todo.
+fn v2_add_criteria_presets(root: &mut Map<String, Value>) -> Result<(), InstagrapError> {
+    root.entry("saved_criteria").or_insert_with(|| json!([]));
+    root.entry("active_criteria_id").or_insert(Value::Null);
+    for todo in array_items(root, "todos")? {
+        todo.entry("criteria_preset_id").or_insert(Value::Null);
+        todo.entry("criteria_preset_name").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// Mutable access to the objects of an array field; a missing field counts as empty.
+fn array_items<'a>(
+    root: &'a mut Map<String, Value>,
+    key: &str,
+) -> Result<Vec<&'a mut Map<String, Value>>, InstagrapError> {
+    match root.get_mut(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter_mut()
+            .map(|item| {
+                item.as_object_mut()
+                    .ok_or_else(|| InstagrapError::Persistence(format!("`{}` contains a non-object entry", key)))
+            })
+            .collect(),
+        Some(_) => Err(InstagrapError::Persistence(format!("`{}` is not an array", key))),
+    }
+}
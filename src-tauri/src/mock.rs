@@ -0,0 +1,320 @@
+// In-process mock of the instagram-api and bio-classifier services on
+// tiny_http, for demo mode and integration tests.
+//
+// Both services share one port, so an environment can point its API and
+// classifier URLs at the same mock. How scrapes behave is decided by the
+// `MockScenario` in effect when the scrape starts.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tiny_http::{Method, Request, Server};
+
+use crate::automation::{json_response, parse_query};
+use crate::error::InstagrapError;
+use crate::settings::{Environment, Settings};
+use crate::state::AppState;
+
+pub const DEFAULT_MOCK_CRITERIA: &str = "Bio mentions running, marathons or triathlons";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MockScenario {
+    /// Queued; `polls` status checks answer running, the next one completed
+    QueuedThenCompleted { polls: u32 },
+    /// Queued; the first status check answers failed with `message`
+    Failure { message: String },
+    /// Queued; `polls` status checks answer running, the next one failed with `message`
+    FailsAfter { polls: u32, message: String },
+    /// Like `QueuedThenCompleted { polls: 0 }`, but every answer takes `delay`
+    Slow { delay: Duration },
+    /// `/scrape-status` answers 404; only `/legacy-scrape-status` knows the job
+    LegacyFallback,
+    /// `/scrape-status` answers 500 and `/legacy-scrape-status` does not know the job
+    StatusError,
+}
+
+impl Default for MockScenario {
+    fn default() -> Self {
+        Self::QueuedThenCompleted { polls: 1 }
+    }
+}
+
+struct MockJob {
+    target: String,
+    target_yes: u32,
+    scenario: MockScenario,
+    polls: u32,
+    cancelled: bool,
+}
+
+struct MockState {
+    scenario: MockScenario,
+    logged_in: bool,
+    criteria: String,
+    // Keyed by both exec_id and operation id
+    jobs: HashMap<String, Arc<Mutex<MockJob>>>,
+    next_id: u32,
+    requests: Vec<String>,
+}
+
+pub struct MockBackend {
+    server: Arc<Server>,
+    thread: Option<JoinHandle<()>>,
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockBackend {
+    /// Starts the mock on a free localhost port.
+    pub fn start(scenario: MockScenario) -> Result<Self, InstagrapError> {
+        let server = Server::http("127.0.0.1:0")
+            .map_err(|e| InstagrapError::Network(format!("Failed to start mock backend: {}", e)))?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .ok_or_else(|| InstagrapError::Internal("Mock backend is not listening on a TCP port".to_string()))?;
+        let server = Arc::new(server);
+        let state = Arc::new(Mutex::new(MockState {
+            scenario,
+            logged_in: true,
+            criteria: DEFAULT_MOCK_CRITERIA.to_string(),
+            jobs: HashMap::new(),
+            next_id: 1,
+            requests: Vec::new(),
+        }));
+
+        let (serving, shared) = (server.clone(), state.clone());
+        let thread = thread::Builder::new()
+            .name("mock-backend".into())
+            .spawn(move || {
+                for request in serving.incoming_requests() {
+                    // One thread per request so a slow answer doesn't hold up the rest
+                    let shared = shared.clone();
+                    thread::spawn(move || handle(&shared, request));
+                }
+            })
+            .map_err(|e| InstagrapError::Internal(format!("Failed to spawn mock backend thread: {}", e)))?;
+
+        eprintln!("🧪 Mock backend listening on http://127.0.0.1:{}", port);
+        Ok(Self { server, thread: Some(thread), port, state })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// Scenario for scrapes started from now on.
+    pub fn set_scenario(&self, scenario: MockScenario) {
+        lock(&self.state).scenario = scenario;
+    }
+
+    pub fn set_logged_in(&self, logged_in: bool) {
+        lock(&self.state).logged_in = logged_in;
+    }
+
+    /// Requests received so far, as "METHOD /path".
+    pub fn requests(&self) -> Vec<String> {
+        lock(&self.state).requests.clone()
+    }
+
+    /// An environment pointing both services at this mock.
+    pub fn environment(&self) -> Environment {
+        Environment {
+            name: "mock".into(),
+            api_base: self.url(),
+            classifier_base: self.url(),
+            gcp_project: "instagrap-demo".into(),
+            default_bucket: "gs://instagrap-demo/".into(),
+        }
+    }
+
+    /// In-memory app state wired to this mock, for demo mode and tests. Nothing
+    /// is written to the real state database.
+    pub fn demo_state(&self) -> Result<AppState, InstagrapError> {
+        let mut state = AppState::new();
+        let mut settings = Settings::default();
+        settings.environments.push(self.environment());
+        settings.active_environment = "mock".into();
+        state.set_settings(settings)?;
+        Ok(state)
+    }
+}
+
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panicking handler must not take the whole mock down with it
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn handle(state: &Mutex<MockState>, mut request: Request) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+    let query = parse_query(query);
+    let param = |key: &str| query.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone()).unwrap_or_default();
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body).ok();
+    let body: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+
+    let delay = {
+        let mut state = lock(state);
+        state.requests.push(format!("{} {}", method, path));
+        match state.scenario {
+            MockScenario::Slow { delay } => Some(delay),
+            _ => None,
+        }
+    };
+    if let Some(delay) = delay {
+        thread::sleep(delay);
+    }
+
+    let (status, reply) = match (&method, path) {
+        (Method::Get, "/login-status") => {
+            if lock(state).logged_in {
+                (200, json!({ "status": "ok", "ok": true }))
+            } else {
+                (200, json!({ "status": "none", "ok": false }))
+            }
+        }
+        (Method::Post, "/register-state") => match body.get("gcs_uri").and_then(|u| u.as_str()) {
+            Some(uri) if uri.starts_with("gs://") => {
+                lock(state).logged_in = true;
+                (200, json!({ "ok": true }))
+            }
+            _ => (400, json!({ "error": "gcs_uri must start with gs://" })),
+        },
+        (Method::Post, "/remote-scrape") => start_scrape(state, &body),
+        (Method::Get, "/scrape-status") => scrape_status(state, &param("exec_id"), false),
+        (Method::Get, "/legacy-scrape-status") => scrape_status(state, &param("operation"), true),
+        (Method::Post, "/cancel-scrape") => cancel_scrape(state, &body),
+        (Method::Delete, "/scrape-artifacts") => (200, json!({ "deleted": param("exec_id") })),
+        (Method::Get, "/criteria") => (200, json!({ "criteria": lock(state).criteria })),
+        (Method::Put, "/prompt") => match body.get("criteria").and_then(|c| c.as_str()) {
+            Some(criteria) => {
+                lock(state).criteria = criteria.to_string();
+                (200, json!({ "prompt": compose_prompt(criteria) }))
+            }
+            None => (400, json!({ "error": "criteria is required" })),
+        },
+        (Method::Post, "/prompt/reset") => {
+            lock(state).criteria = DEFAULT_MOCK_CRITERIA.to_string();
+            (200, json!({ "prompt": compose_prompt(DEFAULT_MOCK_CRITERIA) }))
+        }
+        _ => (404, json!({ "error": "not found" })),
+    };
+    request.respond(json_response(status, &reply)).ok();
+}
+
+fn start_scrape(state: &Mutex<MockState>, body: &Value) -> (u16, Value) {
+    let Some(target) = body.get("target").and_then(|t| t.as_str()).filter(|t| !t.is_empty()) else {
+        return (400, json!({ "error": "target is required" }));
+    };
+    let mut state = lock(state);
+    let id = state.next_id;
+    state.next_id += 1;
+    let (operation, exec_id) = (format!("mock-op-{}", id), format!("mock-exec-{}", id));
+    let job = Arc::new(Mutex::new(MockJob {
+        target: target.to_string(),
+        target_yes: body.get("target_yes").and_then(|n| n.as_u64()).unwrap_or(10) as u32,
+        scenario: state.scenario.clone(),
+        polls: 0,
+        cancelled: false,
+    }));
+    state.jobs.insert(operation.clone(), job.clone());
+    state.jobs.insert(exec_id.clone(), job);
+    (200, json!({ "status": "queued", "operation": operation, "exec_id": exec_id }))
+}
+
+fn scrape_status(state: &Mutex<MockState>, id: &str, legacy: bool) -> (u16, Value) {
+    let Some(job) = lock(state).jobs.get(id).cloned() else {
+        return (404, json!({ "error": "unknown scrape" }));
+    };
+    let mut job = lock(&job);
+    if job.scenario == MockScenario::LegacyFallback && !legacy {
+        return (404, json!({ "error": "no status object for this exec_id" }));
+    }
+    if job.scenario == MockScenario::StatusError {
+        return if legacy { (404, json!({ "error": "unknown scrape" })) } else { (500, json!({ "error": "status store unavailable" })) };
+    }
+    if job.cancelled {
+        return (200, json!({ "status": "cancelled", "results": partial_profiles(&job) }));
+    }
+    job.polls += 1;
+    match &job.scenario {
+        MockScenario::Failure { message } => (200, json!({ "status": "failed", "message": message })),
+        MockScenario::FailsAfter { polls, message } if job.polls > *polls => (200, json!({ "status": "failed", "message": message })),
+        MockScenario::QueuedThenCompleted { polls } | MockScenario::FailsAfter { polls, .. } if job.polls <= *polls => {
+            let progress = json!({
+                "polls": job.polls,
+                "scanned": job.polls * 25,
+                "classified": job.polls * 20,
+                "yes_count": job.polls.min(job.target_yes),
+            });
+            // Each check hands over the profile found since the previous one
+            let batch: Vec<Value> = (job.polls <= job.target_yes).then(|| fake_profile(&job.target, job.polls)).into_iter().collect();
+            (200, json!({
+                "status": "running",
+                "message": format!("Scanning followers of {}", job.target),
+                "progress": progress,
+                "results": batch,
+            }))
+        }
+        _ => (200, json!({ "status": "completed", "results": fake_profiles(&job.target, job.target_yes) })),
+    }
+}
+
+// Jobs stop with one profile per status check answered so far. A job whose
+// next check would complete it has already finished and stays completed.
+fn cancel_scrape(state: &Mutex<MockState>, body: &Value) -> (u16, Value) {
+    let id = ["exec_id", "operation"].iter().find_map(|key| body.get(*key).and_then(|v| v.as_str()));
+    let Some(job) = id.and_then(|id| lock(state).jobs.get(id).cloned()) else {
+        return (404, json!({ "error": "unknown scrape" }));
+    };
+    let mut job = lock(&job);
+    let finished = match &job.scenario {
+        MockScenario::QueuedThenCompleted { polls } => job.polls >= *polls,
+        MockScenario::Failure { .. } | MockScenario::FailsAfter { .. } => false,
+        MockScenario::Slow { .. } | MockScenario::LegacyFallback | MockScenario::StatusError => true,
+    };
+    if finished && !job.cancelled {
+        return (200, json!({ "status": "completed", "results": fake_profiles(&job.target, job.target_yes) }));
+    }
+    job.cancelled = true;
+    (200, json!({ "status": "cancelled", "results": partial_profiles(&job) }))
+}
+
+fn partial_profiles(job: &MockJob) -> Vec<Value> {
+    fake_profiles(&job.target, job.polls.min(job.target_yes))
+}
+
+fn fake_profiles(target: &str, count: u32) -> Vec<Value> {
+    (1..=count).map(|i| fake_profile(target, i)).collect()
+}
+
+fn fake_profile(target: &str, i: u32) -> Value {
+    let target = target.trim_start_matches('@');
+    json!({
+        "username": format!("{}_fan_{}", target, i),
+        "full_name": format!("{} Fan {}", target, i),
+        "bio": "Marathon runner and coffee lover",
+        "follower_count": 100 * i,
+        "verdict": "yes",
+        "reason": "Bio mentions running",
+        "confidence": 0.9,
+    })
+}
+
+fn compose_prompt(criteria: &str) -> String {
+    format!("You classify Instagram bios.\n\nCriteria:\n{}\n\nAnswer yes or no with a reason.", criteria)
+}
@@ -0,0 +1,598 @@
+// Remote scraping operations: starting them, tracking their status and the
+// per-target run history. An operation is a view of one job run, see
+// crate::jobs.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::api::{RemoteScrapeRequest, ScrapeStatus};
+use crate::results::{merge_results, validated_results, ProfileResult};
+use crate::error::InstagrapError;
+use crate::jobs::Job;
+use crate::state::{AppState, AppStateManager};
+use crate::status::{OperationStatus, TodoStatus};
+use crate::timeline::{EventKind, OperationEvent};
+
+// Structure to store scraping operation data
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScrapingOperation {
+    pub operation_id: String,
+    pub target_account: String,
+    pub target_count: u32,
+    pub started_at: String,
+    pub status: OperationStatus,
+    pub results: Option<Vec<ProfileResult>>,
+    pub error_message: Option<String>,
+    pub exec_id: Option<String>,
+    // What happened to the run so far, see crate::timeline
+    #[serde(default)]
+    pub events: Vec<OperationEvent>,
+}
+
+/// Parameters of a remote scrape as sent by the frontend.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScrapeRequest {
+    pub target: String,
+    pub target_yes: u32,
+    pub batch_size: u32,
+    pub num_bio_pages: u32,
+    // Optional: allow overriding criteria per job via preset id or raw criteria
+    pub criteria_preset_id: Option<String>,
+    pub criteria_text: Option<String>,
+    // What to do if the target already has a scrape running; refused when unset
+    #[serde(default)]
+    pub on_conflict: Option<ConflictResolution>,
+}
+
+/// How to start a scrape for a target that already has one running.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictResolution {
+    /// Follow the running scrape instead of starting another
+    Attach,
+    /// Cancel the running scrape, then start the new one
+    Cancel,
+    /// Start a second scrape next to it, with its own history entry
+    Force,
+}
+
+impl ConflictResolution {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Attach => "attach",
+            Self::Cancel => "cancel",
+            Self::Force => "force",
+        }
+    }
+}
+
+impl std::str::FromStr for ConflictResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "attach" => Ok(Self::Attach),
+            "cancel" => Ok(Self::Cancel),
+            "force" => Ok(Self::Force),
+            other => Err(format!("Unknown conflict resolution: {}", other)),
+        }
+    }
+}
+
+/// Returned when a scrape is started for a target that already has one running.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ScrapeConflict {
+    pub target_account: String,
+    /// The running scrape; None while it waits for the backend to accept it
+    pub operation_id: Option<String>,
+    pub exec_id: Option<String>,
+    /// The todo the running scrape belongs to, if any
+    pub todo_id: Option<String>,
+    pub started_at: String,
+    /// What the caller may pass as `on_conflict`
+    pub options: Vec<ConflictResolution>,
+}
+
+impl fmt::Display for ScrapeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A scrape of {} is already running", self.target_account)?;
+        if let Some(operation_id) = &self.operation_id {
+            write!(f, " (operation {})", operation_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A direct scrape waiting for the backend to accept it. It holds its target
+/// until the operation is recorded, as a running todo does.
+#[derive(Clone, Debug)]
+pub struct StartingScrape {
+    pub target_account: String,
+    pub started_at: String,
+}
+
+pub fn operation_json(op: &ScrapingOperation) -> serde_json::Value {
+    json!({
+        "operation_id": op.operation_id,
+        "target_account": op.target_account,
+        "target_count": op.target_count,
+        "started_at": op.started_at,
+        "status": op.status,
+        "results": op.results,
+        "error_message": op.error_message,
+        "exec_id": op.exec_id,
+        "progress": op.latest_progress()
+    })
+}
+
+// Instagram handles are case-insensitive and often typed with a leading '@'
+pub fn same_target(a: &str, b: &str) -> bool {
+    a.trim().trim_start_matches('@').eq_ignore_ascii_case(b.trim().trim_start_matches('@'))
+}
+
+impl AppState {
+    /// Records a scrape that only exists as an operation, e.g. one started
+    /// from the scrape form, as an unlisted job with that single run.
+    pub fn add_operation(&mut self, operation: ScrapingOperation) -> Result<(), InstagrapError> {
+        let mut job = Job::from_operation(operation);
+        if let Some(run) = job.runs.last_mut().filter(|run| run.events.is_empty()) {
+            match EventKind::finished(run.status) {
+                Some(kind) => run.push_event(kind, run.finished_message(), Utc::now()),
+                None => run.push_event(EventKind::Queued, None, Utc::now()),
+            }
+        }
+        // Runs accumulate per target; only prune_target_history removes old ones
+        self.add_job(job)
+    }
+
+    pub fn update_operation(&mut self, operation_id: &str, status: OperationStatus, results: Option<Vec<ProfileResult>>, error_message: Option<String>) -> Result<(), InstagrapError> {
+        self.update_run(operation_id, true, |run| run.apply(status, results, error_message, Utc::now()))
+    }
+
+    pub fn get_operation(&self, operation_id: &str) -> Option<ScrapingOperation> {
+        let (job, run) = self.job_for_operation(operation_id)?;
+        job.operation(run)
+    }
+
+    /// Every run the backend accepted, oldest first.
+    pub fn operations(&self) -> Vec<ScrapingOperation> {
+        let mut operations: Vec<ScrapingOperation> = self
+            .jobs
+            .iter()
+            .flat_map(|job| job.runs.iter().filter_map(|run| job.operation(run)))
+            .collect();
+        operations.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        operations
+    }
+
+    /// All runs for a target account, newest first.
+    pub fn get_target_history(&self, target_account: &str) -> Vec<ScrapingOperation> {
+        self.operations()
+            .into_iter()
+            .rev()
+            .filter(|op| same_target(&op.target_account, target_account))
+            .collect()
+    }
+
+    /// Deletes all but the newest `keep` finished runs of a target. Running
+    /// operations and the runs todos are showing are never pruned. Returns
+    /// the number of removed runs.
+    pub fn prune_target_history(&mut self, target_account: &str, keep: usize) -> Result<usize, InstagrapError> {
+        let doomed: Vec<String> = self
+            .get_target_history(target_account)
+            .into_iter()
+            .filter(|op| op.status.is_terminal())
+            .skip(keep)
+            .map(|op| op.operation_id)
+            .filter(|operation_id| !self.is_todo_run(operation_id))
+            .collect();
+        for operation_id in &doomed {
+            self.remove_operation(operation_id)?;
+        }
+        Ok(doomed.len())
+    }
+
+    /// Operations still in flight, stalled ones included.
+    pub fn get_running_operations(&self) -> Vec<ScrapingOperation> {
+        self.operations().into_iter().filter(|op| op.status.is_active()).collect()
+    }
+
+    /// Removes finished operations from the history, except the runs todos
+    /// are showing.
+    pub fn clear_completed_operations(&mut self) -> Result<(), InstagrapError> {
+        let finished: Vec<String> = self
+            .operations()
+            .into_iter()
+            .filter(|op| !op.status.is_active() && !self.is_todo_run(&op.operation_id))
+            .map(|op| op.operation_id)
+            .collect();
+        for operation_id in &finished {
+            self.remove_operation(operation_id)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a run from the history. A direct scrape goes with its only
+    /// run; the run a todo is showing can only go with the todo.
+    pub fn remove_operation(&mut self, operation_id: &str) -> Result<(), InstagrapError> {
+        let Some((job, run)) = self.job_for_operation(operation_id) else {
+            return Ok(());
+        };
+        if !job.listed {
+            self.store.delete_job(job)?;
+            let job_id = job.id.clone();
+            self.jobs.retain(|job| job.id != job_id);
+            return Ok(());
+        }
+        if self.is_todo_run(operation_id) {
+            return Err(InstagrapError::Validation(format!("Operation {} is the current run of todo {}; delete the todo instead", operation_id, job.id)));
+        }
+        self.store.delete_job_run(&job.id, run)?;
+        let (job_id, attempt) = (job.id.clone(), run.attempt);
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == job_id) {
+            job.runs.retain(|run| run.attempt != attempt);
+        }
+        Ok(())
+    }
+
+    // The run a todo currently shows
+    fn is_todo_run(&self, operation_id: &str) -> bool {
+        self.job_for_operation(operation_id)
+            .is_some_and(|(job, run)| job.listed && job.current_attempt == Some(run.attempt))
+    }
+
+    /// The operation's results so far with `batch` merged in. None while
+    /// neither has any.
+    pub fn results_with(&self, operation_id: &str, batch: Option<Vec<ProfileResult>>) -> Option<Vec<ProfileResult>> {
+        let existing = self.job_for_operation(operation_id).and_then(|(_, run)| run.results.clone());
+        match (existing, batch) {
+            (Some(mut results), Some(batch)) => {
+                merge_results(&mut results, batch);
+                Some(results)
+            }
+            (existing, batch) => existing.or(batch.map(|batch| {
+                let mut results = Vec::new();
+                merge_results(&mut results, batch);
+                results
+            })),
+        }
+    }
+
+    /// Merges a batch of partial results into a running operation. Returns
+    /// how many profiles were new.
+    pub fn merge_partial_results(&mut self, operation_id: &str, batch: Vec<ProfileResult>) -> Result<usize, InstagrapError> {
+        self.update_run(operation_id, true, |run| {
+            let added = merge_results(run.results.get_or_insert_with(Vec::new), batch);
+            if added > 0 {
+                eprintln!("📥 Operation {} has {} results so far (+{})", operation_id, run.results.as_ref().map_or(0, Vec::len), added);
+            }
+            Ok(added)
+        })
+    }
+
+    /// Marks an operation cancelled, merging the final `batch` into the
+    /// results it already has. Returns the ids of the todos cancelled with it:
+    /// the todo it was the current run of, if any.
+    pub fn mark_operation_cancelled(&mut self, operation_id: &str, batch: Option<Vec<ProfileResult>>) -> Result<Vec<String>, InstagrapError> {
+        let results = self.results_with(operation_id, batch);
+        self.update_operation(operation_id, OperationStatus::Cancelled, results, None)?;
+        Ok(self
+            .job_for_operation(operation_id)
+            .filter(|(job, _)| job.listed && job.status == TodoStatus::Cancelled)
+            .map(|(job, _)| job.id.clone())
+            .into_iter()
+            .collect())
+    }
+
+    /// Applies a backend status answer to a running operation: the answer
+    /// goes on its timeline, results that came with it are merged into the
+    /// ones collected so far, and completed, failed and cancelled runs take
+    /// the backend's outcome while anything else leaves it running. A failed
+    /// or cancelled run keeps the results it found. Returns the response to
+    /// hand out instead of the raw answer, if there is one.
+    pub fn apply_scrape_status(&mut self, operation: &ScrapingOperation, status_result: &ScrapeStatus) -> Result<Option<serde_json::Value>, InstagrapError> {
+        let operation_id = operation.operation_id.as_str();
+        self.record_poll(operation_id, status_result, Utc::now())?;
+        let batch = status_result.results.as_ref().map(|r| validated_results(r, &operation.target_account));
+        match status_result.status.as_str() {
+            "completed" => {
+                if batch.is_some() {
+                    let results = self.results_with(operation_id, batch).unwrap_or_default();
+                    let response = json!({ "status": "completed", "results": results, "count": results.len() });
+                    self.update_operation(operation_id, OperationStatus::Completed, Some(results), None)?;
+                    return Ok(Some(response));
+                }
+            }
+            "failed" => {
+                let error_message = status_result.failure_message().unwrap_or("Unknown error").to_string();
+                let results = self.results_with(operation_id, batch);
+                let response = json!({
+                    "status": "failed",
+                    "error_message": error_message,
+                    "results": results,
+                    "count": results.as_ref().map_or(0, Vec::len),
+                });
+                self.update_operation(operation_id, OperationStatus::Failed, results, Some(error_message))?;
+                if status_result.is_transient_failure() {
+                    if let Some(todo_id) = self.job_for_operation(operation_id).filter(|(job, _)| job.listed).map(|(job, _)| job.id.clone()) {
+                        self.mark_attempt_retryable(&todo_id)?;
+                    }
+                }
+                return Ok(Some(response));
+            }
+            // Cancelled from somewhere else; keep what it got so far
+            "cancelled" => {
+                let results = self.results_with(operation_id, batch);
+                let response = json!({ "status": "cancelled", "results": results, "count": results.as_ref().map_or(0, Vec::len) });
+                self.update_operation(operation_id, OperationStatus::Cancelled, results, None)?;
+                return Ok(Some(response));
+            }
+            _ => {
+                if let Some(batch) = batch.filter(|b| !b.is_empty()) {
+                    self.merge_partial_results(operation_id, batch)?;
+                    // Hand out everything found so far rather than the last batch
+                    let mut response = json!(status_result);
+                    response["results"] = json!(self.results_with(operation_id, None));
+                    return Ok(Some(response));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The scrape already running for `target`, todos and scrapes still being
+    /// started included, if any.
+    pub fn scrape_conflict(&self, target: &str) -> Option<ScrapeConflict> {
+        self.conflict_except(target, None)
+    }
+
+    /// The scrape that keeps the todo `todo_id` from starting: another one
+    /// already running for its target.
+    pub fn todo_conflict(&self, todo_id: &str) -> Option<ScrapeConflict> {
+        let job = self.get_job(todo_id)?;
+        self.conflict_except(&job.target_account, Some(todo_id))
+    }
+
+    fn conflict_except(&self, target: &str, except: Option<&str>) -> Option<ScrapeConflict> {
+        let running = |job: &&Job| job.status == TodoStatus::Running && Some(job.id.as_str()) != except;
+        let Some(job) = self.jobs.iter().filter(running).find(|job| same_target(&job.target_account, target)) else {
+            let starting = self.starting_scrapes.iter().find(|s| same_target(&s.target_account, target))?;
+            return Some(ScrapeConflict {
+                target_account: starting.target_account.clone(),
+                operation_id: None,
+                exec_id: None,
+                todo_id: None,
+                started_at: starting.started_at.clone(),
+                options: vec![ConflictResolution::Force],
+            });
+        };
+        let run = job.current_run();
+        let operation_id = run.and_then(|run| run.operation_id.clone());
+        // Without an operation there is nothing to follow or cancel yet
+        let options = match operation_id {
+            Some(_) => vec![ConflictResolution::Attach, ConflictResolution::Cancel, ConflictResolution::Force],
+            None => vec![ConflictResolution::Force],
+        };
+        Some(ScrapeConflict {
+            target_account: job.target_account.clone(),
+            operation_id,
+            exec_id: run.and_then(|run| run.exec_id.clone()),
+            todo_id: job.listed.then(|| job.id.clone()),
+            started_at: run.map_or(job.created_at.clone(), |run| run.started_at.clone()),
+            options,
+        })
+    }
+
+    /// Holds `target` for a direct scrape about to be sent to the backend.
+    pub fn reserve_scrape(&mut self, target: &str) {
+        self.starting_scrapes.push(StartingScrape { target_account: target.to_string(), started_at: Utc::now().to_rfc3339() });
+    }
+
+    /// Lets go of a target held by `reserve_scrape`.
+    pub fn release_scrape(&mut self, target: &str) {
+        if let Some(index) = self.starting_scrapes.iter().position(|s| same_target(&s.target_account, target)) {
+            self.starting_scrapes.remove(index);
+        }
+    }
+
+    /// Records the operation described by a `/remote-scrape` response, if any.
+    pub fn record_scrape_response(&mut self, target: &str, target_yes: u32, result: &ScrapeStatus) -> Result<(), InstagrapError> {
+        if let Some(operation) = operation_for_response(target, target_yes, result) {
+            let operation_id = operation.operation_id.clone();
+            // The backend handed back a job we already follow
+            if self.get_operation(&operation_id).is_some() {
+                eprintln!("ℹ️ Operation {} is already recorded", operation_id);
+                return Ok(());
+            }
+            self.add_operation(operation)?;
+            eprintln!("✅ Operation saved to persistent storage: {}", operation_id);
+        }
+        Ok(())
+    }
+}
+
+/// The operation a `/remote-scrape` response describes: a running one when the
+/// scrape was queued, a completed one when it finished right away.
+pub fn operation_for_response(target: &str, target_yes: u32, result: &ScrapeStatus) -> Option<ScrapingOperation> {
+    let (operation_id, status, results) = match result.status.as_str() {
+        "queued" => (result.operation.clone()?, OperationStatus::Running, None),
+        // If completed immediately, keep the results
+        "completed" => (
+            format!("completed_{}", Uuid::new_v4()),
+            OperationStatus::Completed,
+            Some(validated_results(result.results.as_ref()?, target)),
+        ),
+        _ => return None,
+    };
+    Some(ScrapingOperation {
+        operation_id,
+        target_account: target.to_string(),
+        target_count: target_yes,
+        started_at: Utc::now().to_rfc3339(),
+        status,
+        results,
+        error_message: None,
+        exec_id: result.exec_id.clone().filter(|_| status == OperationStatus::Running),
+        events: Vec::new(),
+    })
+}
+
+/// Starts a scrape on the backend with the criteria selected for this job and
+/// records the resulting operation. Returns the backend response unchanged.
+/// If the target already has a scrape running, `req.on_conflict` decides what
+/// happens; without it the call fails with a conflict error.
+pub async fn start_remote_scrape(state: &AppStateManager, req: ScrapeRequest) -> Result<serde_json::Value, InstagrapError> {
+    // Checked and reserved under one lock, so two starts can't both pass
+    let conflict = {
+        let mut app_state = state.lock()?;
+        let conflict = app_state.scrape_conflict(&req.target);
+        if conflict.is_none() {
+            app_state.reserve_scrape(&req.target);
+        }
+        conflict
+    };
+    if let Some(conflict) = conflict {
+        let Some(resolution) = req.on_conflict.filter(|r| conflict.options.contains(r)) else {
+            eprintln!("⚠️ Refusing to start a second scrape: {}", conflict);
+            return Err(InstagrapError::Conflict(Box::new(conflict)));
+        };
+        match (resolution, conflict.operation_id) {
+            (ConflictResolution::Attach, Some(operation_id)) => {
+                eprintln!("🔗 Attaching to running operation {} for {}", operation_id, req.target);
+                let operation = state.lock()?.get_operation(&operation_id).ok_or_else(|| InstagrapError::not_found("Operation"))?;
+                return Ok(json!({
+                    "status": operation.status,
+                    "operation": operation.operation_id,
+                    "exec_id": operation.exec_id,
+                    "attached": true,
+                }));
+            }
+            (ConflictResolution::Cancel, Some(operation_id)) => {
+                cancel_operation(state, &operation_id, false).await?;
+            }
+            _ => eprintln!("⚠️ Starting a parallel scrape of {}", req.target),
+        }
+        state.lock()?.reserve_scrape(&req.target);
+    }
+
+    let result = send_remote_scrape(state, &req).await;
+    let mut app_state = state.lock()?;
+    app_state.release_scrape(&req.target);
+    let result = result?;
+    // If the operation was queued, save it to persistent storage
+    app_state.record_scrape_response(&req.target, req.target_yes, &result)?;
+    Ok(json!(result))
+}
+
+/// Starts a scrape on the backend without recording anything; callers store
+/// the operation themselves.
+pub async fn send_remote_scrape(state: &AppStateManager, req: &ScrapeRequest) -> Result<ScrapeStatus, InstagrapError> {
+    eprintln!("🔍 [DEBUG] Proxy remote scrape called with: criteria_preset_id={:?} criteria_text={:?}", req.criteria_preset_id, req.criteria_text);
+    eprintln!("🔍 [DEBUG] Proxy remote scrape called with: target={}, target_yes={}, batch_size={}", req.target, req.target_yes, req.batch_size);
+    // Do NOT mutate backend-global criteria. Gather per-job selection only.
+    let (active_preset_for_job, criteria_text_for_job) =
+        state.lock()?.criteria_for_job(req.criteria_preset_id.clone(), req.criteria_text.clone());
+
+    let body = RemoteScrapeRequest {
+        target: req.target.clone(),
+        target_yes: req.target_yes,
+        batch_size: req.batch_size,
+        num_bio_pages: req.num_bio_pages,
+        // pass through per-job criteria selection
+        criteria_preset_id: active_preset_for_job,
+        criteria_text: criteria_text_for_job,
+    };
+    eprintln!("🛰️ [DEBUG] proxy_remote_scrape -> backend body keys: preset_id_present={} text_present={}",
+        body.criteria_preset_id.is_some(),
+        body.criteria_text.as_ref().map(|s| !s.is_empty()).unwrap_or(false)
+    );
+
+    state.api()?.remote_scrape(&body).await
+}
+
+/// Refreshes a running operation from the backend. Finished operations, and
+/// running ones when the backend is unreachable, are answered from the store.
+pub async fn check_operation_status(state: &AppStateManager, operation_id: &str) -> Result<serde_json::Value, InstagrapError> {
+    // First, check if the operation exists and get its current status
+    let operation = state
+        .lock()?
+        .get_operation(operation_id)
+        .ok_or_else(|| InstagrapError::not_found("Operation"))?;
+
+    let cached = json!({
+        "status": operation.status,
+        "results": operation.results,
+        "error_message": operation.error_message
+    });
+    if !operation.status.is_active() {
+        // Return cached status for completed/failed operations
+        return Ok(cached);
+    }
+
+    // Check the actual status from the backend
+    // Prefer GCS exec_id if available; fallback to legacy operation id
+    let exec_id = operation.exec_id.clone().unwrap_or_default();
+    let api = state.api()?;
+    let status_result = match api.scrape_status(&exec_id, &operation.target_account, Some(&operation.operation_id)).await {
+        Ok(status_result) => status_result,
+        // Return the cached status if we can't reach the backend
+        Err(_e) => return Ok(cached),
+    };
+
+    // Update the state based on the backend response
+    let response = state.lock()?.apply_scrape_status(&operation, &status_result)?;
+    Ok(response.unwrap_or_else(|| json!(status_result)))
+}
+
+/// Stops a running operation on the backend and marks it, and the todo
+/// running it, cancelled. Partial results from the backend are kept. With
+/// `delete_artifacts` the backend copy of the run is deleted afterwards.
+pub async fn cancel_operation(state: &AppStateManager, operation_id: &str, delete_artifacts: bool) -> Result<serde_json::Value, InstagrapError> {
+    let operation = state
+        .lock()?
+        .get_operation(operation_id)
+        .ok_or_else(|| InstagrapError::not_found("Operation"))?;
+    if operation.status.is_terminal() {
+        return Err(InstagrapError::Validation(format!("Operation {} is already {}", operation_id, operation.status)));
+    }
+
+    let api = state.api()?;
+    let response = api.cancel_scrape(&operation.target_account, operation.exec_id.as_deref(), operation_id).await?;
+    let batch = response.results.as_ref().map(|r| validated_results(r, &operation.target_account));
+    let (status, todo_ids, count) = {
+        let mut app_state = state.lock()?;
+        let (status, todo_ids) = if response.status == "completed" {
+            // It finished before the cancel arrived; a todo running it completes with it
+            let results = app_state.results_with(operation_id, batch);
+            app_state.update_operation(operation_id, OperationStatus::Completed, results, None)?;
+            (OperationStatus::Completed, Vec::new())
+        } else {
+            (OperationStatus::Cancelled, app_state.mark_operation_cancelled(operation_id, batch)?)
+        };
+        let count = app_state.get_operation(operation_id).and_then(|op| op.results).map_or(0, |r| r.len());
+        (status, todo_ids, count)
+    };
+    eprintln!("🛑 Operation {} {} with {} results", operation_id, status, count);
+
+    let mut artifacts_deleted = false;
+    if let (true, Some(exec_id)) = (delete_artifacts, &operation.exec_id) {
+        match api.delete_scrape_artifacts(&operation.target_account, exec_id).await {
+            Ok(()) => {
+                eprintln!("🧹 Deleted GCS artifacts for exec_id: {}", exec_id);
+                artifacts_deleted = true;
+            }
+            Err(e) => eprintln!("⚠️ Failed to delete GCS artifacts for exec_id {}: {}", exec_id, e),
+        }
+    }
+
+    Ok(json!({
+        "operation_id": operation_id,
+        "status": status,
+        "count": count,
+        "todo_ids": todo_ids,
+        "artifacts_deleted": artifacts_deleted,
+    }))
+}
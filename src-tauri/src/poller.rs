@@ -0,0 +1,217 @@
+// Background refresh of running operations and todos.
+//
+// Status used to move only while a window polled for it. The poller runs for
+// the whole life of the app instead: its first tick reconciles whatever was
+// left running at launch (see crate::reconcile), then it refreshes every
+// running operation, marks the ones that stopped making progress stalled (see
+// crate::timeline), reports the todos that finished with them, queues
+// scheduled runs and retries that are due, starts queued todos as slots free
+// up and reports each change through the `emit` callback (the desktop app
+// forwards them as Tauri events). Operations whose status stays the same are
+// checked less and less often, from `min_interval` up to `max_interval`.
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::error::InstagrapError;
+use crate::operations::{self, operation_json};
+use crate::queue;
+use crate::reconcile::{self, RECONCILIATION_FINISHED};
+use crate::state::AppStateManager;
+use crate::status::TodoStatus;
+use crate::todos::{todo_json, Todo};
+
+pub const OPERATION_UPDATED: &str = "operation-updated";
+pub const TODO_UPDATED: &str = "todo-updated";
+
+#[derive(Clone, Debug)]
+pub struct PollerConfig {
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+}
+
+impl Default for PollerConfig {
+    fn default() -> Self {
+        Self { min_interval: Duration::from_secs(5), max_interval: Duration::from_secs(60) }
+    }
+}
+
+struct Schedule {
+    due: Instant,
+    interval: Duration,
+    last_response: Option<Value>,
+}
+
+pub struct Poller<E> {
+    state: AppStateManager,
+    config: PollerConfig,
+    emit: E,
+    schedule: HashMap<String, Schedule>,
+    // Todos seen running, reported once they finish
+    running_todos: HashSet<String>,
+}
+
+impl<E: Fn(&str, Value) + Send + Sync> Poller<E> {
+    pub fn new(state: AppStateManager, config: PollerConfig, emit: E) -> Self {
+        Self { state, config, emit, schedule: HashMap::new(), running_todos: HashSet::new() }
+    }
+
+    /// Polls until the runtime shuts down.
+    pub async fn run(mut self) {
+        eprintln!("🔄 Background poller started");
+        loop {
+            let wait = self.tick().await;
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Refreshes every operation that is due and settles finished todos.
+    /// Returns how long to wait before the next tick.
+    pub async fn tick(&mut self) -> Duration {
+        let reconcile = self.state.lock().map(|mut state| std::mem::take(&mut state.reconciliation_pending));
+        if reconcile.unwrap_or(false) {
+            self.reconcile().await;
+        }
+        let running: Vec<String> = match self.state.lock() {
+            Ok(state) => {
+                let todos = state.get_todos().into_iter().filter(|t| t.status == TodoStatus::Running);
+                self.running_todos.extend(todos.map(|t| t.id));
+                state.get_running_operations().into_iter().map(|op| op.operation_id).collect()
+            }
+            Err(e) => {
+                eprintln!("❌ Poller could not read state: {}", e);
+                return self.config.max_interval;
+            }
+        };
+        self.schedule.retain(|id, _| running.contains(id));
+
+        let now = Instant::now();
+        for operation_id in running {
+            let due = self.schedule.get(&operation_id).is_none_or(|s| s.due <= now);
+            if due {
+                self.refresh_operation(&operation_id).await;
+            }
+        }
+        if let Err(e) = self.detect_stalls() {
+            eprintln!("❌ Poller failed to check for stalled operations: {}", e);
+        }
+        if let Err(e) = self.settle_todos().await {
+            eprintln!("❌ Poller failed to update todos: {}", e);
+        }
+        if let Err(e) = self.start_queued_todos().await {
+            eprintln!("❌ Poller failed to start queued todos: {}", e);
+        }
+
+        let now = Instant::now();
+        self.schedule
+            .values()
+            .map(|s| s.due.saturating_duration_since(now))
+            .min()
+            .unwrap_or(self.config.min_interval)
+    }
+
+    // One pass over whatever was left running when the app last stopped
+    async fn reconcile(&self) {
+        let summary = match reconcile::reconcile(&self.state).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                eprintln!("❌ Reconciliation failed: {}", e);
+                return;
+            }
+        };
+        for item in &summary.todos {
+            if let Some(todo) = self.state.lock().ok().and_then(|state| state.get_todo(&item.id).as_ref().map(todo_json)) {
+                (self.emit)(TODO_UPDATED, todo);
+            }
+        }
+        (self.emit)(RECONCILIATION_FINISHED, json!(summary));
+    }
+
+    fn detect_stalls(&self) -> Result<(), InstagrapError> {
+        let mut state = self.state.lock()?;
+        for operation_id in state.detect_stalled_operations(Utc::now())? {
+            if let Some(operation) = state.get_operation(&operation_id) {
+                (self.emit)(OPERATION_UPDATED, operation_json(&operation));
+            }
+        }
+        Ok(())
+    }
+
+    async fn refresh_operation(&mut self, operation_id: &str) {
+        let response = match operations::check_operation_status(&self.state, operation_id).await {
+            Ok(response) => Some(response),
+            Err(e) => {
+                eprintln!("❌ Poller failed to refresh operation {}: {}", operation_id, e);
+                None
+            }
+        };
+
+        let min_interval = self.config.min_interval;
+        let schedule = self.schedule.entry(operation_id.to_string()).or_insert(Schedule {
+            due: Instant::now(),
+            interval: min_interval,
+            last_response: None,
+        });
+        let changed = response.is_some() && response != schedule.last_response;
+        // Back off while nothing moves, check again soon once something does
+        schedule.interval = if changed { min_interval } else { (schedule.interval * 2).min(self.config.max_interval) };
+        schedule.due = Instant::now() + schedule.interval;
+        if !changed {
+            return;
+        }
+        schedule.last_response = response.clone();
+
+        let operation = self.state.lock().ok().and_then(|state| state.get_operation(operation_id));
+        if let (Some(operation), Some(response)) = (operation, response) {
+            let mut payload = operation_json(&operation);
+            if let Some(progress) = response.get("progress") {
+                payload["progress"] = progress.clone();
+            }
+            (self.emit)(OPERATION_UPDATED, payload);
+        }
+    }
+
+    // Due scheduled runs and retries join the queue; finished jobs free slots
+    // for queued todos
+    async fn start_queued_todos(&self) -> Result<(), InstagrapError> {
+        {
+            let mut state = self.state.lock()?;
+            state.queue_due_runs(Utc::now())?;
+            state.queue_due_retries(Utc::now())?;
+        }
+        let filled = queue::fill_slots(&self.state).await?;
+        for todo_id in filled.started.iter().chain(&filled.failed) {
+            if let Some(todo) = self.state.lock()?.get_todo(todo_id) {
+                (self.emit)(TODO_UPDATED, todo_json(&todo));
+            }
+        }
+        Ok(())
+    }
+
+    // Todos finish with their operation; the ones seen running that have
+    // finished since are reported, and completed ones no longer need the
+    // backend's copy of their results
+    async fn settle_todos(&mut self) -> Result<(), InstagrapError> {
+        let finished: Vec<Todo> = {
+            let state = self.state.lock()?;
+            self.running_todos.iter().filter_map(|id| state.get_todo(id)).filter(|t| t.status != TodoStatus::Running).collect()
+        };
+        self.running_todos.retain(|id| !finished.iter().any(|t| &t.id == id));
+
+        for todo in finished {
+            if todo.status == TodoStatus::Completed {
+                // Results are stored locally now, the backend copy is no longer needed
+                if let Some(exec_id) = &todo.exec_id {
+                    match self.state.api()?.delete_scrape_artifacts(&todo.target_account, exec_id).await {
+                        Ok(()) => eprintln!("🧹 Deleted GCS artifacts for exec_id: {}", exec_id),
+                        Err(e) => eprintln!("⚠️ Failed to delete GCS artifacts for exec_id {}: {}", exec_id, e),
+                    }
+                }
+            }
+            (self.emit)(TODO_UPDATED, todo_json(&todo));
+        }
+        Ok(())
+    }
+}
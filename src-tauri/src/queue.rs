@@ -0,0 +1,190 @@
+// Todo queue: "run all" / "run next" with a limit on concurrent remote jobs.
+//
+// Queued todo ids are saved with the state, and the background poller calls
+// `fill_slots` on every tick, so a queue left behind by a restart picks up
+// where it stopped and finished jobs make room for the next todo.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::InstagrapError;
+use crate::state::{AppState, AppStateManager};
+use crate::status::TodoStatus;
+use crate::todos;
+
+pub const DEFAULT_MAX_CONCURRENT: u32 = 2;
+pub const MAX_CONCURRENT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TodoQueue {
+    /// How many remote jobs may run at once
+    pub max_concurrent: u32,
+    /// Pending todo ids waiting for a free slot, in run order
+    pub queued: Vec<String>,
+}
+
+/// Todos a `fill_slots` pass took off the queue.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct FilledSlots {
+    pub started: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl Default for TodoQueue {
+    fn default() -> Self {
+        Self { max_concurrent: DEFAULT_MAX_CONCURRENT, queued: Vec::new() }
+    }
+}
+
+impl AppState {
+    /// Remote jobs in flight: running scrapes, including todos that are
+    /// starting and have no operation yet.
+    pub fn running_jobs(&self) -> usize {
+        self.jobs.iter().filter(|job| job.status == TodoStatus::Running).count()
+    }
+
+    /// Appends pending todos to the queue, skipping ones already queued.
+    /// Returns how many were added.
+    pub fn enqueue_todos(&mut self, todo_ids: &[String]) -> Result<usize, InstagrapError> {
+        let mut queue = self.todo_queue.clone();
+        for todo_id in todo_ids {
+            let todo = self.get_todo(todo_id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+            if todo.status != TodoStatus::Pending {
+                return Err(InstagrapError::Validation(format!("Todo {} is {}, only pending todos can be queued", todo_id, todo.status)));
+            }
+            if !queue.queued.contains(todo_id) {
+                queue.queued.push(todo_id.clone());
+            }
+        }
+        let added = queue.queued.len() - self.todo_queue.queued.len();
+        self.save_todo_queue(queue)?;
+        Ok(added)
+    }
+
+    pub fn set_max_concurrent(&mut self, max_concurrent: u32) -> Result<(), InstagrapError> {
+        if !(1..=MAX_CONCURRENT_LIMIT).contains(&max_concurrent) {
+            return Err(InstagrapError::Validation(format!("Concurrency must be between 1 and {}", MAX_CONCURRENT_LIMIT)));
+        }
+        self.save_todo_queue(TodoQueue { max_concurrent, ..self.todo_queue.clone() })
+    }
+
+    pub fn queue_json(&self) -> Value {
+        json!({
+            "max_concurrent": self.todo_queue.max_concurrent,
+            "queued": self.todo_queue.queued,
+            "running": self.running_jobs(),
+        })
+    }
+
+    /// Takes the next runnable todo off the queue and marks it running, so the
+    /// slot is claimed before the lock is released. Without `respect_limit`
+    /// (an explicit "run next") the oldest pending todo is used when the queue
+    /// is empty, and quiet hours don't apply.
+    fn claim_next_todo(&mut self, respect_limit: bool) -> Result<Option<String>, InstagrapError> {
+        let full = self.running_jobs() >= self.todo_queue.max_concurrent as usize;
+        if respect_limit && (full || self.is_quiet_at(Utc::now())) {
+            return Ok(None);
+        }
+        let mut queue = self.todo_queue.clone();
+        let is_pending = |state: &Self, id: &str| state.get_todo(id).is_some_and(|t| t.status == TodoStatus::Pending);
+        // Todos whose target already has a scrape running wait for it to finish
+        let startable = |state: &Self, id: &str| is_pending(state, id) && state.todo_conflict(id).is_none();
+        // Deleted or already started todos are dropped from the queue on the way
+        let position = queue.queued.iter().position(|id| startable(self, id));
+        let next = match position {
+            Some(position) => {
+                let next = queue.queued.remove(position);
+                let waiting: Vec<String> = queue.queued.drain(..position).filter(|id| is_pending(self, id)).collect();
+                queue.queued.splice(..0, waiting);
+                Some(next)
+            }
+            None => {
+                queue.queued.retain(|id| is_pending(self, id));
+                if respect_limit {
+                    None
+                } else {
+                    self.jobs.iter().find(|t| t.listed && startable(self, &t.id)).map(|t| t.id.clone())
+                }
+            }
+        };
+        if queue != self.todo_queue {
+            self.save_todo_queue(queue)?;
+        }
+        if let Some(todo_id) = &next {
+            self.update_todo(todo_id, TodoStatus::Running, None, None, None)?;
+        }
+        Ok(next)
+    }
+
+    fn save_todo_queue(&mut self, queue: TodoQueue) -> Result<(), InstagrapError> {
+        self.store.save_todo_queue(&queue)?;
+        self.todo_queue = queue;
+        Ok(())
+    }
+}
+
+// ===== Commands =====
+
+/// Queues every pending todo, oldest first, and starts as many as the
+/// concurrency limit allows. Returns the queue with the ids of the todos that
+/// were started and of those that failed to start.
+pub async fn run_all(state: &AppStateManager) -> Result<Value, InstagrapError> {
+    let added = {
+        let mut app_state = state.lock()?;
+        let pending: Vec<String> = app_state
+            .get_todos()
+            .into_iter()
+            .filter(|t| t.status == TodoStatus::Pending)
+            .map(|t| t.id)
+            .collect();
+        app_state.enqueue_todos(&pending)?
+    };
+    eprintln!("📋 Queued {} todos", added);
+    let filled = fill_slots(state).await?;
+    let mut response = state.lock()?.queue_json();
+    response["started"] = json!(filled.started);
+    response["failed"] = json!(filled.failed);
+    Ok(response)
+}
+
+/// Starts the next queued todo, or the oldest pending one when nothing is
+/// queued, if a slot is free. Returns the started todo's id and the backend
+/// response.
+pub async fn run_next(state: &AppStateManager) -> Result<Value, InstagrapError> {
+    let todo_id = {
+        let mut app_state = state.lock()?;
+        let (running, limit) = (app_state.running_jobs(), app_state.todo_queue.max_concurrent);
+        if running >= limit as usize {
+            return Err(InstagrapError::Validation(format!("{} jobs are already running (limit {})", running, limit)));
+        }
+        app_state.claim_next_todo(false)?
+    };
+    let Some(todo_id) = todo_id else {
+        return Err(InstagrapError::not_found("Pending todo"));
+    };
+    let response = todos::run_todo(state, &todo_id, None).await?;
+    Ok(json!({ "todo_id": todo_id, "response": response }))
+}
+
+/// Starts queued todos until the concurrency limit is reached or the queue is
+/// empty. A todo that fails to start is marked failed and the next one is
+/// tried; one whose target already has a scrape running stays queued.
+/// Returns the ids of the todos that were started and of those that failed
+/// to start.
+pub async fn fill_slots(state: &AppStateManager) -> Result<FilledSlots, InstagrapError> {
+    let mut filled = FilledSlots::default();
+    loop {
+        let next = state.lock()?.claim_next_todo(true)?;
+        let Some(todo_id) = next else { break };
+        eprintln!("▶️ Starting queued todo {}", todo_id);
+        match todos::run_todo(state, &todo_id, None).await {
+            Ok(_) => filled.started.push(todo_id),
+            Err(e) => {
+                eprintln!("❌ Queued todo {} failed to start: {}", todo_id, e);
+                filled.failed.push(todo_id);
+            }
+        }
+    }
+    Ok(filled)
+}
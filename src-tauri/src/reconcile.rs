@@ -0,0 +1,122 @@
+// Startup reconciliation of running operations and todos with the backend.
+//
+// After a crash or the laptop sleeping, operations and todos can stay
+// "running" long after the backend finished or forgot them. On startup,
+// `AppStateManager::new` asks for one pass over them, which the background
+// poller runs on its first tick: every running operation is checked once,
+// finished ones take the backend's outcome, ones the backend answers 404 for
+// are marked lost, and running todos follow their operation, which is the run
+// they are on. An unreachable backend leaves things as they were. The outcome
+// is kept as a summary for the UI.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::error::InstagrapError;
+use crate::operations::ScrapingOperation;
+use crate::state::AppStateManager;
+use crate::status::{OperationStatus, TodoStatus};
+use crate::todos::Todo;
+
+pub const RECONCILIATION_FINISHED: &str = "reconciliation-finished";
+
+// Outcomes besides the statuses themselves
+pub const UNREACHABLE: &str = "unreachable";
+
+const OPERATION_LOST: &str = "The backend no longer knows this scrape. It may have expired while the app was closed.";
+const NEVER_STARTED: &str = "The app stopped before the backend confirmed this scrape.";
+
+/// What the pass did with one operation or todo.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReconciledItem {
+    pub id: String,
+    pub target_account: String,
+    /// Status after the pass, or "unreachable" when the backend could not be asked
+    pub outcome: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReconcileSummary {
+    pub checked_at: String,
+    pub operations: Vec<ReconciledItem>,
+    pub todos: Vec<ReconciledItem>,
+}
+
+impl ReconcileSummary {
+    /// Operations that ended up with `outcome`.
+    pub fn count(&self, outcome: &str) -> usize {
+        self.operations.iter().filter(|item| item.outcome == outcome).count()
+    }
+
+    /// One line for logs and notifications, e.g. "3 running jobs checked: 2 completed, 1 lost".
+    pub fn message(&self) -> String {
+        let parts: Vec<String> = ["completed", "failed", "cancelled", "lost", "running", "stalled", UNREACHABLE]
+            .iter()
+            .map(|outcome| (outcome, self.count(outcome)))
+            .filter(|(_, count)| *count > 0)
+            .map(|(outcome, count)| format!("{} {}", count, if *outcome == "running" { "still running" } else { outcome }))
+            .collect();
+        if parts.is_empty() {
+            return "No running jobs to check".to_string();
+        }
+        format!("{} running jobs checked: {}", self.operations.len(), parts.join(", "))
+    }
+}
+
+/// Checks every running operation and todo against the backend once and
+/// stores the summary on the state.
+pub async fn reconcile(state: &AppStateManager) -> Result<ReconcileSummary, InstagrapError> {
+    let (running, running_todos): (Vec<ScrapingOperation>, Vec<Todo>) = {
+        let app_state = state.lock()?;
+        let todos = app_state.get_todos().into_iter().filter(|t| t.status == TodoStatus::Running).collect();
+        (app_state.get_running_operations(), todos)
+    };
+    let api = state.api()?;
+    let mut summary = ReconcileSummary { checked_at: Utc::now().to_rfc3339(), ..Default::default() };
+
+    for operation in running {
+        let operation_id = operation.operation_id.as_str();
+        let exec_id = operation.exec_id.clone().unwrap_or_default();
+        let outcome = match api.scrape_status(&exec_id, &operation.target_account, Some(operation_id)).await {
+            Ok(status) => {
+                let mut app_state = state.lock()?;
+                app_state.apply_scrape_status(&operation, &status)?;
+                app_state.get_operation(operation_id).map_or(OperationStatus::Running, |op| op.status).to_string()
+            }
+            Err(InstagrapError::BackendHttp { status: 404, .. }) => {
+                let mut app_state = state.lock()?;
+                // Whatever it collected before the backend forgot it is kept
+                let results = app_state.results_with(operation_id, None);
+                app_state.update_operation(operation_id, OperationStatus::Lost, results, Some(OPERATION_LOST.to_string()))?;
+                OperationStatus::Lost.to_string()
+            }
+            Err(e) => {
+                eprintln!("⚠️ Could not reconcile operation {}: {}", operation_id, e);
+                UNREACHABLE.to_string()
+            }
+        };
+        summary.operations.push(ReconciledItem { id: operation.operation_id.clone(), target_account: operation.target_account.clone(), outcome });
+    }
+
+    // A todo's operation is its current run, so the pass above settled the
+    // todos that had one
+    for todo in running_todos {
+        let Some(current) = state.lock()?.get_todo(&todo.id) else { continue };
+        let status = match current.status {
+            // Still running, or the backend couldn't be asked
+            TodoStatus::Running if current.operation_id.is_some() => continue,
+            TodoStatus::Running => lose_todo(state, &current, NEVER_STARTED)?,
+            status => status,
+        };
+        summary.todos.push(ReconciledItem { id: todo.id.clone(), target_account: todo.target_account.clone(), outcome: status.to_string() });
+    }
+
+    eprintln!("🔎 Reconciliation: {}", summary.message());
+    state.lock()?.last_reconciliation = Some(summary.clone());
+    Ok(summary)
+}
+
+fn lose_todo(state: &AppStateManager, todo: &Todo, message: &str) -> Result<TodoStatus, InstagrapError> {
+    state.lock()?.update_todo(&todo.id, TodoStatus::Lost, todo.operation_id.clone(), None, Some(message.to_string()))?;
+    Ok(TodoStatus::Lost)
+}
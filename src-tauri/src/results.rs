@@ -0,0 +1,157 @@
+// Typed scrape results.
+//
+// The backend and the classifier have used slightly different field names over
+// time, so the well-known fields accept a few aliases. Anything not modelled
+// here is kept in `extras` and written back out unchanged.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ProfileResult {
+    pub username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub following_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_private: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_verified: Option<bool>,
+    /// Classifier verdict, e.g. "yes"/"no" or a category name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Classifier confidence between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+}
+
+// A well-known field with the wrong type or an impossible value doesn't make
+// the lead unusable: it stays in `extras` under its own name and the typed
+// field is left empty.
+impl<'de> Deserialize<'de> for ProfileResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = Map::deserialize(deserializer)?;
+        Ok(Self {
+            username: take_field(&mut fields, &["username"], any).unwrap_or_default(),
+            url: take_field(&mut fields, &["url"], any),
+            full_name: take_field(&mut fields, &["full_name", "fullName"], any),
+            bio: take_field(&mut fields, &["bio", "biography"], any),
+            follower_count: take_field(&mut fields, &["follower_count", "followers", "followers_count"], any),
+            following_count: take_field(&mut fields, &["following_count", "following", "followings_count"], any),
+            is_private: take_field(&mut fields, &["is_private", "private"], any),
+            is_verified: take_field(&mut fields, &["is_verified", "verified"], any),
+            verdict: take_field(&mut fields, &["verdict", "classification"], any),
+            reason: take_field(&mut fields, &["reason"], any),
+            confidence: take_field(&mut fields, &["confidence"], |c: &f64| (0.0..=1.0).contains(c)),
+            extras: fields,
+        })
+    }
+}
+
+fn any<T>(_: &T) -> bool {
+    true
+}
+
+// Removes and returns the first of `names` that holds a valid `T`. Nulls are
+// dropped; anything else that doesn't fit is left in `fields`.
+fn take_field<T: DeserializeOwned>(fields: &mut Map<String, Value>, names: &[&str], valid: impl Fn(&T) -> bool) -> Option<T> {
+    for name in names {
+        match fields.get(*name) {
+            None => continue,
+            Some(Value::Null) => {
+                fields.remove(*name);
+            }
+            Some(value) => {
+                if let Some(parsed) = T::deserialize(value).ok().filter(&valid) {
+                    fields.remove(*name);
+                    return Some(parsed);
+                }
+            }
+        }
+    }
+    None
+}
+
+impl ProfileResult {
+    /// Parses one raw backend result and checks it is usable.
+    pub fn parse(value: Value) -> Result<Self, String> {
+        let mut result: Self =
+            serde_json::from_value(value).map_err(|e| format!("Malformed result: {}", e))?;
+        result.username = result.username.trim().trim_start_matches('@').to_string();
+        result.validate()?;
+        Ok(result)
+    }
+
+    /// A result is usable as long as it names the profile.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.username.is_empty() {
+            return Err("Result has no username".to_string());
+        }
+        Ok(())
+    }
+
+    /// Profile URL, derived from the username when the backend did not send one.
+    pub fn profile_url(&self) -> String {
+        self.url
+            .clone()
+            .unwrap_or_else(|| format!("https://www.instagram.com/{}/", self.username))
+    }
+}
+
+/// Results that failed validation, with the reason each was dropped.
+#[derive(Debug, Default)]
+pub struct ParsedResults {
+    pub profiles: Vec<ProfileResult>,
+    pub rejected: Vec<String>,
+}
+
+/// Parses a batch of raw results, dropping invalid entries instead of failing the whole batch.
+pub fn parse_results(values: Vec<Value>) -> ParsedResults {
+    let mut parsed = ParsedResults::default();
+    for (index, value) in values.into_iter().enumerate() {
+        match ProfileResult::parse(value) {
+            Ok(profile) => parsed.profiles.push(profile),
+            Err(e) => parsed.rejected.push(format!("#{}: {}", index, e)),
+        }
+    }
+    parsed
+}
+
+/// Like `parse_results`, but logs and drops invalid entries. `context` names the
+/// run the results belong to in the log.
+pub fn validated_results(raw: &[Value], context: &str) -> Vec<ProfileResult> {
+    let parsed = parse_results(raw.to_vec());
+    for rejected in &parsed.rejected {
+        eprintln!("⚠️ Dropped invalid result for {}: {}", context, rejected);
+    }
+    parsed.profiles
+}
+
+/// Adds a batch to `results`, keeping one entry per username (case-insensitive).
+/// A profile seen again takes the newer data in its original place. Returns how
+/// many profiles were new.
+pub fn merge_results(results: &mut Vec<ProfileResult>, batch: Vec<ProfileResult>) -> usize {
+    let mut added = 0;
+    for profile in batch {
+        match results.iter_mut().find(|r| r.username.eq_ignore_ascii_case(&profile.username)) {
+            Some(existing) => *existing = profile,
+            None => {
+                results.push(profile);
+                added += 1;
+            }
+        }
+    }
+    added
+}
@@ -0,0 +1,136 @@
+// Todo retries and attempt history.
+//
+// Every run of a todo stays on its job (see crate::jobs), and the finished
+// ones are listed as the todo's attempts (timestamps, operation/exec ids,
+// error), so a retry doesn't overwrite what happened before. `retry_todo`
+// puts a failed or cancelled todo back in the queue with the same parameters
+// and preset. With a retry policy set, todos that failed to start because of
+// a transient backend error (see `InstagrapError::retryable`), or whose scrape
+// the backend later reported as failed for a transient reason (see
+// `ScrapeStatus::is_transient_failure`), are retried on their own with
+// exponential backoff, up to `max_attempts` failures in a row.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::InstagrapError;
+use crate::state::AppState;
+use crate::status::{OperationStatus, TodoStatus};
+use crate::todos::Todo;
+
+pub const MAX_ATTEMPTS_LIMIT: u32 = 10;
+// Backoff doubles after every failure but never waits longer than this
+const MAX_BACKOFF_SECONDS: u64 = 3600;
+
+/// One finished run of a todo.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TodoAttempt {
+    /// 1-based, in run order
+    pub attempt: u32,
+    pub started_at: Option<String>,
+    pub finished_at: String,
+    pub status: TodoStatus,
+    pub operation_id: Option<String>,
+    pub exec_id: Option<String>,
+    pub error_message: Option<String>,
+    /// The run failed on a transient backend error and may be retried automatically
+    pub retryable: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Failures in a row after which a todo is left failed
+    pub max_attempts: u32,
+    /// Wait before the first automatic retry; doubles for each further one
+    pub backoff_seconds: u64,
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), InstagrapError> {
+        if !(1..=MAX_ATTEMPTS_LIMIT).contains(&self.max_attempts) {
+            return Err(InstagrapError::Validation(format!("Max attempts must be between 1 and {}", MAX_ATTEMPTS_LIMIT)));
+        }
+        if self.backoff_seconds == 0 {
+            return Err(InstagrapError::validation("Backoff must be at least one second"));
+        }
+        Ok(())
+    }
+
+    /// Wait before retrying after `failures` failures in a row.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u64.saturating_pow(failures.saturating_sub(1));
+        Duration::seconds(self.backoff_seconds.saturating_mul(factor).min(MAX_BACKOFF_SECONDS) as i64)
+    }
+}
+
+impl Todo {
+    /// Failed attempts since the last one that didn't fail.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.attempts.iter().rev().take_while(|a| a.status == TodoStatus::Failed).count() as u32
+    }
+}
+
+impl AppState {
+    pub fn set_retry_policy(&mut self, policy: Option<RetryPolicy>) -> Result<(), InstagrapError> {
+        if let Some(policy) = &policy {
+            policy.validate()?;
+        }
+        self.store.save_retry_policy(policy.as_ref())?;
+        self.retry_policy = policy;
+        Ok(())
+    }
+
+    /// Puts a failed or cancelled todo back in the queue with the same
+    /// parameters and preset. Earlier attempts are kept.
+    pub fn retry_todo(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        let todo = self.get_todo(todo_id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+        todo.status.retry()?;
+        self.reset_todo_for_run(todo_id)?;
+        self.enqueue_todos(&[todo_id.to_string()])?;
+        eprintln!("🔁 Todo {} queued for another attempt", todo_id);
+        Ok(())
+    }
+
+    /// Marks the latest attempt of a todo as a transient failure and, if the
+    /// retry policy allows another try, schedules it.
+    pub(crate) fn mark_attempt_retryable(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Err(InstagrapError::not_found("Todo"));
+        }
+        let policy = self.retry_policy.clone();
+        self.update_job(todo_id, false, |job| {
+            let Some(run) = job.runs.last_mut().filter(|run| run.status == OperationStatus::Failed) else {
+                return Ok(());
+            };
+            run.retryable = true;
+            let failures = job.todo().consecutive_failures();
+            job.next_retry_at = match &policy {
+                Some(policy) if failures < policy.max_attempts => {
+                    let at = Utc::now() + policy.backoff(failures);
+                    eprintln!("🔁 Todo {} will be retried at {} (attempt {} of {})", todo_id, at, failures + 1, policy.max_attempts);
+                    Some(at.to_rfc3339())
+                }
+                _ => None,
+            };
+            Ok(())
+        })
+    }
+
+    /// Queues every failed todo whose automatic retry is due. Returns their ids.
+    pub fn queue_due_retries(&mut self, now: DateTime<Utc>) -> Result<Vec<String>, InstagrapError> {
+        let due: Vec<String> = self
+            .jobs
+            .iter()
+            .filter(|t| t.listed && t.status == TodoStatus::Failed)
+            .filter(|t| {
+                let at = t.next_retry_at.as_deref().and_then(|at| DateTime::parse_from_rfc3339(at).ok());
+                at.is_some_and(|at| at <= now)
+            })
+            .map(|t| t.id.clone())
+            .collect();
+        for todo_id in &due {
+            self.retry_todo(todo_id)?;
+        }
+        Ok(due)
+    }
+}
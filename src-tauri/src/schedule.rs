@@ -0,0 +1,347 @@
+// Scheduled and recurring todos.
+//
+// A schedule is attached to a todo: run once at a given time, on a cron
+// expression or every N minutes. When a run is due the todo goes back to
+// pending and joins the todo queue, so every run starts its own operation
+// (runs accumulate per target) and the concurrency limit applies. Nothing
+// starts during quiet hours. The background poller queues due runs on every
+// tick.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::error::InstagrapError;
+use crate::state::AppState;
+use crate::status::TodoStatus;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Schedule {
+    Once { at: DateTime<Utc> },
+    /// Standard five-field cron expression, evaluated in local time
+    Cron { expression: String },
+    Interval { minutes: u32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TodoSchedule {
+    pub todo_id: String,
+    pub schedule: Schedule,
+    pub paused: bool,
+    /// None once a one-off schedule has run
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+/// Local-time window ("HH:MM" to "HH:MM") in which nothing starts. It may wrap
+/// past midnight, e.g. 22:00 to 07:00.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<(), InstagrapError> {
+        match self {
+            Self::Once { .. } => Ok(()),
+            Self::Cron { expression } => CronExpr::parse(expression).map(|_| ()),
+            Self::Interval { minutes: 0 } => Err(InstagrapError::validation("Interval must be at least one minute")),
+            Self::Interval { .. } => Ok(()),
+        }
+    }
+
+    /// First run strictly after `after`; one-off schedules only run at `at`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Once { at } => (*at > after).then_some(*at),
+            Self::Cron { expression } => {
+                let cron = CronExpr::parse(expression).ok()?;
+                cron.next_after(&after.with_timezone(&Local)).map(|t| t.with_timezone(&Utc))
+            }
+            Self::Interval { minutes } => Some(after + Duration::minutes(i64::from(*minutes))),
+        }
+    }
+
+    fn is_recurring(&self) -> bool {
+        !matches!(self, Self::Once { .. })
+    }
+}
+
+impl QuietHours {
+    fn bounds(&self) -> Result<(NaiveTime, NaiveTime), InstagrapError> {
+        let parse = |s: &str| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .map_err(|_| InstagrapError::Validation(format!("Invalid quiet hours time \"{}\", expected HH:MM", s)))
+        };
+        let (start, end) = (parse(&self.start)?, parse(&self.end)?);
+        if start == end {
+            return Err(InstagrapError::validation("Quiet hours must not start and end at the same time"));
+        }
+        Ok((start, end))
+    }
+
+    /// Whether `at` (a local time of day) falls inside the quiet window.
+    pub fn contains(&self, at: NaiveTime) -> bool {
+        let Ok((start, end)) = self.bounds() else { return false };
+        if start < end {
+            start <= at && at < end
+        } else {
+            at >= start || at < end
+        }
+    }
+
+    /// `at`, or the end of the quiet window if `at` falls inside it.
+    pub fn defer<Tz: TimeZone>(&self, at: DateTime<Tz>) -> DateTime<Tz> {
+        let Ok((_, end)) = self.bounds() else { return at };
+        if !self.contains(at.time()) {
+            return at;
+        }
+        let date = if at.time() < end { at.date_naive() } else { at.date_naive() + Duration::days(1) };
+        at.timezone().from_local_datetime(&date.and_time(end)).earliest().unwrap_or(at)
+    }
+}
+
+impl AppState {
+    /// Attaches `schedule` to a todo, replacing any previous one.
+    pub fn set_todo_schedule(&mut self, todo_id: &str, schedule: Schedule) -> Result<TodoSchedule, InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Err(InstagrapError::not_found("Todo"));
+        }
+        schedule.validate()?;
+        let now = Utc::now();
+        let next_run_at = match &schedule {
+            // A one-off time in the past runs on the next tick
+            Schedule::Once { at } => Some(*at),
+            recurring => recurring.next_after(now),
+        };
+        let entry = TodoSchedule { todo_id: todo_id.to_string(), schedule, paused: false, next_run_at, last_run_at: None };
+        self.store.save_todo_schedule(&entry)?;
+        self.todo_schedules.retain(|s| s.todo_id != todo_id);
+        self.todo_schedules.push(entry.clone());
+        Ok(entry)
+    }
+
+    pub fn remove_todo_schedule(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        self.store.delete_todo_schedule(todo_id)?;
+        self.todo_schedules.retain(|s| s.todo_id != todo_id);
+        Ok(())
+    }
+
+    /// Pauses or resumes a schedule. Recurring runs missed while paused are
+    /// skipped; the next one is counted from now.
+    pub fn set_schedule_paused(&mut self, todo_id: &str, paused: bool) -> Result<TodoSchedule, InstagrapError> {
+        let index = self
+            .todo_schedules
+            .iter()
+            .position(|s| s.todo_id == todo_id)
+            .ok_or_else(|| InstagrapError::not_found("Schedule"))?;
+        // Changed on a copy so a failed save leaves memory as it was
+        let mut entry = self.todo_schedules[index].clone();
+        if !paused && entry.paused && entry.schedule.is_recurring() {
+            entry.next_run_at = entry.schedule.next_after(Utc::now());
+        }
+        entry.paused = paused;
+        self.store.save_todo_schedule(&entry)?;
+        self.todo_schedules[index] = entry.clone();
+        Ok(entry)
+    }
+
+    pub fn set_quiet_hours(&mut self, quiet_hours: Option<QuietHours>) -> Result<(), InstagrapError> {
+        if let Some(quiet_hours) = &quiet_hours {
+            quiet_hours.bounds()?;
+        }
+        self.store.save_quiet_hours(quiet_hours.as_ref())?;
+        self.quiet_hours = quiet_hours;
+        Ok(())
+    }
+
+    pub fn is_quiet_at(&self, at: DateTime<Utc>) -> bool {
+        self.quiet_hours.as_ref().is_some_and(|q| q.contains(at.with_timezone(&Local).time()))
+    }
+
+    /// The next `limit` runs across all active schedules, soonest first. Runs
+    /// that fall in quiet hours are shown at the end of the quiet window.
+    pub fn upcoming_runs(&self, limit: usize, now: DateTime<Utc>) -> Vec<Value> {
+        let mut runs: Vec<(DateTime<Utc>, DateTime<Utc>, &TodoSchedule)> = Vec::new();
+        for entry in self.todo_schedules.iter().filter(|s| !s.paused) {
+            let mut next = entry.next_run_at;
+            for _ in 0..limit {
+                let Some(at) = next else { break };
+                let starts = match &self.quiet_hours {
+                    Some(quiet) => quiet.defer(at.max(now).with_timezone(&Local)).with_timezone(&Utc),
+                    None => at.max(now),
+                };
+                runs.push((at, starts, entry));
+                next = entry.schedule.next_after(at);
+            }
+        }
+        runs.sort_by_key(|(_, starts, _)| *starts);
+        runs.truncate(limit);
+        runs.into_iter()
+            .map(|(at, starts, entry)| {
+                let target = self.get_todo(&entry.todo_id).map(|t| t.target_account.clone());
+                json!({
+                    "todo_id": entry.todo_id,
+                    "target_account": target,
+                    "scheduled_at": at,
+                    "starts_at": starts,
+                    "delayed_by_quiet_hours": starts > at.max(now),
+                })
+            })
+            .collect()
+    }
+
+    /// Puts every todo whose run is due back to pending and queues it, then
+    /// moves its schedule to the following run. A todo that is still running
+    /// skips the run. Nothing is queued during quiet hours. Returns the queued
+    /// todo ids.
+    pub fn queue_due_runs(&mut self, now: DateTime<Utc>) -> Result<Vec<String>, InstagrapError> {
+        if self.is_quiet_at(now) {
+            return Ok(Vec::new());
+        }
+        let due: Vec<TodoSchedule> = self
+            .todo_schedules
+            .iter()
+            .filter(|s| !s.paused && s.next_run_at.is_some_and(|at| at <= now))
+            .cloned()
+            .collect();
+
+        let mut queued = Vec::new();
+        for mut entry in due {
+            match self.get_todo(&entry.todo_id).map(|t| t.status) {
+                None => {
+                    self.remove_todo_schedule(&entry.todo_id)?;
+                    continue;
+                }
+                Some(TodoStatus::Running) => {
+                    eprintln!("⏭️ Todo {} is still running, skipping its scheduled run", entry.todo_id);
+                }
+                Some(_) => {
+                    self.reset_todo_for_run(&entry.todo_id)?;
+                    self.enqueue_todos(std::slice::from_ref(&entry.todo_id))?;
+                    entry.last_run_at = Some(now);
+                    queued.push(entry.todo_id.clone());
+                    eprintln!("⏰ Scheduled run of todo {} queued", entry.todo_id);
+                }
+            }
+            entry.next_run_at = entry.schedule.next_after(now);
+            self.store.save_todo_schedule(&entry)?;
+            if let Some(slot) = self.todo_schedules.iter_mut().find(|s| s.todo_id == entry.todo_id) {
+                *slot = entry;
+            }
+        }
+        Ok(queued)
+    }
+}
+
+// ===== Cron expressions =====
+
+/// Parsed five-field cron expression (minute, hour, day of month, month, day
+/// of week). Fields accept `*`, numbers, ranges, lists and `/step`; day of
+/// week runs 0-7 with both 0 and 7 meaning Sunday.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronExpr {
+    pub fn parse(expression: &str) -> Result<Self, InstagrapError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(InstagrapError::Validation(format!(
+                "Cron expression \"{}\" must have 5 fields (minute hour day month weekday)",
+                expression
+            )));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, "weekday")?;
+        // 7 is Sunday too
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// First matching minute strictly after `after`, searched up to five
+    /// years ahead.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let tz = after.timezone();
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date();
+        let last = start.date() + Duration::days(366 * 5);
+        while date <= last {
+            if self.matches_day(date) {
+                for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                    for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if candidate < start {
+                            continue;
+                        }
+                        // Times skipped by a DST change don't exist and are left out
+                        if let Some(at) = tz.from_local_datetime(&candidate).earliest() {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        // As in cron, a restricted day and weekday match when either does
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64, InstagrapError> {
+    let invalid = || InstagrapError::Validation(format!("Invalid cron {} field \"{}\"", name, field));
+    let number = |s: &str| s.parse::<u32>().ok().filter(|n| (min..=max).contains(n)).ok_or_else(invalid);
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (number(from)?, number(to)?),
+                // "5/15" means every 15 starting at 5
+                None if part.contains('/') => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
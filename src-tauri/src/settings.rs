@@ -0,0 +1,141 @@
+// Runtime settings: named environments holding the service endpoints, the GCP
+// project and the default login-state bucket. The active environment decides
+// which backend and classifier the clients talk to, without a rebuild.
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{InstagramApiClient, API_BASE};
+use crate::classifier::{HttpClassifierClient, CLASSIFY_API_BASE};
+use crate::error::InstagrapError;
+use crate::state::AppState;
+
+pub const DEFAULT_GCP_PROJECT: &str = "newera-93301";
+pub const DEFAULT_BUCKET: &str = "gs://insta-state/";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Environment {
+    pub name: String,
+    pub api_base: String,
+    pub classifier_base: String,
+    pub gcp_project: String,
+    /// gs:// URL the login state is uploaded to
+    pub default_bucket: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub active_environment: String,
+    pub environments: Vec<Environment>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let prod = Environment {
+            name: "prod".into(),
+            api_base: API_BASE.into(),
+            classifier_base: CLASSIFY_API_BASE.into(),
+            gcp_project: DEFAULT_GCP_PROJECT.into(),
+            default_bucket: DEFAULT_BUCKET.into(),
+        };
+        // Staging starts as a copy of prod until its deployment URLs are filled in
+        let staging = Environment { name: "staging".into(), ..prod.clone() };
+        let local = Environment {
+            name: "local".into(),
+            api_base: "http://127.0.0.1:8080".into(),
+            classifier_base: "http://127.0.0.1:8081".into(),
+            gcp_project: DEFAULT_GCP_PROJECT.into(),
+            default_bucket: "gs://insta-state-local/".into(),
+        };
+        Self { active_environment: prod.name.clone(), environments: vec![prod, staging, local] }
+    }
+}
+
+impl Settings {
+    pub fn environment(&self, name: &str) -> Option<&Environment> {
+        self.environments.iter().find(|e| e.name == name)
+    }
+
+    /// The active environment; `validate` guarantees it exists.
+    pub fn active(&self) -> Result<&Environment, InstagrapError> {
+        self.environment(&self.active_environment)
+            .ok_or_else(|| InstagrapError::not_found(format!("Environment \"{}\"", self.active_environment)))
+    }
+
+    /// Every problem with these settings, empty when they are usable.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.environment(&self.active_environment).is_none() {
+            problems.push(format!("Active environment \"{}\" does not exist", self.active_environment));
+        }
+        for (i, env) in self.environments.iter().enumerate() {
+            let name = env.name.trim();
+            if name.is_empty() {
+                problems.push(format!("Environment #{} has no name", i + 1));
+            } else if self.environments[..i].iter().any(|other| other.name == env.name) {
+                problems.push(format!("Environment \"{}\" is defined twice", name));
+            }
+            for (field, url) in [("api_base", &env.api_base), ("classifier_base", &env.classifier_base)] {
+                match Url::parse(url) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") && !url.cannot_be_a_base() => {}
+                    _ => problems.push(format!("{}: {} must be an http(s) URL", name, field)),
+                }
+            }
+            if env.gcp_project.trim().is_empty() {
+                problems.push(format!("{}: gcp_project is required", name));
+            }
+            if env.default_bucket.strip_prefix("gs://").is_none_or(|b| b.trim_matches('/').is_empty()) {
+                problems.push(format!("{}: default_bucket must be a gs:// URL", name));
+            }
+        }
+        problems
+    }
+
+    pub fn validate(&self) -> Result<(), InstagrapError> {
+        match self.problems() {
+            problems if problems.is_empty() => Ok(()),
+            problems => Err(InstagrapError::Validation(problems.join("; "))),
+        }
+    }
+}
+
+impl AppState {
+    /// Validates and saves `settings`, then points the clients at the active
+    /// environment.
+    pub fn set_settings(&mut self, settings: Settings) -> Result<(), InstagrapError> {
+        settings.validate()?;
+        let (api, classifier) = clients_for(settings.active()?)?;
+        self.store.save_settings(&settings)?;
+        self.api = api;
+        self.classifier = classifier;
+        eprintln!("✅ Using environment {}", settings.active_environment);
+        self.settings = settings;
+        Ok(())
+    }
+
+    pub fn set_active_environment(&mut self, name: &str) -> Result<(), InstagrapError> {
+        if self.settings.environment(name).is_none() {
+            return Err(InstagrapError::not_found(format!("Environment \"{}\"", name)));
+        }
+        let settings = Settings { active_environment: name.to_string(), ..self.settings.clone() };
+        self.set_settings(settings)
+    }
+
+    /// Applies settings loaded from the store; unusable ones fall back to the
+    /// defaults so the app still starts.
+    pub(crate) fn apply_loaded_settings(&mut self, settings: Settings) {
+        let clients = settings.validate().and_then(|_| clients_for(settings.active()?));
+        match clients {
+            Ok((api, classifier)) => {
+                self.api = api;
+                self.classifier = classifier;
+                self.settings = settings;
+            }
+            Err(e) => eprintln!("❌ Ignoring saved settings, using defaults: {}", e),
+        }
+    }
+}
+
+fn clients_for(env: &Environment) -> Result<(InstagramApiClient, HttpClassifierClient), InstagrapError> {
+    Ok((InstagramApiClient::new(&env.api_base)?, HttpClassifierClient::new(&env.classifier_base)?))
+}
@@ -0,0 +1,151 @@
+// In-memory application state backed by the SQLite store.
+//
+// Job, operation, todo and criteria methods live next to their types in
+// `jobs`, `operations`, `todos` and `criteria`; this module only holds the
+// shared state itself and how it is loaded.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::api::InstagramApiClient;
+use crate::classifier::HttpClassifierClient;
+use crate::criteria::SavedCriteriaPreset;
+use crate::error::InstagrapError;
+use crate::jobs::Job;
+use crate::operations::StartingScrape;
+use crate::queue::TodoQueue;
+use crate::reconcile::ReconcileSummary;
+use crate::retry::RetryPolicy;
+use crate::schedule::{QuietHours, TodoSchedule};
+use crate::settings::Settings;
+use crate::store::{Store, WhenLocked};
+use crate::timeline::DEFAULT_STALL_AFTER_MINUTES;
+
+// Structure to store app state
+pub struct AppState {
+    // Todos and direct scrapes with all their runs, see crate::jobs
+    pub jobs: Vec<Job>,
+    pub last_login_gcs_uri: Option<String>,
+    // Saved criteria presets for classifier prompt
+    pub saved_criteria: Vec<SavedCriteriaPreset>,
+    pub active_criteria_id: Option<String>, // if None, use default from API
+    // Backing SQLite store; every mutation writes only the rows it touched
+    pub store: Store,
+    // Backend client used by every scrape and status call
+    pub api: InstagramApiClient,
+    pub classifier: HttpClassifierClient,
+    // Environments and the active one, see crate::settings
+    pub settings: Settings,
+    // Todos waiting for a free slot, see crate::queue
+    pub todo_queue: TodoQueue,
+    // Run schedules attached to todos, see crate::schedule
+    pub todo_schedules: Vec<TodoSchedule>,
+    pub quiet_hours: Option<QuietHours>,
+    // Automatic retries of transient failures, see crate::retry
+    pub retry_policy: Option<RetryPolicy>,
+    // Set at launch until the poller has reconciled running jobs, see crate::reconcile
+    pub reconciliation_pending: bool,
+    pub last_reconciliation: Option<ReconcileSummary>,
+    // Running operations without progress for this long become stalled, see crate::timeline
+    pub stall_after_minutes: u32,
+    // Direct scrapes sent to the backend but not recorded yet, see crate::operations
+    pub starting_scrapes: Vec<StartingScrape>,
+}
+
+impl AppState {
+    /// Empty state backed by an in-memory store.
+    pub fn new() -> Self {
+        Self {
+            jobs: Vec::new(),
+            last_login_gcs_uri: None,
+            saved_criteria: Vec::new(),
+            active_criteria_id: None,
+            store: Store::default(),
+            api: InstagramApiClient::default(),
+            classifier: HttpClassifierClient::default(),
+            settings: Settings::default(),
+            todo_queue: TodoQueue::default(),
+            todo_schedules: Vec::new(),
+            quiet_hours: None,
+            retry_policy: None,
+            reconciliation_pending: false,
+            last_reconciliation: None,
+            stall_after_minutes: DEFAULT_STALL_AFTER_MINUTES,
+            starting_scrapes: Vec::new(),
+        }
+    }
+
+    /// Loads the state from the default database in the user's config directory.
+    pub fn load(when_locked: WhenLocked) -> Result<Self, InstagrapError> {
+        let state = Self::from_store(Store::open_default(when_locked)?)?;
+        eprintln!(
+            "✅ App state loaded ({} jobs, {} todos)",
+            state.jobs.len(),
+            state.jobs.iter().filter(|job| job.listed).count()
+        );
+        Ok(state)
+    }
+
+    /// Loads the state from `store` and keeps writing to it.
+    pub fn from_store(store: Store) -> Result<Self, InstagrapError> {
+        let mut state = store.load_state()?;
+        state.store = store;
+        Ok(state)
+    }
+
+    /// Restores the named backup and reloads everything from it. If the
+    /// restored data can't be loaded, the state from before is put back so
+    /// memory and disk still agree.
+    pub fn restore_backup(&mut self, name: &str) -> Result<(), InstagrapError> {
+        let undo = self.store.restore_backup(name)?;
+        match self.store.load_state() {
+            Ok(mut restored) => {
+                std::mem::swap(&mut restored.store, &mut self.store);
+                *self = restored;
+                Ok(())
+            }
+            Err(e) => {
+                self.store.restore_backup(&undo.name)?;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// AppState shared between concurrent callers (Tauri commands, the automation
+/// server, scripts). Clones share the same state.
+#[derive(Clone)]
+pub struct AppStateManager(Arc<Mutex<AppState>>);
+
+impl AppStateManager {
+    /// Loads the saved state. Whatever was left running is reconciled with the
+    /// backend on the poller's first tick. Fails instead of starting from an
+    /// empty in-memory state, where every change would be lost.
+    pub fn new(when_locked: WhenLocked) -> Result<Self, InstagrapError> {
+        let mut state = AppState::load(when_locked)?;
+        state.reconciliation_pending = true;
+        Ok(Self::with_state(state))
+    }
+
+    pub fn with_state(state: AppState) -> Self {
+        Self(Arc::new(Mutex::new(state)))
+    }
+
+    pub fn lock(&self) -> Result<MutexGuard<'_, AppState>, InstagrapError> {
+        self.0.lock().map_err(|e| InstagrapError::Internal(format!("Failed to lock state: {}", e)))
+    }
+
+    /// The backend client; clones share one connection pool.
+    pub fn api(&self) -> Result<InstagramApiClient, InstagrapError> {
+        Ok(self.lock()?.api.clone())
+    }
+
+    pub fn classifier(&self) -> Result<HttpClassifierClient, InstagrapError> {
+        Ok(self.lock()?.classifier.clone())
+    }
+}
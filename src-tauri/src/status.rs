@@ -0,0 +1,228 @@
+// Status state machines for scraping operations and todos.
+//
+// Both serialize to the same lowercase strings the frontend has always used.
+// Forward transitions follow pending -> running -> completed/failed/cancelled
+// (or lost, when the backend no longer knows a job we thought was running).
+// Operations can also go from running to stalled and back while they make no
+// progress; anything else has to go through an explicit path (retry, reopen,
+// manual completion) so a stray update can't move a finished job backwards.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatus {
+    Pending,
+    Running,
+    Stalled,
+    Completed,
+    Failed,
+    Cancelled,
+    Lost,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    Lost,
+}
+
+/// Returned when a status change is not allowed from the current status.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransitionError {
+    pub entity: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot move {} from \"{}\" to \"{}\"", self.entity, self.from, self.to)
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+fn check<T: Copy>(entity: &'static str, from: &str, to: &str, target: T, allowed: bool) -> Result<T, TransitionError> {
+    if allowed {
+        Ok(target)
+    } else {
+        Err(TransitionError { entity, from: from.to_string(), to: to.to_string() })
+    }
+}
+
+impl OperationStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Stalled => "stalled",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Lost => "lost",
+        }
+    }
+
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled | Self::Lost)
+    }
+
+    /// Still in flight on the backend, whether or not it is making progress.
+    pub fn is_active(self) -> bool {
+        matches!(self, Self::Running | Self::Stalled)
+    }
+
+    // Every status is listed on the left so a new one has to be placed here
+    fn is_forward(self, to: Self) -> bool {
+        use OperationStatus::*;
+        match (self, to) {
+            (Pending, Running | Cancelled) => true,
+            (Running, Completed | Failed | Cancelled | Lost | Stalled) => true,
+            (Stalled, Running | Completed | Failed | Cancelled | Lost) => true,
+            (Pending | Running | Stalled | Completed | Failed | Cancelled | Lost, _) => false,
+        }
+    }
+
+    /// Validates a regular lifecycle step. Re-applying the current status is a no-op.
+    pub fn transition(self, to: Self) -> Result<Self, TransitionError> {
+        let allowed = self == to || self.is_forward(to);
+        check("operation", self.as_str(), to.as_str(), to, allowed)
+    }
+
+    /// Failed, cancelled or lost operations can be started again.
+    pub fn retry(self) -> Result<Self, TransitionError> {
+        let allowed = matches!(self, Self::Failed | Self::Cancelled | Self::Lost);
+        check("operation", self.as_str(), "pending", Self::Pending, allowed)
+    }
+}
+
+impl TodoStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Lost => "lost",
+        }
+    }
+
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled | Self::Lost)
+    }
+
+    // Same lifecycle as operations, which todos don't show as stalled
+    fn is_forward(self, to: Self) -> bool {
+        use TodoStatus::*;
+        match (self, to) {
+            (Pending, Running | Cancelled) => true,
+            (Running, Completed | Failed | Cancelled | Lost) => true,
+            (Pending | Running | Completed | Failed | Cancelled | Lost, _) => false,
+        }
+    }
+
+    /// Validates a regular lifecycle step. Re-applying the current status is a no-op.
+    pub fn transition(self, to: Self) -> Result<Self, TransitionError> {
+        let allowed = self == to || self.is_forward(to);
+        check("todo", self.as_str(), to.as_str(), to, allowed)
+    }
+
+    /// Failed, cancelled or lost todos go back to the queue.
+    pub fn retry(self) -> Result<Self, TransitionError> {
+        let allowed = matches!(self, Self::Failed | Self::Cancelled | Self::Lost);
+        check("todo", self.as_str(), "pending", Self::Pending, allowed)
+    }
+
+    /// A completed todo is put back to pending, e.g. when its manual completion is undone.
+    pub fn reopen(self) -> Result<Self, TransitionError> {
+        let allowed = self == Self::Completed;
+        check("todo", self.as_str(), "pending", Self::Pending, allowed)
+    }
+
+    /// The user may mark any todo that is not currently running as done.
+    pub fn complete_manually(self) -> Result<Self, TransitionError> {
+        let allowed = self != Self::Running;
+        check("todo", self.as_str(), "completed", Self::Completed, allowed)
+    }
+}
+
+/// The status a todo shows for its current run; a stalled run is still running.
+impl From<OperationStatus> for TodoStatus {
+    fn from(status: OperationStatus) -> Self {
+        match status {
+            OperationStatus::Pending => Self::Pending,
+            OperationStatus::Running | OperationStatus::Stalled => Self::Running,
+            OperationStatus::Completed => Self::Completed,
+            OperationStatus::Failed => Self::Failed,
+            OperationStatus::Cancelled => Self::Cancelled,
+            OperationStatus::Lost => Self::Lost,
+        }
+    }
+}
+
+impl From<TodoStatus> for OperationStatus {
+    fn from(status: TodoStatus) -> Self {
+        match status {
+            TodoStatus::Pending => Self::Pending,
+            TodoStatus::Running => Self::Running,
+            TodoStatus::Completed => Self::Completed,
+            TodoStatus::Failed => Self::Failed,
+            TodoStatus::Cancelled => Self::Cancelled,
+            TodoStatus::Lost => Self::Lost,
+        }
+    }
+}
+
+impl fmt::Display for OperationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl FromStr for OperationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "stalled" => Ok(Self::Stalled),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            "lost" => Ok(Self::Lost),
+            other => Err(format!("Unknown operation status: {}", other)),
+        }
+    }
+}
+
+impl FromStr for TodoStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            "lost" => Ok(Self::Lost),
+            other => Err(format!("Unknown todo status: {}", other)),
+        }
+    }
+}
@@ -0,0 +1,1488 @@
+// SQLite-backed persistence for AppState.
+//
+// Every AppState mutation writes only the rows it touched instead of rewriting
+// the whole state, so large result sets no longer make small edits expensive.
+//
+// The desktop app and instagrap-cli each keep the state in memory while they
+// run, so only one of them may have the database open at a time: opening it
+// from the config directory takes a lock file that is held until the store is
+// dropped.
+
+use chrono::{DateTime, Utc};
+use rusqlite::backup::Progress;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Row, MAIN_DB};
+use serde::Serialize;
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::automation::AutomationSettings;
+use crate::backup::{self, BackupInfo, AUTO_BACKUP_INTERVAL_MINUTES, MAX_BACKUPS};
+use crate::criteria::SavedCriteriaPreset;
+use crate::error::InstagrapError;
+use crate::jobs::{Job, JobRun};
+use crate::migrations::{self, LegacyState};
+use crate::queue::TodoQueue;
+use crate::results::ProfileResult;
+use crate::retry::RetryPolicy;
+use crate::schedule::{QuietHours, TodoSchedule};
+use crate::settings::Settings;
+use crate::state::AppState;
+use crate::timeline::OperationEvent;
+
+const DB_FILE_NAME: &str = "instagram_scraper_state.db";
+const LEGACY_JSON_FILE_NAME: &str = "instagram_scraper_state.json";
+const LOCK_FILE_NAME: &str = "instagram_scraper_state.lock";
+
+const META_LAST_LOGIN_GCS_URI: &str = "last_login_gcs_uri";
+const META_ACTIVE_CRITERIA_ID: &str = "active_criteria_id";
+const META_AUTOMATION: &str = "automation_server";
+const META_SETTINGS: &str = "settings";
+const META_TODO_QUEUE: &str = "todo_queue";
+const META_QUIET_HOURS: &str = "quiet_hours";
+const META_RETRY_POLICY: &str = "retry_policy";
+const META_STALL_AFTER_MINUTES: &str = "stall_after_minutes";
+const META_LEGACY_JSON_IMPORTED: &str = "legacy_json_imported";
+
+const OWNER_RUN: &str = "run";
+
+// Database schema migrations, applied in order and tracked in `PRAGMA user_version`.
+// DB_MIGRATIONS[n] upgrades the database from version n to n + 1; append only.
+const DB_MIGRATIONS: &[&str] = &[
+    SCHEMA_V1,
+    SCHEMA_V2_PROFILES,
+    SCHEMA_V3_SCHEDULES,
+    SCHEMA_V4_ATTEMPTS,
+    SCHEMA_V5_EVENTS,
+    SCHEMA_V6_JOBS,
+    SCHEMA_V7_PROFILE_KEYS,
+];
+
+const SCHEMA_V1: &str = r#"
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS operations (
+    operation_id TEXT PRIMARY KEY,
+    target_account TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error_message TEXT,
+    exec_id TEXT,
+    -- NULL means "no results yet", which the frontend tells apart from an empty list
+    results_count INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS todos (
+    id TEXT PRIMARY KEY,
+    target_account TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    bio_agents INTEGER NOT NULL,
+    batch_size INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    completed_at TEXT,
+    operation_id TEXT,
+    exec_id TEXT,
+    error_message TEXT,
+    manually_completed INTEGER NOT NULL DEFAULT 0,
+    criteria_preset_id TEXT,
+    criteria_preset_name TEXT,
+    results_count INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS criteria_presets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    criteria TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS result_profiles (
+    owner_kind TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    username TEXT,
+    payload TEXT NOT NULL,
+    PRIMARY KEY (owner_kind, owner_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_result_profiles_username ON result_profiles(username);
+"#;
+
+// One row per username across all runs. Result lists only reference profiles by
+// username; `payload` is kept just for results that have no username.
+const SCHEMA_V2_PROFILES: &str = r#"
+CREATE TABLE profiles (
+    username TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL
+);
+
+CREATE TABLE profile_sightings (
+    username TEXT NOT NULL,
+    operation_id TEXT NOT NULL,
+    target_account TEXT NOT NULL,
+    seen_at TEXT NOT NULL,
+    PRIMARY KEY (username, operation_id)
+);
+
+CREATE INDEX idx_profile_sightings_target ON profile_sightings(target_account);
+
+INSERT OR IGNORE INTO profiles (username, data, first_seen_at, last_seen_at)
+SELECT r.username, r.payload, MIN(o.started_at), MAX(o.started_at)
+FROM result_profiles r
+JOIN operations o ON r.owner_kind = 'operation' AND o.operation_id = r.owner_id
+WHERE r.username IS NOT NULL
+GROUP BY r.username;
+
+INSERT OR IGNORE INTO profiles (username, data, first_seen_at, last_seen_at)
+SELECT r.username, r.payload, MIN(COALESCE(t.started_at, t.created_at)), MAX(COALESCE(t.started_at, t.created_at))
+FROM result_profiles r
+JOIN todos t ON r.owner_kind = 'todo' AND t.id = r.owner_id
+WHERE r.username IS NOT NULL
+GROUP BY r.username;
+
+INSERT OR IGNORE INTO profile_sightings (username, operation_id, target_account, seen_at)
+SELECT r.username, o.operation_id, o.target_account, o.started_at
+FROM result_profiles r
+JOIN operations o ON r.owner_kind = 'operation' AND o.operation_id = r.owner_id
+WHERE r.username IS NOT NULL;
+
+CREATE TABLE result_profiles_v2 (
+    owner_kind TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    username TEXT,
+    payload TEXT,
+    PRIMARY KEY (owner_kind, owner_id, position)
+);
+
+INSERT INTO result_profiles_v2 (owner_kind, owner_id, position, username, payload)
+SELECT owner_kind, owner_id, position, username, CASE WHEN username IS NULL THEN payload END
+FROM result_profiles;
+
+DROP TABLE result_profiles;
+ALTER TABLE result_profiles_v2 RENAME TO result_profiles;
+CREATE INDEX idx_result_profiles_username ON result_profiles(username);
+"#;
+
+// One schedule per todo; `schedule` holds the serialized crate::schedule::Schedule
+const SCHEMA_V3_SCHEDULES: &str = r#"
+CREATE TABLE todo_schedules (
+    todo_id TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    paused INTEGER NOT NULL DEFAULT 0,
+    next_run_at TEXT,
+    last_run_at TEXT
+);
+"#;
+
+// Finished runs of each todo, see crate::retry
+const SCHEMA_V4_ATTEMPTS: &str = r#"
+ALTER TABLE todos ADD COLUMN next_retry_at TEXT;
+
+CREATE TABLE todo_attempts (
+    todo_id TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    started_at TEXT,
+    finished_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    operation_id TEXT,
+    exec_id TEXT,
+    error_message TEXT,
+    retryable INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (todo_id, attempt)
+);
+"#;
+
+// Timeline of each operation, see crate::timeline; `progress` holds serialized counters
+const SCHEMA_V5_EVENTS: &str = r#"
+CREATE TABLE operation_events (
+    operation_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    at TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    status TEXT,
+    progress TEXT,
+    message TEXT,
+    polls INTEGER NOT NULL DEFAULT 0,
+    last_polled_at TEXT,
+    PRIMARY KEY (operation_id, seq)
+);
+"#;
+
+// Todos and operations become jobs with runs, see crate::jobs. Todos turn into
+// listed jobs whose attempts, and current run if it isn't an attempt yet, are
+// runs that take status and results from their operation. Operations no todo
+// ran become unlisted jobs with a single run. Results are re-keyed to their
+// run ("job_id:attempt"); a current run whose operation has none keeps the
+// todo's copy.
+const SCHEMA_V6_JOBS: &str = r#"
+CREATE TABLE jobs (
+    id TEXT PRIMARY KEY,
+    listed INTEGER NOT NULL,
+    target_account TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    bio_agents INTEGER NOT NULL,
+    batch_size INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    completed_at TEXT,
+    manually_completed INTEGER NOT NULL DEFAULT 0,
+    criteria_preset_id TEXT,
+    criteria_preset_name TEXT,
+    next_retry_at TEXT,
+    current_attempt INTEGER
+);
+
+CREATE TABLE job_runs (
+    job_id TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    operation_id TEXT,
+    exec_id TEXT,
+    started_at TEXT NOT NULL,
+    finished_at TEXT,
+    status TEXT NOT NULL,
+    error_message TEXT,
+    retryable INTEGER NOT NULL DEFAULT 0,
+    -- NULL means "no results yet", which the frontend tells apart from an empty list
+    results_count INTEGER,
+    PRIMARY KEY (job_id, attempt)
+);
+
+CREATE INDEX idx_job_runs_operation ON job_runs(operation_id);
+
+CREATE TABLE run_events (
+    job_id TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    seq INTEGER NOT NULL,
+    at TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    status TEXT,
+    progress TEXT,
+    message TEXT,
+    polls INTEGER NOT NULL DEFAULT 0,
+    last_polled_at TEXT,
+    PRIMARY KEY (job_id, attempt, seq)
+);
+
+INSERT INTO jobs (id, listed, target_account, target_count, bio_agents, batch_size, status, created_at,
+                  completed_at, manually_completed, criteria_preset_id, criteria_preset_name, next_retry_at)
+SELECT id, 1, target_account, target_count, bio_agents, batch_size, status, created_at,
+       completed_at, manually_completed, criteria_preset_id, criteria_preset_name, next_retry_at
+FROM todos ORDER BY rowid;
+
+INSERT INTO job_runs (job_id, attempt, operation_id, exec_id, started_at, finished_at, status, error_message, retryable)
+SELECT a.todo_id, a.attempt, a.operation_id, COALESCE(a.exec_id, o.exec_id), COALESCE(a.started_at, o.started_at, a.finished_at),
+       a.finished_at, a.status, a.error_message, a.retryable
+FROM todo_attempts a
+JOIN todos t ON t.id = a.todo_id
+LEFT JOIN operations o ON o.operation_id = a.operation_id;
+
+-- The run a todo is on, unless it already ended and was recorded as an attempt
+INSERT INTO job_runs (job_id, attempt, operation_id, exec_id, started_at, finished_at, status, error_message)
+SELECT t.id,
+       (SELECT COALESCE(MAX(a.attempt), 0) + 1 FROM todo_attempts a WHERE a.todo_id = t.id),
+       t.operation_id,
+       COALESCE(t.exec_id, o.exec_id),
+       COALESCE(t.started_at, o.started_at, t.created_at),
+       CASE WHEN t.status = 'running' AND COALESCE(o.status, 'running') IN ('running', 'stalled') THEN NULL
+            ELSE COALESCE(t.completed_at, t.started_at, o.started_at, t.created_at) END,
+       CASE WHEN t.status = 'running' THEN COALESCE(o.status, 'running') ELSE t.status END,
+       COALESCE(t.error_message, o.error_message)
+FROM todos t
+LEFT JOIN operations o ON o.operation_id = t.operation_id
+WHERE t.status != 'pending'
+  AND (t.started_at IS NOT NULL OR t.operation_id IS NOT NULL)
+  AND NOT EXISTS (
+      SELECT 1 FROM todo_attempts a
+      WHERE a.todo_id = t.id AND a.started_at IS t.started_at AND a.operation_id IS t.operation_id
+  );
+
+UPDATE jobs SET current_attempt = (SELECT MAX(r.attempt) FROM job_runs r WHERE r.job_id = jobs.id)
+WHERE id IN (
+    SELECT id FROM todos
+    WHERE status != 'pending' AND (started_at IS NOT NULL OR operation_id IS NOT NULL)
+);
+
+INSERT INTO jobs (id, listed, target_account, target_count, bio_agents, batch_size, status, created_at,
+                  completed_at, current_attempt)
+SELECT o.operation_id, 0, o.target_account, o.target_count, 1, 10,
+       CASE o.status WHEN 'stalled' THEN 'running' ELSE o.status END,
+       o.started_at,
+       CASE WHEN o.status IN ('completed', 'failed', 'cancelled', 'lost') THEN
+           COALESCE((SELECT MAX(e.at) FROM operation_events e WHERE e.operation_id = o.operation_id), o.started_at)
+       END,
+       1
+FROM operations o
+WHERE NOT EXISTS (SELECT 1 FROM job_runs r WHERE r.operation_id = o.operation_id)
+ORDER BY o.rowid;
+
+INSERT INTO job_runs (job_id, attempt, operation_id, exec_id, started_at, finished_at, status, error_message)
+SELECT o.operation_id, 1, o.operation_id, o.exec_id, o.started_at, j.completed_at, o.status, o.error_message
+FROM operations o
+JOIN jobs j ON j.id = o.operation_id AND j.listed = 0;
+
+UPDATE job_runs SET results_count = COALESCE(
+    (SELECT o.results_count FROM operations o WHERE o.operation_id = job_runs.operation_id),
+    (SELECT t.results_count FROM todos t JOIN jobs j ON j.id = t.id
+     WHERE t.id = job_runs.job_id AND j.current_attempt = job_runs.attempt)
+);
+
+-- Todos that were still running when their operation ended take its outcome
+UPDATE jobs SET
+    status = (SELECT r.status FROM job_runs r WHERE r.job_id = jobs.id AND r.attempt = jobs.current_attempt),
+    completed_at = COALESCE(completed_at,
+        (SELECT r.finished_at FROM job_runs r WHERE r.job_id = jobs.id AND r.attempt = jobs.current_attempt))
+WHERE status = 'running' AND EXISTS (
+    SELECT 1 FROM job_runs r
+    WHERE r.job_id = jobs.id AND r.attempt = jobs.current_attempt
+      AND r.status IN ('completed', 'failed', 'cancelled', 'lost')
+);
+
+INSERT INTO run_events (job_id, attempt, seq, at, kind, status, progress, message, polls, last_polled_at)
+SELECT r.job_id, r.attempt, e.seq, e.at, e.kind, e.status, e.progress, e.message, e.polls, e.last_polled_at
+FROM operation_events e
+JOIN job_runs r ON r.operation_id = e.operation_id;
+
+CREATE TABLE result_profiles_v6 (
+    owner_kind TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    username TEXT,
+    payload TEXT,
+    PRIMARY KEY (owner_kind, owner_id, position)
+);
+
+INSERT INTO result_profiles_v6 (owner_kind, owner_id, position, username, payload)
+SELECT 'run', r.job_id || ':' || r.attempt, p.position, p.username, p.payload
+FROM job_runs r
+JOIN operations o ON o.operation_id = r.operation_id AND o.results_count IS NOT NULL
+JOIN result_profiles p ON p.owner_kind = 'operation' AND p.owner_id = r.operation_id;
+
+INSERT INTO result_profiles_v6 (owner_kind, owner_id, position, username, payload)
+SELECT 'run', r.job_id || ':' || r.attempt, p.position, p.username, p.payload
+FROM job_runs r
+JOIN jobs j ON j.id = r.job_id AND j.current_attempt = r.attempt
+JOIN result_profiles p ON p.owner_kind = 'todo' AND p.owner_id = r.job_id
+WHERE NOT EXISTS (
+    SELECT 1 FROM operations o WHERE o.operation_id = r.operation_id AND o.results_count IS NOT NULL
+);
+
+DROP TABLE result_profiles;
+ALTER TABLE result_profiles_v6 RENAME TO result_profiles;
+CREATE INDEX idx_result_profiles_username ON result_profiles(username);
+
+DROP TABLE operation_events;
+DROP TABLE todo_attempts;
+DROP TABLE todos;
+DROP TABLE operations;
+"#;
+
+// Profiles are keyed by the lowercased username, see `profile_key`. Rows that
+// only differed in case are merged: the newest data, the earliest first and
+// latest last sighting, and one sighting per run.
+const SCHEMA_V7_PROFILE_KEYS: &str = r#"
+CREATE TABLE profiles_v7 (
+    username TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL
+);
+
+INSERT INTO profiles_v7 (username, data, first_seen_at, last_seen_at)
+SELECT LOWER(p.username),
+    (SELECT n.data FROM profiles n WHERE LOWER(n.username) = LOWER(p.username) ORDER BY n.last_seen_at DESC LIMIT 1),
+    MIN(p.first_seen_at), MAX(p.last_seen_at)
+FROM profiles p
+GROUP BY LOWER(p.username);
+
+DROP TABLE profiles;
+ALTER TABLE profiles_v7 RENAME TO profiles;
+
+CREATE TABLE profile_sightings_v7 (
+    username TEXT NOT NULL,
+    operation_id TEXT NOT NULL,
+    target_account TEXT NOT NULL,
+    seen_at TEXT NOT NULL,
+    PRIMARY KEY (username, operation_id)
+);
+
+INSERT OR IGNORE INTO profile_sightings_v7 (username, operation_id, target_account, seen_at)
+SELECT LOWER(username), operation_id, target_account, seen_at
+FROM profile_sightings
+ORDER BY seen_at;
+
+DROP TABLE profile_sightings;
+ALTER TABLE profile_sightings_v7 RENAME TO profile_sightings;
+CREATE INDEX idx_profile_sightings_target ON profile_sightings(target_account);
+
+UPDATE result_profiles SET username = LOWER(username) WHERE username IS NOT NULL;
+"#;
+
+/// A profile from the global store with every run that produced it.
+#[derive(Serialize)]
+pub struct StoredProfile {
+    /// Lowercased key; `data.username` keeps the casing last reported
+    pub username: String,
+    pub data: ProfileResult,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    pub sightings: Vec<ProfileSighting>,
+}
+
+#[derive(Serialize)]
+pub struct ProfileSighting {
+    pub operation_id: String,
+    pub target_account: String,
+    pub seen_at: String,
+}
+
+pub struct Store {
+    conn: Connection,
+    // None for in-memory stores
+    path: Option<PathBuf>,
+    // Held while the store is open, see `open_in_dir`
+    _lock: Option<File>,
+}
+
+/// What opening the state database does while another process has it open.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WhenLocked {
+    /// Block until the other process is done, e.g. the desktop app waiting
+    /// for a CLI command to finish
+    Wait,
+    /// Fail right away, e.g. a CLI command while the desktop app is open
+    Fail,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::open_in_memory().expect("failed to open in-memory state store")
+    }
+}
+
+impl Store {
+    pub fn config_dir() -> Result<PathBuf, InstagrapError> {
+        let config_dir = dirs::config_dir().ok_or_else(|| InstagrapError::persistence("Could not find config directory"))?;
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to create config directory: {}", e)))?;
+        Ok(config_dir)
+    }
+
+    /// Opens the database in the user's config directory, see `open_in_dir`.
+    pub fn open_default(when_locked: WhenLocked) -> Result<Self, InstagrapError> {
+        Self::open_in_dir(&Self::config_dir()?, when_locked)
+    }
+
+    /// Opens the database in `config_dir` for this process only. A database
+    /// that fails its integrity check is quarantined and replaced by the newest
+    /// healthy backup.
+    pub fn open_in_dir(config_dir: &Path, when_locked: WhenLocked) -> Result<Self, InstagrapError> {
+        let lock = lock_state(config_dir, when_locked)?;
+        let db_path = config_dir.join(DB_FILE_NAME);
+        let mut store = match Self::open(&db_path) {
+            Ok(store) => store,
+            Err(e) if db_path.exists() && check_file(&db_path).is_err() => {
+                eprintln!("❌ State database is corrupt: {}", e);
+                backup::quarantine(&db_path)?;
+                Self::recover_from_backup(config_dir, &db_path)?
+            }
+            Err(e) => return Err(e),
+        };
+        store._lock = Some(lock);
+        store.create_backup_if_due()?;
+        store.import_legacy_json(&config_dir.join(LEGACY_JSON_FILE_NAME))?;
+        Ok(store)
+    }
+
+    pub fn open(path: &Path) -> Result<Self, InstagrapError> {
+        let conn = Connection::open(path)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to open state database {}: {}", path.display(), e)))?;
+        quick_check(&conn)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = FULL;")
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to configure state database: {}", e)))?;
+        Self::init(conn, Some(path.to_path_buf()))
+    }
+
+    pub fn open_in_memory() -> Result<Self, InstagrapError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to open in-memory database: {}", e)))?;
+        Self::init(conn, None)
+    }
+
+    fn init(conn: Connection, path: Option<PathBuf>) -> Result<Self, InstagrapError> {
+        migrate_database(&conn)?;
+        Ok(Self { conn, path, _lock: None })
+    }
+
+    fn recover_from_backup(config_dir: &Path, db_path: &Path) -> Result<Self, InstagrapError> {
+        let dir = backup::backup_dir(config_dir)?;
+        for candidate in backup::list_backups(config_dir)? {
+            let source = dir.join(&candidate.name);
+            if let Err(e) = check_file(&source) {
+                eprintln!("⚠️ Skipping unusable backup {}: {}", candidate.name, e);
+                continue;
+            }
+            backup::replace_database(&source, db_path)?;
+            eprintln!("✅ Recovered state from backup {}", candidate.name);
+            return Self::open(db_path);
+        }
+        eprintln!("⚠️ No usable backup found, starting with an empty state");
+        Self::open(db_path)
+    }
+
+    fn db_path(&self) -> Result<&Path, InstagrapError> {
+        self.path.as_deref().ok_or_else(|| InstagrapError::Persistence("State is not persisted to disk".to_string()))
+    }
+
+    fn config_dir_of(&self) -> Result<&Path, InstagrapError> {
+        self.db_path()?
+            .parent()
+            .ok_or_else(|| InstagrapError::Persistence("State database has no parent directory".to_string()))
+    }
+
+    // ===== Backups =====
+
+    /// Writes a consistent snapshot of the database into the backup directory
+    /// and prunes old backups.
+    pub fn create_backup(&self) -> Result<BackupInfo, InstagrapError> {
+        let info = self.snapshot()?;
+        backup::prune_backups(self.config_dir_of()?, MAX_BACKUPS)?;
+        Ok(info)
+    }
+
+    /// Like `create_backup`, but only when the newest backup is older than
+    /// `AUTO_BACKUP_INTERVAL_MINUTES`. Returns the new backup, if one was taken.
+    pub fn create_backup_if_due(&self) -> Result<Option<BackupInfo>, InstagrapError> {
+        let newest = self.list_backups()?.into_iter().next();
+        let taken_at = newest.and_then(|b| DateTime::parse_from_rfc3339(&b.created_at).ok());
+        let due = taken_at.is_none_or(|at| Utc::now().signed_duration_since(at) >= chrono::Duration::minutes(AUTO_BACKUP_INTERVAL_MINUTES));
+        if !due {
+            return Ok(None);
+        }
+        self.create_backup().map(Some)
+    }
+
+    fn snapshot(&self) -> Result<BackupInfo, InstagrapError> {
+        let dir = backup::backup_dir(self.config_dir_of()?)?;
+        let dest = backup::new_backup_path(&dir);
+        let tmp = backup::with_suffix(&dest, ".tmp");
+        self.conn
+            .execute("VACUUM INTO ?1", params![tmp.to_string_lossy()])
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to write backup: {}", e)))?;
+        fs::rename(&tmp, &dest).map_err(|e| InstagrapError::Persistence(format!("Failed to finalize backup: {}", e)))?;
+        let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        backup::list_backups(self.config_dir_of()?)?
+            .into_iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| InstagrapError::Persistence(format!("Backup {} disappeared after writing", name)))
+    }
+
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, InstagrapError> {
+        backup::list_backups(self.config_dir_of()?)
+    }
+
+    /// Replaces the live database with the named backup. The current state is
+    /// snapshotted first so a restore can itself be undone; that snapshot is
+    /// returned. The backup is copied into the open connection, so the store
+    /// keeps writing to the same file whether or not the restore succeeds.
+    pub fn restore_backup(&mut self, name: &str) -> Result<BackupInfo, InstagrapError> {
+        let config_dir = self.config_dir_of()?.to_path_buf();
+        let source = backup::resolve_backup(&config_dir, name)?;
+        check_file(&source)?;
+        let undo = self.snapshot()?;
+
+        self.conn
+            .restore(MAIN_DB, &source, None::<fn(Progress)>)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to restore backup {}: {}", name, e)))?;
+        // Backups taken by an older version still need its migrations
+        migrate_database(&self.conn)?;
+
+        backup::prune_backups(&config_dir, MAX_BACKUPS)?;
+        eprintln!("✅ Restored state from backup {}", name);
+        Ok(undo)
+    }
+
+    // ===== Loading =====
+
+    pub fn load_state(&self) -> Result<AppState, InstagrapError> {
+        let mut state = AppState::new();
+        state.jobs = self.load_jobs()?;
+        state.saved_criteria = self.load_criteria_presets()?;
+        state.last_login_gcs_uri = self.get_meta(META_LAST_LOGIN_GCS_URI)?;
+        state.active_criteria_id = self.get_meta(META_ACTIVE_CRITERIA_ID)?;
+        if let Some(settings) = self.load_settings()? {
+            state.apply_loaded_settings(settings);
+        }
+        state.todo_queue = self.load_todo_queue()?.unwrap_or_default();
+        state.todo_schedules = self.load_todo_schedules()?;
+        state.quiet_hours = self.load_quiet_hours()?;
+        state.retry_policy = self.load_retry_policy()?;
+        if let Some(minutes) = self.load_stall_after_minutes()? {
+            state.stall_after_minutes = minutes;
+        }
+        Ok(state)
+    }
+
+    fn load_jobs(&self) -> Result<Vec<Job>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, listed, target_account, target_count, bio_agents, batch_size, status,
+                        created_at, completed_at, manually_completed, criteria_preset_id,
+                        criteria_preset_name, next_retry_at, current_attempt
+                 FROM jobs ORDER BY rowid",
+            )
+            .map_err(db_err)?;
+        let jobs = stmt
+            .query_map([], job_from_row)
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        jobs.into_iter()
+            .map(|mut job| {
+                job.runs = self.load_job_runs(&job.id)?;
+                Ok(job)
+            })
+            .collect()
+    }
+
+    fn load_job_runs(&self, job_id: &str) -> Result<Vec<JobRun>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT attempt, operation_id, exec_id, started_at, finished_at, status,
+                        error_message, retryable, results_count
+                 FROM job_runs WHERE job_id = ?1 ORDER BY attempt",
+            )
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![job_id], |row| {
+                Ok((
+                    JobRun {
+                        attempt: row.get(0)?,
+                        operation_id: row.get(1)?,
+                        exec_id: row.get(2)?,
+                        started_at: row.get(3)?,
+                        finished_at: row.get(4)?,
+                        status: parse_column(row, 5)?,
+                        results: None,
+                        error_message: row.get(6)?,
+                        retryable: row.get(7)?,
+                        events: Vec::new(),
+                    },
+                    row.get::<_, Option<i64>>(8)?,
+                ))
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        rows.into_iter()
+            .map(|(mut run, results_count)| {
+                let key = run.key(job_id);
+                if results_count.is_some() {
+                    run.results = Some(self.load_results(OWNER_RUN, &key)?);
+                }
+                run.events = self.load_run_events(job_id, run.attempt)?;
+                Ok(run)
+            })
+            .collect()
+    }
+
+    fn load_run_events(&self, job_id: &str, attempt: u32) -> Result<Vec<OperationEvent>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT seq, at, kind, status, progress, message, polls, last_polled_at
+                 FROM run_events WHERE job_id = ?1 AND attempt = ?2 ORDER BY seq",
+            )
+            .map_err(db_err)?;
+        let events = stmt
+            .query_map(params![job_id, attempt], |row| {
+                let progress: Option<String> = row.get(4)?;
+                Ok(OperationEvent {
+                    seq: row.get(0)?,
+                    at: row.get(1)?,
+                    kind: parse_column(row, 2)?,
+                    status: row.get(3)?,
+                    progress: progress.and_then(|p| serde_json::from_str(&p).ok()),
+                    message: row.get(5)?,
+                    polls: row.get(6)?,
+                    last_polled_at: row.get(7)?,
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(events)
+    }
+
+    fn load_criteria_presets(&self) -> Result<Vec<SavedCriteriaPreset>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, name, criteria, created_at, updated_at
+                 FROM criteria_presets ORDER BY rowid",
+            )
+            .map_err(db_err)?;
+        let presets = stmt
+            .query_map([], |row| {
+                Ok(SavedCriteriaPreset {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    criteria: row.get(2)?,
+                    created_at: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(presets)
+    }
+
+    fn load_results(&self, owner_kind: &str, owner_id: &str) -> Result<Vec<ProfileResult>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT COALESCE(r.payload, p.data) FROM result_profiles r
+                 LEFT JOIN profiles p ON p.username = r.username
+                 WHERE r.owner_kind = ?1 AND r.owner_id = ?2 ORDER BY r.position",
+            )
+            .map_err(db_err)?;
+        let payloads = stmt
+            .query_map(params![owner_kind, owner_id], |row| row.get::<_, String>(0))
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        payloads
+            .iter()
+            .map(|p| serde_json::from_str(p).map_err(|e| InstagrapError::Persistence(format!("Failed to parse stored result: {}", e))))
+            .collect()
+    }
+
+    // ===== Jobs =====
+
+    /// Writes the job and its runs, along with the newest run's events. With
+    /// `with_results` the newest run's stored results are replaced too; older
+    /// runs never change once a new one has started.
+    pub fn save_job(&self, job: &Job, with_results: bool) -> Result<(), InstagrapError> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        write_job(&tx, job, with_results)?;
+        tx.commit().map_err(db_err)
+    }
+
+    /// Deletes a job with all its runs; its profiles stay in the profile store.
+    pub fn delete_job(&self, job: &Job) -> Result<(), InstagrapError> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        for run in &job.runs {
+            delete_run(&tx, &job.id, run)?;
+        }
+        tx.execute("DELETE FROM jobs WHERE id = ?1", params![job.id])
+            .map_err(db_err)?;
+        tx.execute("DELETE FROM todo_schedules WHERE todo_id = ?1", params![job.id])
+            .map_err(db_err)?;
+        tx.commit().map_err(db_err)
+    }
+
+    /// Deletes one run of a job from the history.
+    pub fn delete_job_run(&self, job_id: &str, run: &JobRun) -> Result<(), InstagrapError> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        delete_run(&tx, job_id, run)?;
+        tx.commit().map_err(db_err)
+    }
+
+    // ===== Criteria presets =====
+
+    pub fn save_criteria_preset(&self, preset: &SavedCriteriaPreset) -> Result<(), InstagrapError> {
+        self.conn
+            .execute(
+                "INSERT INTO criteria_presets (id, name, criteria, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(id) DO UPDATE SET
+                    name = excluded.name,
+                    criteria = excluded.criteria,
+                    created_at = excluded.created_at,
+                    updated_at = excluded.updated_at",
+                params![preset.id, preset.name, preset.criteria, preset.created_at, preset.updated_at],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    pub fn delete_criteria_preset(&self, id: &str) -> Result<(), InstagrapError> {
+        self.conn
+            .execute("DELETE FROM criteria_presets WHERE id = ?1", params![id])
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    pub fn save_active_criteria_id(&self, id: Option<&str>) -> Result<(), InstagrapError> {
+        self.set_meta(META_ACTIVE_CRITERIA_ID, id)
+    }
+
+    // ===== Automation server =====
+
+    pub fn load_automation_settings(&self) -> Result<Option<AutomationSettings>, InstagrapError> {
+        self.get_meta(META_AUTOMATION)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| InstagrapError::Persistence(format!("Invalid automation settings: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_automation_settings(&self, settings: &AutomationSettings) -> Result<(), InstagrapError> {
+        let value = serde_json::to_string(settings)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize automation settings: {}", e)))?;
+        self.set_meta(META_AUTOMATION, Some(&value))
+    }
+
+    // ===== Settings =====
+
+    pub fn load_settings(&self) -> Result<Option<Settings>, InstagrapError> {
+        self.get_meta(META_SETTINGS)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| InstagrapError::Persistence(format!("Invalid settings: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_settings(&self, settings: &Settings) -> Result<(), InstagrapError> {
+        let value = serde_json::to_string(settings)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize settings: {}", e)))?;
+        self.set_meta(META_SETTINGS, Some(&value))
+    }
+
+    // ===== Todo queue =====
+
+    pub fn load_todo_queue(&self) -> Result<Option<TodoQueue>, InstagrapError> {
+        self.get_meta(META_TODO_QUEUE)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| InstagrapError::Persistence(format!("Invalid todo queue: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_todo_queue(&self, queue: &TodoQueue) -> Result<(), InstagrapError> {
+        let value = serde_json::to_string(queue)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize todo queue: {}", e)))?;
+        self.set_meta(META_TODO_QUEUE, Some(&value))
+    }
+
+    // ===== Schedules =====
+
+    fn load_todo_schedules(&self) -> Result<Vec<TodoSchedule>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT todo_id, schedule, paused, next_run_at, last_run_at FROM todo_schedules ORDER BY rowid")
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        let invalid = |e: String| InstagrapError::Persistence(format!("Invalid stored schedule: {}", e));
+        let time = |t: Option<String>| {
+            t.map(|t| DateTime::parse_from_rfc3339(&t).map(|t| t.with_timezone(&Utc)).map_err(|e| invalid(e.to_string())))
+                .transpose()
+        };
+        rows.into_iter()
+            .map(|(todo_id, schedule, paused, next_run_at, last_run_at)| {
+                Ok(TodoSchedule {
+                    todo_id,
+                    schedule: serde_json::from_str(&schedule).map_err(|e| invalid(e.to_string()))?,
+                    paused,
+                    next_run_at: time(next_run_at)?,
+                    last_run_at: time(last_run_at)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn save_todo_schedule(&self, entry: &TodoSchedule) -> Result<(), InstagrapError> {
+        let schedule = serde_json::to_string(&entry.schedule)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize schedule: {}", e)))?;
+        self.conn
+            .execute(
+                "INSERT INTO todo_schedules (todo_id, schedule, paused, next_run_at, last_run_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(todo_id) DO UPDATE SET
+                    schedule = excluded.schedule,
+                    paused = excluded.paused,
+                    next_run_at = excluded.next_run_at,
+                    last_run_at = excluded.last_run_at",
+                params![
+                    entry.todo_id,
+                    schedule,
+                    entry.paused,
+                    entry.next_run_at.map(|t| t.to_rfc3339()),
+                    entry.last_run_at.map(|t| t.to_rfc3339()),
+                ],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    pub fn delete_todo_schedule(&self, todo_id: &str) -> Result<(), InstagrapError> {
+        self.conn
+            .execute("DELETE FROM todo_schedules WHERE todo_id = ?1", params![todo_id])
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    fn load_quiet_hours(&self) -> Result<Option<QuietHours>, InstagrapError> {
+        self.get_meta(META_QUIET_HOURS)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| InstagrapError::Persistence(format!("Invalid quiet hours: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_quiet_hours(&self, quiet_hours: Option<&QuietHours>) -> Result<(), InstagrapError> {
+        let value = quiet_hours
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize quiet hours: {}", e)))?;
+        self.set_meta(META_QUIET_HOURS, value.as_deref())
+    }
+
+    // ===== Retry policy =====
+
+    fn load_retry_policy(&self) -> Result<Option<RetryPolicy>, InstagrapError> {
+        self.get_meta(META_RETRY_POLICY)?
+            .map(|v| serde_json::from_str(&v).map_err(|e| InstagrapError::Persistence(format!("Invalid retry policy: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_retry_policy(&self, policy: Option<&RetryPolicy>) -> Result<(), InstagrapError> {
+        let value = policy
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize retry policy: {}", e)))?;
+        self.set_meta(META_RETRY_POLICY, value.as_deref())
+    }
+
+    // ===== Stall threshold =====
+
+    fn load_stall_after_minutes(&self) -> Result<Option<u32>, InstagrapError> {
+        self.get_meta(META_STALL_AFTER_MINUTES)?
+            .map(|v| v.parse().map_err(|e| InstagrapError::Persistence(format!("Invalid stall threshold: {}", e))))
+            .transpose()
+    }
+
+    pub fn save_stall_after_minutes(&self, minutes: u32) -> Result<(), InstagrapError> {
+        self.set_meta(META_STALL_AFTER_MINUTES, Some(&minutes.to_string()))
+    }
+
+    // ===== Meta =====
+
+    fn get_meta(&self, key: &str) -> Result<Option<String>, InstagrapError> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(db_err)
+    }
+
+    fn set_meta(&self, key: &str, value: Option<&str>) -> Result<(), InstagrapError> {
+        match value {
+            Some(v) => self.conn.execute(
+                "INSERT INTO meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, v],
+            ),
+            None => self.conn.execute("DELETE FROM meta WHERE key = ?1", params![key]),
+        }
+        .map_err(db_err)?;
+        Ok(())
+    }
+
+    // ===== Profile store =====
+
+    /// Profiles matching `query` (substring of the username) and, optionally,
+    /// seen while scraping `target_account`. Most recently seen first.
+    pub fn search_profiles(
+        &self,
+        query: Option<&str>,
+        target_account: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<StoredProfile>, u64), InstagrapError> {
+        let pattern = format!("%{}%", escape_like(&query.unwrap_or("").trim().to_lowercase()));
+        let filter = "FROM profiles p
+             WHERE LOWER(p.username) LIKE ?1 ESCAPE '\\'
+               AND (?2 IS NULL OR EXISTS (
+                    SELECT 1 FROM profile_sightings s
+                    WHERE s.username = p.username AND LOWER(s.target_account) = LOWER(?2)))";
+
+        let total: u64 = self
+            .conn
+            .query_row(&format!("SELECT COUNT(*) {}", filter), params![pattern, target_account], |row| row.get(0))
+            .map_err(db_err)?;
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT p.username, p.data, p.first_seen_at, p.last_seen_at {}
+                 ORDER BY p.last_seen_at DESC, p.username LIMIT ?3 OFFSET ?4",
+                filter
+            ))
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map(params![pattern, target_account, limit, offset], profile_from_row)
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+
+        let profiles = rows
+            .into_iter()
+            .map(|row| self.hydrate_profile(row))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((profiles, total))
+    }
+
+    pub fn get_profile(&self, username: &str) -> Result<Option<StoredProfile>, InstagrapError> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT username, data, first_seen_at, last_seen_at FROM profiles WHERE username = ?1",
+                params![profile_key(username)],
+                profile_from_row,
+            )
+            .optional()
+            .map_err(db_err)?;
+        row.map(|row| self.hydrate_profile(row)).transpose()
+    }
+
+    /// Returns the subset of `usernames` that was already collected by an earlier run.
+    pub fn known_usernames(&self, usernames: &[String]) -> Result<Vec<String>, InstagrapError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT 1 FROM profiles WHERE username = ?1")
+            .map_err(db_err)?;
+        let mut known = Vec::new();
+        for username in usernames {
+            if stmt.exists(params![profile_key(username)]).map_err(db_err)? {
+                known.push(username.clone());
+            }
+        }
+        Ok(known)
+    }
+
+    fn hydrate_profile(&self, (mut profile, data): (StoredProfile, String)) -> Result<StoredProfile, InstagrapError> {
+        profile.data = serde_json::from_str(&data)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to parse stored profile: {}", e)))?;
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT operation_id, target_account, seen_at FROM profile_sightings
+                 WHERE username = ?1 ORDER BY seen_at",
+            )
+            .map_err(db_err)?;
+        profile.sightings = stmt
+            .query_map(params![profile.username], |row| {
+                Ok(ProfileSighting {
+                    operation_id: row.get(0)?,
+                    target_account: row.get(1)?,
+                    seen_at: row.get(2)?,
+                })
+            })
+            .map_err(db_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_err)?;
+        Ok(profile)
+    }
+
+    // ===== Legacy JSON import =====
+
+    /// Imports `instagram_scraper_state.json` once, then renames it so it is kept but never re-read.
+    fn import_legacy_json(&self, json_path: &Path) -> Result<(), InstagrapError> {
+        if self.get_meta(META_LEGACY_JSON_IMPORTED)?.is_some() || !json_path.exists() {
+            return Ok(());
+        }
+
+        let state_json = fs::read_to_string(json_path)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to read legacy state file: {}", e)))?;
+        let parsed = serde_json::from_str(&state_json)
+            .map_err(|e| InstagrapError::Persistence(e.to_string()))
+            .and_then(migrations::migrate)
+            .and_then(|doc| serde_json::from_value::<LegacyState>(doc).map_err(|e| InstagrapError::Persistence(e.to_string())));
+        let legacy = match parsed {
+            Ok(state) => state,
+            Err(e) => {
+                // Keep the unreadable file around for inspection instead of discarding it
+                eprintln!("⚠️ Could not import legacy state file {}: {}", json_path.display(), e);
+                backup::quarantine(json_path)?;
+                return Ok(());
+            }
+        };
+
+        self.import_state(&legacy)?;
+        self.set_meta(META_LEGACY_JSON_IMPORTED, Some(&chrono::Utc::now().to_rfc3339()))?;
+
+        fs::rename(json_path, backup::with_suffix(json_path, ".imported"))
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to rename imported legacy state file: {}", e)))?;
+
+        eprintln!(
+            "✅ Imported {} operations, {} todos and {} criteria presets from {}",
+            legacy.scraping_operations.len(),
+            legacy.todos.len(),
+            legacy.saved_criteria.len(),
+            json_path.display()
+        );
+        Ok(())
+    }
+
+    fn import_state(&self, state: &LegacyState) -> Result<(), InstagrapError> {
+        let tx = self.conn.unchecked_transaction().map_err(db_err)?;
+        let todos = state.todos.iter().map(|todo| Job::from_todo(todo.clone(), &state.scraping_operations));
+        let ran: Vec<&str> = state.todos.iter().filter_map(|todo| todo.operation_id.as_deref()).collect();
+        let scrapes = state
+            .scraping_operations
+            .iter()
+            .filter(|op| !ran.contains(&op.operation_id.as_str()))
+            .map(|op| Job::from_operation(op.clone()));
+        // Every job from a legacy document has at most one run
+        for job in todos.chain(scrapes) {
+            write_job(&tx, &job, true)?;
+        }
+        for preset in &state.saved_criteria {
+            self.save_criteria_preset(preset)?;
+        }
+        self.set_meta(META_LAST_LOGIN_GCS_URI, state.last_login_gcs_uri.as_deref())?;
+        self.set_meta(META_ACTIVE_CRITERIA_ID, state.active_criteria_id.as_deref())?;
+        tx.commit().map_err(db_err)
+    }
+}
+
+fn write_job(conn: &Connection, job: &Job, with_results: bool) -> Result<(), InstagrapError> {
+    conn.execute(
+        "INSERT INTO jobs (id, listed, target_account, target_count, bio_agents, batch_size, status,
+                           created_at, completed_at, manually_completed, criteria_preset_id,
+                           criteria_preset_name, next_retry_at, current_attempt)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+         ON CONFLICT(id) DO UPDATE SET
+            listed = excluded.listed,
+            target_account = excluded.target_account,
+            target_count = excluded.target_count,
+            bio_agents = excluded.bio_agents,
+            batch_size = excluded.batch_size,
+            status = excluded.status,
+            created_at = excluded.created_at,
+            completed_at = excluded.completed_at,
+            manually_completed = excluded.manually_completed,
+            criteria_preset_id = excluded.criteria_preset_id,
+            criteria_preset_name = excluded.criteria_preset_name,
+            next_retry_at = excluded.next_retry_at,
+            current_attempt = excluded.current_attempt",
+        params![
+            job.id,
+            job.listed,
+            job.target_account,
+            job.target_count,
+            job.bio_agents,
+            job.batch_size,
+            job.status.as_str(),
+            job.created_at,
+            job.completed_at,
+            job.manually_completed,
+            job.criteria_preset_id,
+            job.criteria_preset_name,
+            job.next_retry_at,
+            job.current_attempt,
+        ],
+    )
+    .map_err(db_err)?;
+    for run in &job.runs {
+        write_run(conn, &job.id, run)?;
+    }
+    let Some(run) = job.runs.last() else {
+        return Ok(());
+    };
+    write_run_events(conn, &job.id, run)?;
+    if with_results {
+        let key = run.key(&job.id);
+        let sighting = run.operation_id.as_deref().map(|operation_id| Sighting {
+            operation_id,
+            target_account: &job.target_account,
+        });
+        replace_results(conn, OWNER_RUN, &key, run.results.as_deref(), &run.started_at, sighting)?;
+    }
+    Ok(())
+}
+
+fn write_run(conn: &Connection, job_id: &str, run: &JobRun) -> Result<(), InstagrapError> {
+    conn.prepare_cached(
+        "INSERT INTO job_runs (job_id, attempt, operation_id, exec_id, started_at, finished_at, status,
+                               error_message, retryable, results_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(job_id, attempt) DO UPDATE SET
+            operation_id = excluded.operation_id,
+            exec_id = excluded.exec_id,
+            started_at = excluded.started_at,
+            finished_at = excluded.finished_at,
+            status = excluded.status,
+            error_message = excluded.error_message,
+            retryable = excluded.retryable,
+            results_count = excluded.results_count",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute(params![
+            job_id,
+            run.attempt,
+            run.operation_id,
+            run.exec_id,
+            run.started_at,
+            run.finished_at,
+            run.status.as_str(),
+            run.error_message,
+            run.retryable,
+            run.results.as_ref().map(|r| r.len() as i64),
+        ])
+    })
+    .map_err(db_err)?;
+    Ok(())
+}
+
+fn write_run_events(conn: &Connection, job_id: &str, run: &JobRun) -> Result<(), InstagrapError> {
+    // Events are only ever appended, or folded into by later identical polls
+    for event in &run.events {
+        let progress = event.progress.as_ref().and_then(|p| serde_json::to_string(p).ok());
+        conn.prepare_cached(
+            "INSERT INTO run_events (job_id, attempt, seq, at, kind, status, progress, message, polls, last_polled_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(job_id, attempt, seq) DO UPDATE SET
+                polls = excluded.polls,
+                last_polled_at = excluded.last_polled_at",
+        )
+        .and_then(|mut stmt| {
+            stmt.execute(params![
+                job_id,
+                run.attempt,
+                event.seq,
+                event.at,
+                event.kind.as_str(),
+                event.status,
+                progress,
+                event.message,
+                event.polls,
+                event.last_polled_at,
+            ])
+        })
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+fn delete_run(conn: &Connection, job_id: &str, run: &JobRun) -> Result<(), InstagrapError> {
+    conn.execute("DELETE FROM job_runs WHERE job_id = ?1 AND attempt = ?2", params![job_id, run.attempt])
+        .map_err(db_err)?;
+    conn.execute("DELETE FROM run_events WHERE job_id = ?1 AND attempt = ?2", params![job_id, run.attempt])
+        .map_err(db_err)?;
+    replace_results(conn, OWNER_RUN, &run.key(job_id), None, &run.started_at, None)
+}
+
+fn job_from_row(row: &Row<'_>) -> rusqlite::Result<Job> {
+    Ok(Job {
+        id: row.get(0)?,
+        listed: row.get(1)?,
+        target_account: row.get(2)?,
+        target_count: row.get(3)?,
+        bio_agents: row.get(4)?,
+        batch_size: row.get(5)?,
+        status: parse_column(row, 6)?,
+        created_at: row.get(7)?,
+        completed_at: row.get(8)?,
+        manually_completed: row.get(9)?,
+        criteria_preset_id: row.get(10)?,
+        criteria_preset_name: row.get(11)?,
+        next_retry_at: row.get(12)?,
+        current_attempt: row.get(13)?,
+        runs: Vec::new(),
+    })
+}
+
+/// Reads a TEXT column into a type parsed with `FromStr`, such as the status enums.
+fn parse_column<T: FromStr<Err = String>>(row: &Row<'_>, idx: usize) -> rusqlite::Result<T> {
+    let raw: String = row.get(idx)?;
+    raw.parse().map_err(|e: String| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, e.into())
+    })
+}
+
+/// The run a batch of results came from, recorded against each profile.
+struct Sighting<'a> {
+    operation_id: &'a str,
+    target_account: &'a str,
+}
+
+fn replace_results(
+    conn: &Connection,
+    owner_kind: &str,
+    owner_id: &str,
+    results: Option<&[ProfileResult]>,
+    seen_at: &str,
+    sighting: Option<Sighting<'_>>,
+) -> Result<(), InstagrapError> {
+    conn.execute(
+        "DELETE FROM result_profiles WHERE owner_kind = ?1 AND owner_id = ?2",
+        params![owner_kind, owner_id],
+    )
+    .map_err(db_err)?;
+
+    let Some(results) = results else {
+        return Ok(());
+    };
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT INTO result_profiles (owner_kind, owner_id, position, username, payload)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .map_err(db_err)?;
+    // `seen_at` is when the run started, so saving each batch of a running
+    // scrape doesn't move its profiles' last sighting forward
+    for (position, result) in results.iter().enumerate() {
+        // Rows from before results were validated may lack a username
+        let username = Some(profile_key(&result.username)).filter(|u| !u.is_empty());
+        let payload = serde_json::to_string(result)
+            .map_err(|e| InstagrapError::Persistence(format!("Failed to serialize result: {}", e)))?;
+        match username {
+            Some(username) => {
+                upsert_profile(conn, &username, &payload, seen_at, sighting.as_ref())?;
+                stmt.execute(params![owner_kind, owner_id, position as i64, username, None::<String>])
+            }
+            None => stmt.execute(params![owner_kind, owner_id, position as i64, None::<String>, payload]),
+        }
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+fn upsert_profile(
+    conn: &Connection,
+    username: &str,
+    payload: &str,
+    seen_at: &str,
+    sighting: Option<&Sighting<'_>>,
+) -> Result<(), InstagrapError> {
+    conn.prepare_cached(
+        "INSERT INTO profiles (username, data, first_seen_at, last_seen_at)
+         VALUES (?1, ?2, ?3, ?3)
+         ON CONFLICT(username) DO UPDATE SET
+            data = excluded.data,
+            last_seen_at = MAX(last_seen_at, excluded.last_seen_at)",
+    )
+    .and_then(|mut stmt| stmt.execute(params![username, payload, seen_at]))
+    .map_err(db_err)?;
+
+    if let Some(sighting) = sighting {
+        conn.prepare_cached(
+            "INSERT OR IGNORE INTO profile_sightings (username, operation_id, target_account, seen_at)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .and_then(|mut stmt| stmt.execute(params![username, sighting.operation_id, sighting.target_account, seen_at]))
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+fn profile_from_row(row: &Row<'_>) -> rusqlite::Result<(StoredProfile, String)> {
+    Ok((
+        StoredProfile {
+            username: row.get(0)?,
+            data: ProfileResult::default(),
+            first_seen_at: row.get(2)?,
+            last_seen_at: row.get(3)?,
+            sightings: Vec::new(),
+        },
+        row.get(1)?,
+    ))
+}
+
+// Profiles are stored under the handle as Instagram compares it: without
+// surrounding spaces or a leading '@', and in lowercase
+fn profile_key(username: &str) -> String {
+    username.trim().trim_start_matches('@').to_ascii_lowercase()
+}
+
+// Makes `%`, `_` and `\` match themselves in a LIKE pattern with ESCAPE '\'
+fn escape_like(query: &str) -> String {
+    query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn migrate_database(conn: &Connection) -> Result<(), InstagrapError> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(db_err)?;
+    if version > DB_MIGRATIONS.len() {
+        return Err(InstagrapError::Persistence(format!(
+            "State database was written by a newer version of the app (schema {}, supported up to {})",
+            version,
+            DB_MIGRATIONS.len()
+        )));
+    }
+    for (from, sql) in DB_MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.unchecked_transaction().map_err(db_err)?;
+        tx.execute_batch(sql)
+            .map_err(|e| InstagrapError::Persistence(format!("Database migration from schema {} failed: {}", from, e)))?;
+        tx.pragma_update(None, "user_version", from + 1).map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+    }
+    Ok(())
+}
+
+fn quick_check(conn: &Connection) -> Result<(), InstagrapError> {
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| InstagrapError::Persistence(format!("Integrity check failed: {}", e)))?;
+    if result != "ok" {
+        return Err(InstagrapError::Persistence(format!("Integrity check failed: {}", result)));
+    }
+    Ok(())
+}
+
+// Takes the lock file next to the database; it is released when the returned
+// file is closed, including when the process dies.
+fn lock_state(config_dir: &Path, when_locked: WhenLocked) -> Result<File, InstagrapError> {
+    let path = config_dir.join(LOCK_FILE_NAME);
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| InstagrapError::Persistence(format!("Failed to open {}: {}", path.display(), e)))?;
+    match (file.try_lock(), when_locked) {
+        (Ok(()), _) => Ok(file),
+        (Err(TryLockError::WouldBlock), WhenLocked::Wait) => {
+            eprintln!("⏳ Waiting for another Instagrap process to close the state database");
+            file.lock().map_err(|e| InstagrapError::Persistence(format!("Failed to lock {}: {}", path.display(), e)))?;
+            Ok(file)
+        }
+        (Err(TryLockError::WouldBlock), WhenLocked::Fail) => Err(InstagrapError::persistence(
+            "The state database is in use by the desktop app or another instagrap-cli command. \
+             Close it, or use the automation API while the app is open.",
+        )),
+        (Err(TryLockError::Error(e)), _) => Err(InstagrapError::Persistence(format!("Failed to lock {}: {}", path.display(), e))),
+    }
+}
+
+/// Verifies a database file without modifying it.
+fn check_file(path: &Path) -> Result<(), InstagrapError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| InstagrapError::Persistence(format!("Failed to open {}: {}", path.display(), e)))?;
+    quick_check(&conn)
+}
+
+fn db_err(e: rusqlite::Error) -> InstagrapError {
+    InstagrapError::Persistence(format!("State database error: {}", e))
+}
@@ -0,0 +1,250 @@
+// Per-operation event timeline and stalled-operation detection.
+//
+// Every run of a job (see crate::jobs) keeps an ordered log of what happened
+// to it: when it was queued, each status poll with the backend's progress
+// counters, messages the backend sent along, and how it ended. Polls that
+// report exactly what the previous one did are folded into that event
+// (`polls` counts them), so a slow run doesn't grow the log by one row per
+// check. A running operation whose log hasn't moved for `stall_after_minutes`
+// becomes `stalled`; the next poll that reports something new puts it back to
+// running.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::api::ScrapeStatus;
+use crate::error::InstagrapError;
+use crate::jobs::JobRun;
+use crate::operations::ScrapingOperation;
+use crate::state::AppState;
+use crate::status::OperationStatus;
+
+pub const DEFAULT_STALL_AFTER_MINUTES: u32 = 15;
+pub const MAX_STALL_AFTER_MINUTES: u32 = 24 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Queued,
+    Poll,
+    Message,
+    Stalled,
+    Resumed,
+    Completed,
+    Failed,
+    Cancelled,
+    Lost,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Poll => "poll",
+            Self::Message => "message",
+            Self::Stalled => "stalled",
+            Self::Resumed => "resumed",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Lost => "lost",
+        }
+    }
+
+    /// The event recording that an operation reached `status`, if it is final.
+    pub fn finished(status: OperationStatus) -> Option<Self> {
+        match status {
+            OperationStatus::Completed => Some(Self::Completed),
+            OperationStatus::Failed => Some(Self::Failed),
+            OperationStatus::Cancelled => Some(Self::Cancelled),
+            OperationStatus::Lost => Some(Self::Lost),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(Self::Queued),
+            "poll" => Ok(Self::Poll),
+            "message" => Ok(Self::Message),
+            "stalled" => Ok(Self::Stalled),
+            "resumed" => Ok(Self::Resumed),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            "lost" => Ok(Self::Lost),
+            other => Err(format!("Unknown event kind: {}", other)),
+        }
+    }
+}
+
+/// Progress counters from a status answer's `progress` object. Counters the
+/// backend didn't send stay empty.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub classified: Option<u64>,
+    #[serde(default, alias = "yes", skip_serializing_if = "Option::is_none")]
+    pub yes_count: Option<u64>,
+}
+
+impl Progress {
+    pub fn from_status(status: &ScrapeStatus) -> Option<Self> {
+        let progress: Self = serde_json::from_value(status.extra.get("progress")?.clone()).ok()?;
+        (progress != Self::default()).then_some(progress)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OperationEvent {
+    /// 1-based, in the order things happened
+    pub seq: u32,
+    pub at: String,
+    pub kind: EventKind,
+    /// Status the backend answered, for polls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<Progress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Identical polls folded into this one, itself included
+    #[serde(default)]
+    pub polls: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_polled_at: Option<String>,
+}
+
+impl JobRun {
+    pub(crate) fn push_event(&mut self, kind: EventKind, message: Option<String>, at: DateTime<Utc>) {
+        self.events.push(OperationEvent {
+            seq: self.events.len() as u32 + 1,
+            at: at.to_rfc3339(),
+            kind,
+            status: None,
+            progress: None,
+            message,
+            polls: 0,
+            last_polled_at: None,
+        });
+    }
+
+    /// Records a status answer. Returns false when it only repeated the
+    /// previous poll.
+    fn push_poll(&mut self, status: &ScrapeStatus, at: DateTime<Utc>) -> bool {
+        let progress = Progress::from_status(status);
+        let last_poll = self.events.iter_mut().rev().find(|e| !matches!(e.kind, EventKind::Message | EventKind::Stalled));
+        if let Some(last) = last_poll.filter(|e| e.kind == EventKind::Poll) {
+            if last.status.as_deref() == Some(status.status.as_str()) && last.progress == progress {
+                last.polls += 1;
+                last.last_polled_at = Some(at.to_rfc3339());
+                return false;
+            }
+        }
+        self.push_event(EventKind::Poll, None, at);
+        if let Some(event) = self.events.last_mut() {
+            event.status = Some(status.status.clone());
+            event.progress = progress;
+            event.polls = 1;
+        }
+        true
+    }
+
+    pub fn last_activity_at(&self) -> &str {
+        last_activity_at(&self.events, &self.started_at)
+    }
+}
+
+impl ScrapingOperation {
+    /// Counters of the newest poll that had any.
+    pub fn latest_progress(&self) -> Option<&Progress> {
+        self.events.iter().rev().find_map(|e| e.progress.as_ref())
+    }
+
+    pub fn last_activity_at(&self) -> &str {
+        last_activity_at(&self.events, &self.started_at)
+    }
+}
+
+/// When a log last moved: its newest event other than a stall, or the start
+/// of the run for runs recorded before timelines existed.
+fn last_activity_at<'a>(events: &'a [OperationEvent], started_at: &'a str) -> &'a str {
+    events.iter().rev().find(|e| e.kind != EventKind::Stalled).map_or(started_at, |e| e.at.as_str())
+}
+
+impl AppState {
+    pub fn set_stall_after_minutes(&mut self, minutes: u32) -> Result<(), InstagrapError> {
+        if !(1..=MAX_STALL_AFTER_MINUTES).contains(&minutes) {
+            return Err(InstagrapError::Validation(format!("Stall threshold must be between 1 and {} minutes", MAX_STALL_AFTER_MINUTES)));
+        }
+        self.store.save_stall_after_minutes(minutes)?;
+        self.stall_after_minutes = minutes;
+        Ok(())
+    }
+
+    /// Adds a status answer to the operation's timeline, along with any new
+    /// backend message. A stalled operation whose poll brought news is running
+    /// again.
+    pub fn record_poll(&mut self, operation_id: &str, status: &ScrapeStatus, now: DateTime<Utc>) -> Result<(), InstagrapError> {
+        self.update_run(operation_id, false, |run| {
+            let changed = run.push_poll(status, now);
+            // Failure reasons end up on the failed event instead
+            let message = status.message.clone().filter(|_| status.status != "failed");
+            let last_message = run.events.iter().rev().find(|e| e.kind == EventKind::Message).and_then(|e| e.message.as_ref());
+            if message.is_some() && message.as_ref() != last_message {
+                run.push_event(EventKind::Message, message, now);
+            }
+            if changed && run.status == OperationStatus::Stalled {
+                run.status = run.status.transition(OperationStatus::Running)?;
+                run.push_event(EventKind::Resumed, None, now);
+                eprintln!("▶️ Operation {} is making progress again", operation_id);
+            }
+            Ok(())
+        })
+    }
+
+    /// Marks running operations whose timeline hasn't moved for the stall
+    /// threshold as stalled. Returns their ids.
+    pub fn detect_stalled_operations(&mut self, now: DateTime<Utc>) -> Result<Vec<String>, InstagrapError> {
+        let threshold = Duration::minutes(self.stall_after_minutes as i64);
+        let idle = |run: &JobRun| {
+            let last_activity = DateTime::parse_from_rfc3339(run.last_activity_at()).ok()?;
+            Some(now - last_activity.with_timezone(&Utc)).filter(|idle| *idle >= threshold)
+        };
+        let stalled: Vec<(String, Duration)> = self
+            .jobs
+            .iter()
+            .flat_map(|job| &job.runs)
+            .filter(|run| run.status == OperationStatus::Running)
+            .filter_map(|run| Some((run.operation_id.clone()?, idle(run)?)))
+            .collect();
+        for (operation_id, idle) in &stalled {
+            let message = format!("No progress for {} minutes", idle.num_minutes());
+            eprintln!("⏸️ Operation {} stalled: {}", operation_id, message);
+            self.update_run(operation_id, false, |run| {
+                run.status = run.status.transition(OperationStatus::Stalled)?;
+                run.push_event(EventKind::Stalled, Some(message), now);
+                Ok(())
+            })?;
+        }
+        Ok(stalled.into_iter().map(|(operation_id, _)| operation_id).collect())
+    }
+}
+
+pub fn timeline_json(op: &ScrapingOperation) -> serde_json::Value {
+    json!({
+        "operation_id": op.operation_id,
+        "target_account": op.target_account,
+        "status": op.status,
+        "started_at": op.started_at,
+        "last_activity_at": op.last_activity_at(),
+        "events": op.events
+    })
+}
@@ -0,0 +1,314 @@
+// Scrape todos: planned scrapes the user queues up and runs later.
+//
+// A todo is a view of a listed job (see crate::jobs): its parameters plus the
+// status, operation and results of the run it is on.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::api::ScrapeStatus;
+use crate::jobs::Job;
+use crate::operations::{self, ScrapeRequest};
+use crate::results::{validated_results, ProfileResult};
+use crate::error::InstagrapError;
+use crate::retry::TodoAttempt;
+use crate::state::{AppState, AppStateManager};
+use crate::status::{OperationStatus, TodoStatus};
+use crate::timeline::EventKind;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Todo {
+    pub id: String,
+    pub target_account: String,
+    pub target_count: u32,
+    pub bio_agents: u32,
+    pub batch_size: u32,
+    pub status: TodoStatus,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub operation_id: Option<String>,
+    pub exec_id: Option<String>,
+    pub results: Option<Vec<ProfileResult>>,
+    pub error_message: Option<String>,
+    pub manually_completed: bool,
+    // Optional: which preset this todo intends to use
+    pub criteria_preset_id: Option<String>,
+    // Cached human-readable name at the moment of selection
+    pub criteria_preset_name: Option<String>,
+    // Earlier runs, oldest first, see crate::retry
+    #[serde(default)]
+    pub attempts: Vec<TodoAttempt>,
+    // When a failed todo is retried automatically (RFC 3339)
+    #[serde(default)]
+    pub next_retry_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CreateTodoRequest {
+    pub target_account: String,
+    pub target_count: u32,
+    pub bio_agents: u32,
+    pub batch_size: u32,
+    pub criteria_preset_id: Option<String>,
+}
+
+pub fn todo_json(t: &Todo) -> serde_json::Value {
+    json!({
+        "id": t.id,
+        "target_account": t.target_account,
+        "target_count": t.target_count,
+        "bio_agents": t.bio_agents,
+        "batch_size": t.batch_size,
+        "status": t.status,
+        "created_at": t.created_at,
+        "started_at": t.started_at,
+        "completed_at": t.completed_at,
+        "operation_id": t.operation_id,
+        "exec_id": t.exec_id,
+        "results": t.results,
+        "error_message": t.error_message,
+        "manually_completed": t.manually_completed,
+        "criteria_preset_id": t.criteria_preset_id,
+        "criteria_preset_name": t.criteria_preset_name,
+        "attempts": t.attempts,
+        "next_retry_at": t.next_retry_at,
+    })
+}
+
+impl AppState {
+    /// Creates a pending todo and returns its id.
+    pub fn create_todo(&mut self, req: CreateTodoRequest) -> Result<String, InstagrapError> {
+        // Resolve preset name if id provided
+        let criteria_preset_name = req
+            .criteria_preset_id
+            .as_deref()
+            .and_then(|pid| self.get_criteria_preset(pid))
+            .map(|p| p.name.clone());
+        let job = Job {
+            criteria_preset_id: req.criteria_preset_id,
+            criteria_preset_name,
+            ..Job::new(req.target_account, req.target_count, req.bio_agents, req.batch_size)
+        };
+        let todo_id = job.id.clone();
+        self.add_job(job)?;
+        Ok(todo_id)
+    }
+
+    pub fn get_todo(&self, todo_id: &str) -> Option<Todo> {
+        self.get_job(todo_id).filter(|job| job.listed).map(Job::todo)
+    }
+
+    /// Moves a todo to `status`. Starting it begins a new run; any other
+    /// status is applied to the run it is on, along with the operation id,
+    /// results and error.
+    pub fn update_todo(&mut self, todo_id: &str, status: TodoStatus, operation_id: Option<String>, results: Option<Vec<ProfileResult>>, error_message: Option<String>) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Ok(());
+        }
+        self.update_job(todo_id, true, |job| {
+            let now = Utc::now();
+            let starting = job.status == TodoStatus::Pending && status == TodoStatus::Running;
+            job.status = job.status.transition(status)?;
+            if starting {
+                job.start_run(now);
+            }
+            if let Some(run) = job.current_run_mut() {
+                if operation_id.is_some() {
+                    run.operation_id = operation_id;
+                }
+                run.apply(status.into(), results, error_message, now)?;
+            }
+            if status.is_terminal() && job.completed_at.is_none() {
+                job.completed_at = Some(now.to_rfc3339());
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies a status update coming from a caller that only has raw values:
+    /// the status is parsed, results are validated and `exec_id` is recorded.
+    pub fn update_todo_status(
+        &mut self,
+        todo_id: &str,
+        status: &str,
+        operation_id: Option<String>,
+        exec_id: Option<String>,
+        results: Option<Vec<serde_json::Value>>,
+        error_message: Option<String>,
+    ) -> Result<TodoStatus, InstagrapError> {
+        let status: TodoStatus = status.parse().map_err(InstagrapError::Validation)?;
+        let results = results.map(|r| validated_results(&r, todo_id));
+        self.update_todo(todo_id, status, operation_id, results, error_message)?;
+        // Optionally persist exec_id on the todo if provided
+        if let Some(eid) = exec_id {
+            self.set_todo_exec_id(todo_id, eid)?;
+        }
+        Ok(status)
+    }
+
+    pub fn set_todo_exec_id(&mut self, todo_id: &str, exec_id: String) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Ok(());
+        }
+        self.update_job(todo_id, false, |job| {
+            if let Some(run) = job.current_run_mut() {
+                run.exec_id = Some(exec_id);
+            }
+            Ok(())
+        })
+    }
+
+    /// Applies the backend's answer to starting a todo's scrape to the run it
+    /// is on: the run gets the operation (`operation_id`, `exec_id`) and, if
+    /// the scrape already finished, its outcome.
+    pub fn link_todo_scrape(&mut self, todo_id: &str, result: &ScrapeStatus) -> Result<(), InstagrapError> {
+        let todo = self.get_todo(todo_id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+        let operation = operations::operation_for_response(&todo.target_account, todo.target_count, result);
+        self.update_job(todo_id, true, |job| {
+            let now = Utc::now();
+            let Some(run) = job.current_run_mut().filter(|run| run.status.is_active()) else {
+                return Err(InstagrapError::Validation(format!("Todo {} has no run in progress", todo_id)));
+            };
+            if let Some(operation) = operation {
+                run.operation_id = Some(operation.operation_id);
+                run.exec_id = operation.exec_id.or(run.exec_id.take());
+                match operation.status {
+                    OperationStatus::Completed => run.apply(OperationStatus::Completed, operation.results, None, now)?,
+                    _ => run.push_event(EventKind::Queued, None, now),
+                }
+            } else if result.status == "failed" {
+                let error_message = result.failure_message().map(|m| m.to_string());
+                run.apply(OperationStatus::Failed, None, error_message, now)?;
+            }
+            Ok(())
+        })?;
+        if result.status == "failed" && result.is_transient_failure() {
+            self.mark_attempt_retryable(todo_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_todo_preset(&mut self, todo_id: &str, preset_id: Option<String>, preset_name: Option<String>) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Ok(());
+        }
+        self.update_job(todo_id, false, |job| {
+            job.criteria_preset_id = preset_id;
+            job.criteria_preset_name = preset_name;
+            Ok(())
+        })
+    }
+
+    pub fn toggle_todo_manual_complete(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Ok(());
+        }
+        self.update_job(todo_id, false, |job| {
+            if job.manually_completed {
+                job.status = job.status.reopen()?;
+                job.completed_at = None;
+            } else {
+                job.status = job.status.complete_manually()?;
+                job.completed_at = Some(Utc::now().to_rfc3339());
+            }
+            job.manually_completed = !job.manually_completed;
+            Ok(())
+        })
+    }
+
+    /// Deletes a todo with all its runs.
+    pub fn delete_todo(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        if let Some(job) = self.get_job(todo_id).filter(|job| job.listed) {
+            self.store.delete_job(job)?;
+        }
+        self.jobs.retain(|job| job.id != todo_id);
+        self.todo_schedules.retain(|s| s.todo_id != todo_id);
+        Ok(())
+    }
+
+    /// Puts a finished todo back to pending for another run. Its previous
+    /// runs stay on the job and show up in `attempts`.
+    pub(crate) fn reset_todo_for_run(&mut self, todo_id: &str) -> Result<(), InstagrapError> {
+        if self.get_todo(todo_id).is_none() {
+            return Err(InstagrapError::not_found("Todo"));
+        }
+        self.update_job(todo_id, false, |job| {
+            job.status = match job.status {
+                TodoStatus::Completed => job.status.reopen()?,
+                TodoStatus::Failed | TodoStatus::Cancelled | TodoStatus::Lost => job.status.retry()?,
+                status => status,
+            };
+            job.completed_at = None;
+            job.manually_completed = false;
+            job.next_retry_at = None;
+            job.current_attempt = None;
+            Ok(())
+        })
+    }
+
+    pub fn get_todos(&self) -> Vec<Todo> {
+        self.jobs.iter().filter(|job| job.listed).map(Job::todo).collect()
+    }
+}
+
+/// Starts the scrape for a todo, the same way the desktop "Run" button does, and
+/// returns the backend response. `criteria_preset_override` wins over the
+/// todo's own preset. While another scrape of the same target is running the
+/// todo is left as it is and the call fails with a conflict error.
+pub async fn run_todo(state: &AppStateManager, todo_id: &str, criteria_preset_override: Option<String>) -> Result<serde_json::Value, InstagrapError> {
+    let todo = {
+        let mut app_state = state.lock()?;
+        let todo = app_state.get_todo(todo_id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+        if let Some(conflict) = app_state.todo_conflict(todo_id) {
+            eprintln!("⚠️ Not starting todo {}: {}", todo_id, conflict);
+            return Err(InstagrapError::Conflict(Box::new(conflict)));
+        }
+        app_state.update_todo(todo_id, TodoStatus::Running, None, None, None)?;
+        todo
+    };
+
+    let req = ScrapeRequest {
+        target: todo.target_account.clone(),
+        target_yes: todo.target_count,
+        batch_size: todo.batch_size,
+        num_bio_pages: todo.bio_agents,
+        criteria_preset_id: criteria_preset_override.or(todo.criteria_preset_id.clone()),
+        criteria_text: None,
+        on_conflict: None,
+    };
+    let result = match operations::send_remote_scrape(state, &req).await {
+        Ok(result) => result,
+        Err(e) => {
+            let mut app_state = state.lock()?;
+            app_state.update_todo(todo_id, TodoStatus::Failed, None, None, Some(e.to_string()))?;
+            if e.retryable() {
+                app_state.mark_attempt_retryable(todo_id)?;
+            }
+            return Err(e);
+        }
+    };
+
+    state.lock()?.link_todo_scrape(todo_id, &result)?;
+    match result.status.as_str() {
+        "queued" => eprintln!("✅ Todo queued with operation ID: {:?}", result.operation),
+        "completed" => eprintln!("✅ Todo completed immediately"),
+        "failed" => eprintln!("❌ Todo failed: {:?}", result.failure_message()),
+        _ => {}
+    }
+    Ok(json!(result))
+}
+
+/// Checks the operation behind a running todo. The operation is the todo's
+/// run, so a finished one has already settled the todo. Returns the todo's
+/// status afterwards.
+pub async fn refresh_todo(state: &AppStateManager, todo_id: &str) -> Result<TodoStatus, InstagrapError> {
+    let todo = state.lock()?.get_todo(todo_id).ok_or_else(|| InstagrapError::not_found("Todo"))?;
+    let Some(operation_id) = todo.operation_id.clone().filter(|_| todo.status == TodoStatus::Running) else {
+        return Ok(todo.status);
+    };
+
+    operations::check_operation_status(state, &operation_id).await?;
+    Ok(state.lock()?.get_todo(todo_id).map_or(todo.status, |t| t.status))
+}
@@ -0,0 +1,79 @@
+use instagrap_lib::api::{InstagramApiClient, LoginStatus, RemoteScrapeRequest, ScrapeStatus};
+use instagrap_lib::error::InstagrapError;
+use serde_json::json;
+use std::thread;
+use tiny_http::{Response, Server};
+
+// Answers each request with the first route whose path prefix matches
+fn serve(routes: Vec<(&'static str, u16, &'static str)>) -> String {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let base = format!("http://127.0.0.1:{}", server.server_addr().to_ip().unwrap().port());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let (status, body) = routes
+                .iter()
+                .find(|(path, _, _)| request.url().starts_with(path))
+                .map(|(_, status, body)| (*status, *body))
+                .unwrap_or((404, "not found"));
+            request.respond(Response::from_string(body).with_status_code(status)).ok();
+        }
+    });
+    base
+}
+
+#[test]
+fn builds_encoded_urls() {
+    let client = InstagramApiClient::new("https://api.example.com/v1/").unwrap();
+    let url = client.url("scrape-status", &[("target", "a&b c"), ("exec_id", "x/y?")]);
+    assert_eq!(url.as_str(), "https://api.example.com/v1/scrape-status?target=a%26b+c&exec_id=x%2Fy%3F");
+    assert!(InstagramApiClient::new("not a url").is_err());
+}
+
+#[test]
+fn keeps_unmodelled_status_fields() {
+    let status: ScrapeStatus = serde_json::from_value(json!({
+        "status": "running",
+        "exec_id": "e1",
+        "progress": { "done": 3 },
+    }))
+    .unwrap();
+    assert_eq!(status.exec_id.as_deref(), Some("e1"));
+    assert_eq!(json!(status)["progress"], json!({ "done": 3 }));
+}
+
+#[test]
+fn login_status_may_only_have_ok() {
+    let status: LoginStatus = serde_json::from_value(json!({ "ok": false })).unwrap();
+    assert_eq!((status.status.as_str(), status.ok), ("", Some(false)));
+}
+
+#[tokio::test]
+async fn maps_backend_failures() {
+    let base = serve(vec![
+        ("/login-status", 401, "stale session"),
+        ("/remote-scrape", 503, "overloaded"),
+        ("/scrape-status", 404, "no such exec"),
+        ("/legacy-scrape-status", 200, r#"{"status":"completed","results":[]}"#),
+    ]);
+    let client = InstagramApiClient::new(&base).unwrap();
+
+    assert!(matches!(client.login_status().await, Err(InstagrapError::LoginExpired)));
+
+    let req = RemoteScrapeRequest {
+        target: "nike".into(),
+        target_yes: 5,
+        batch_size: 5,
+        num_bio_pages: 1,
+        criteria_preset_id: None,
+        criteria_text: None,
+    };
+    let error = client.remote_scrape(&req).await.unwrap_err();
+    assert_eq!(error.code(), "backend_http");
+    assert!(error.retryable());
+
+    // A missing exec falls back to the legacy operation id
+    let status = client.scrape_status("e1", "nike", Some("op-1")).await.unwrap();
+    assert_eq!(status.status, "completed");
+    let error = client.scrape_status("e1", "nike", None).await.unwrap_err();
+    assert!(matches!(error, InstagrapError::BackendHttp { status: 404, .. }));
+}
@@ -0,0 +1,128 @@
+use instagrap_lib::automation::AutomationServer;
+use instagrap_lib::state::{AppState, AppStateManager};
+use serde_json::{json, Value};
+
+const TOKEN: &str = "test-token";
+
+fn start() -> (AutomationServer, String) {
+    let server = AutomationServer::start(AppStateManager::with_state(AppState::new()), 0, TOKEN.into()).unwrap();
+    let base = format!("http://127.0.0.1:{}/api", server.port());
+    (server, base)
+}
+
+#[tokio::test]
+async fn rejects_requests_without_token() {
+    let (_server, base) = start();
+    let client = reqwest::Client::new();
+
+    let health = client.get(format!("{}/health", base)).send().await.unwrap();
+    assert_eq!(health.status(), 200);
+
+    let missing = client.get(format!("{}/todos", base)).send().await.unwrap();
+    assert_eq!(missing.status(), 401);
+    let wrong = client.get(format!("{}/todos", base)).bearer_auth("nope").send().await.unwrap();
+    assert_eq!(wrong.status(), 401);
+    let header = client.get(format!("{}/todos", base)).header("X-Instagrap-Token", TOKEN).send().await.unwrap();
+    assert_eq!(header.status(), 200);
+}
+
+#[tokio::test]
+async fn creates_and_lists_todos() {
+    let (_server, base) = start();
+    let client = reqwest::Client::new();
+
+    let created = client
+        .post(format!("{}/todos", base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "target_account": "nike", "target_count": 50, "bio_agents": 2, "batch_size": 10 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(created.status(), 201);
+    let todo: Value = created.json().await.unwrap();
+    let id = todo["id"].as_str().unwrap();
+
+    let list: Value = client
+        .get(format!("{}/todos?status=pending", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["todos"].as_array().unwrap().len(), 1);
+
+    let csv = client
+        .get(format!("{}/todos/{}/results?format=csv", base, id))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(csv.starts_with("Username,Profile URL"));
+
+    let deleted = client.delete(format!("{}/todos/{}", base, id)).bearer_auth(TOKEN).send().await.unwrap();
+    assert_eq!(deleted.status(), 200);
+    let missing = client.get(format!("{}/todos/{}", base, id)).bearer_auth(TOKEN).send().await.unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn manages_criteria_presets() {
+    let (_server, base) = start();
+    let client = reqwest::Client::new();
+
+    let created: Value = client
+        .post(format!("{}/criteria", base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "name": "Runners", "criteria": "Bio mentions running" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = created["id"].as_str().unwrap();
+
+    let rename = client
+        .put(format!("{}/criteria/{}", base, id))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "name": "Marathoners" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(rename.status(), 200);
+
+    let active = client
+        .put(format!("{}/criteria/active", base))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "id": id }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(active.status(), 200);
+
+    let saved: Value = client
+        .get(format!("{}/criteria", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(saved["active_id"], json!(id));
+    assert_eq!(saved["presets"][0]["name"], "Marathoners");
+
+    let bad = client
+        .post(format!("{}/criteria", base))
+        .bearer_auth(TOKEN)
+        .body("not json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(bad.status(), 400);
+}
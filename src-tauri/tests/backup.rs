@@ -0,0 +1,49 @@
+mod common;
+
+use common::todo_request;
+use instagrap_lib::state::AppState;
+use instagrap_lib::store::Store;
+use std::path::PathBuf;
+
+fn database(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("instagrap-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("state.db")
+}
+
+#[test]
+fn automatic_backups_are_taken_at_most_hourly() {
+    let path = database("backup-throttle");
+    let store = Store::open(&path).unwrap();
+
+    assert!(store.create_backup_if_due().unwrap().is_some());
+    assert!(store.create_backup_if_due().unwrap().is_none());
+    // Asking for one explicitly still works
+    store.create_backup().unwrap();
+    assert_eq!(store.list_backups().unwrap().len(), 2);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn restored_state_keeps_writing_to_disk() {
+    let path = database("backup-restore");
+    let mut state = AppState::from_store(Store::open(&path).unwrap()).unwrap();
+    let kept = state.create_todo(todo_request("nike", 2)).unwrap();
+    let backup = state.store.create_backup().unwrap();
+    state.create_todo(todo_request("adidas", 2)).unwrap();
+
+    state.restore_backup(&backup.name).unwrap();
+    let ids: Vec<String> = state.get_todos().into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, [kept.as_str()]);
+    assert!(state.restore_backup("instagram_scraper_state-missing.db").is_err());
+
+    let added = state.create_todo(todo_request("puma", 2)).unwrap();
+    drop(state);
+    let reloaded = AppState::from_store(Store::open(&path).unwrap()).unwrap();
+    let ids: Vec<String> = reloaded.get_todos().into_iter().map(|t| t.id).collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&kept) && ids.contains(&added));
+    drop(reloaded);
+    let _ = std::fs::remove_dir_all(path.parent().unwrap());
+}
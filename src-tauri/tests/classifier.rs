@@ -0,0 +1,32 @@
+use instagrap_lib::classifier::{self, FakeClassifierClient};
+use instagrap_lib::error::InstagrapError;
+
+#[tokio::test]
+async fn updates_and_resets_criteria() {
+    let client = FakeClassifierClient::new("Bio mentions fitness");
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, "Bio mentions fitness");
+
+    let prompt = classifier::update_classification_prompt(&client, "  Bio mentions running\n").await.unwrap();
+    assert_eq!(prompt.prompt, FakeClassifierClient::compose_prompt("Bio mentions running"));
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, "Bio mentions running");
+
+    classifier::reset_classification_prompt(&client).await.unwrap();
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, "Bio mentions fitness");
+}
+
+#[tokio::test]
+async fn rejects_empty_criteria_without_calling_the_service() {
+    let client = FakeClassifierClient::new("default");
+    let error = classifier::update_classification_prompt(&client, "   ").await.unwrap_err();
+    assert_eq!(error.code(), "validation");
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, "default");
+}
+
+#[tokio::test]
+async fn surfaces_service_errors() {
+    let client = FakeClassifierClient::new("default");
+    client.fail_next(InstagrapError::BackendHttp { status: 502, body: "bad gateway".into() });
+    let error = classifier::reset_classification_prompt(&client).await.unwrap_err();
+    assert!(error.retryable());
+    assert!(classifier::reset_classification_prompt(&client).await.is_ok());
+}
@@ -0,0 +1,34 @@
+// Setup shared by the integration tests. Each test crate uses only some of
+// it, hence the dead_code allowance.
+#![allow(dead_code)]
+
+use instagrap_lib::mock::{MockBackend, MockScenario};
+use instagrap_lib::state::AppStateManager;
+use instagrap_lib::todos::{self, CreateTodoRequest};
+
+/// A mock backend running `scenario` and demo-mode state pointed at it.
+pub fn demo(scenario: MockScenario) -> (MockBackend, AppStateManager) {
+    let mock = MockBackend::start(scenario).unwrap();
+    let state = AppStateManager::with_state(mock.demo_state().unwrap());
+    (mock, state)
+}
+
+pub fn todo_request(target: &str, target_count: u32) -> CreateTodoRequest {
+    CreateTodoRequest {
+        target_account: target.to_string(),
+        target_count,
+        bio_agents: 1,
+        batch_size: 5,
+        criteria_preset_id: None,
+    }
+}
+
+pub fn add_todo(state: &AppStateManager, target: &str) -> String {
+    state.lock().unwrap().create_todo(todo_request(target, 2)).unwrap()
+}
+
+pub async fn running_todo(state: &AppStateManager, target: &str) -> String {
+    let id = add_todo(state, target);
+    todos::run_todo(state, &id, None).await.unwrap();
+    id
+}
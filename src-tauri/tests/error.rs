@@ -0,0 +1,56 @@
+use instagrap_lib::error::InstagrapError;
+use instagrap_lib::state::AppState;
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::todos::CreateTodoRequest;
+use serde_json::json;
+
+#[test]
+fn serializes_to_stable_shape() {
+    let error = InstagrapError::BackendHttp { status: 503, body: "unavailable".into() };
+    assert_eq!(
+        json!(error),
+        json!({
+            "code": "backend_http",
+            "message": "HTTP error 503: unavailable",
+            "retryable": true,
+            "details": { "status": 503, "body": "unavailable" },
+        })
+    );
+
+    let error = InstagrapError::not_found("Todo");
+    assert_eq!(json!(error), json!({ "code": "not_found", "message": "Todo not found", "retryable": false, "details": null }));
+}
+
+#[test]
+fn only_transient_failures_are_retryable() {
+    assert!(InstagrapError::Network("reset".into()).retryable());
+    assert!(InstagrapError::Timeout("slow".into()).retryable());
+    assert!(InstagrapError::BackendHttp { status: 429, body: String::new() }.retryable());
+    assert!(!InstagrapError::BackendHttp { status: 400, body: String::new() }.retryable());
+    assert!(!InstagrapError::LoginExpired.retryable());
+    assert!(!InstagrapError::validation("bad").retryable());
+}
+
+#[test]
+fn status_errors_carry_their_code() {
+    let mut state = AppState::new();
+    let id = state
+        .create_todo(CreateTodoRequest {
+            target_account: "nike".into(),
+            target_count: 10,
+            bio_agents: 1,
+            batch_size: 5,
+            criteria_preset_id: None,
+        })
+        .unwrap();
+
+    let invalid = state.update_todo_status(&id, "done", None, None, None, None).unwrap_err();
+    assert_eq!(invalid.code(), "validation");
+
+    let skipped = state.update_todo(&id, TodoStatus::Completed, None, None, None).unwrap_err();
+    let value = json!(skipped);
+    assert_eq!(value["code"], "invalid_transition");
+    assert_eq!(value["details"], json!({ "entity": "todo", "from": "pending", "to": "completed" }));
+
+    assert_eq!(state.set_active_criteria(Some("missing".into())).unwrap_err().code(), "not_found");
+}
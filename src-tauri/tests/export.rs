@@ -0,0 +1,30 @@
+use instagrap_lib::export::{to_csv, to_json, CSV_HEADERS};
+use instagrap_lib::results::ProfileResult;
+use serde_json::json;
+
+#[test]
+fn csv_has_one_row_per_profile() {
+    let profiles = vec![
+        ProfileResult::parse(json!({ "username": "a", "followers": 10, "verdict": "yes", "confidence": 0.5 })).unwrap(),
+        ProfileResult::parse(json!({ "username": "b", "url": "https://instagram.com/b" })).unwrap(),
+    ];
+    let csv = to_csv(&profiles);
+    let lines: Vec<_> = csv.lines().collect();
+    assert_eq!(lines[0], CSV_HEADERS.join(","));
+    assert_eq!(lines[1], "a,https://www.instagram.com/a/,,,10,,,,yes,,0.5");
+    assert_eq!(lines[2], "b,https://instagram.com/b,,,,,,,,,");
+}
+
+#[test]
+fn csv_escapes_special_characters() {
+    let profile = ProfileResult::parse(json!({ "username": "a", "bio": "runs, \"fast\"\nand far" })).unwrap();
+    let csv = to_csv(&[profile]);
+    assert!(csv.contains("\"runs, \"\"fast\"\"\nand far\""));
+}
+
+#[test]
+fn json_keeps_extra_fields() {
+    let profile = ProfileResult::parse(json!({ "username": "a", "batch": 2 })).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&to_json(&[profile]).unwrap()).unwrap();
+    assert_eq!(parsed, json!([{ "username": "a", "batch": 2 }]));
+}
@@ -0,0 +1,180 @@
+-- A state database at schema version 5, before todos and operations became jobs.
+-- Built from SCHEMA_V1 .. SCHEMA_V5_EVENTS in src/store.rs.
+
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS operations (
+    operation_id TEXT PRIMARY KEY,
+    target_account TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error_message TEXT,
+    exec_id TEXT,
+    -- NULL means "no results yet", which the frontend tells apart from an empty list
+    results_count INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS todos (
+    id TEXT PRIMARY KEY,
+    target_account TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    bio_agents INTEGER NOT NULL,
+    batch_size INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    completed_at TEXT,
+    operation_id TEXT,
+    exec_id TEXT,
+    error_message TEXT,
+    manually_completed INTEGER NOT NULL DEFAULT 0,
+    criteria_preset_id TEXT,
+    criteria_preset_name TEXT,
+    results_count INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS criteria_presets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    criteria TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS result_profiles (
+    owner_kind TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    username TEXT,
+    payload TEXT NOT NULL,
+    PRIMARY KEY (owner_kind, owner_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_result_profiles_username ON result_profiles(username);
+CREATE TABLE profiles (
+    username TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL
+);
+
+CREATE TABLE profile_sightings (
+    username TEXT NOT NULL,
+    operation_id TEXT NOT NULL,
+    target_account TEXT NOT NULL,
+    seen_at TEXT NOT NULL,
+    PRIMARY KEY (username, operation_id)
+);
+
+CREATE INDEX idx_profile_sightings_target ON profile_sightings(target_account);
+
+INSERT OR IGNORE INTO profiles (username, data, first_seen_at, last_seen_at)
+SELECT r.username, r.payload, MIN(o.started_at), MAX(o.started_at)
+FROM result_profiles r
+JOIN operations o ON r.owner_kind = 'operation' AND o.operation_id = r.owner_id
+WHERE r.username IS NOT NULL
+GROUP BY r.username;
+
+INSERT OR IGNORE INTO profiles (username, data, first_seen_at, last_seen_at)
+SELECT r.username, r.payload, MIN(COALESCE(t.started_at, t.created_at)), MAX(COALESCE(t.started_at, t.created_at))
+FROM result_profiles r
+JOIN todos t ON r.owner_kind = 'todo' AND t.id = r.owner_id
+WHERE r.username IS NOT NULL
+GROUP BY r.username;
+
+INSERT OR IGNORE INTO profile_sightings (username, operation_id, target_account, seen_at)
+SELECT r.username, o.operation_id, o.target_account, o.started_at
+FROM result_profiles r
+JOIN operations o ON r.owner_kind = 'operation' AND o.operation_id = r.owner_id
+WHERE r.username IS NOT NULL;
+
+CREATE TABLE result_profiles_v2 (
+    owner_kind TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    username TEXT,
+    payload TEXT,
+    PRIMARY KEY (owner_kind, owner_id, position)
+);
+
+INSERT INTO result_profiles_v2 (owner_kind, owner_id, position, username, payload)
+SELECT owner_kind, owner_id, position, username, CASE WHEN username IS NULL THEN payload END
+FROM result_profiles;
+
+DROP TABLE result_profiles;
+ALTER TABLE result_profiles_v2 RENAME TO result_profiles;
+CREATE INDEX idx_result_profiles_username ON result_profiles(username);
+CREATE TABLE todo_schedules (
+    todo_id TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    paused INTEGER NOT NULL DEFAULT 0,
+    next_run_at TEXT,
+    last_run_at TEXT
+);
+ALTER TABLE todos ADD COLUMN next_retry_at TEXT;
+
+CREATE TABLE todo_attempts (
+    todo_id TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    started_at TEXT,
+    finished_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    operation_id TEXT,
+    exec_id TEXT,
+    error_message TEXT,
+    retryable INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (todo_id, attempt)
+);
+CREATE TABLE operation_events (
+    operation_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    at TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    status TEXT,
+    progress TEXT,
+    message TEXT,
+    polls INTEGER NOT NULL DEFAULT 0,
+    last_polled_at TEXT,
+    PRIMARY KEY (operation_id, seq)
+);
+
+-- A direct scrape
+INSERT INTO operations VALUES ('op-direct', 'adidas', 5, '2025-01-01T09:00:00+00:00', 'completed', NULL, 'exec-direct', 1);
+INSERT INTO operation_events VALUES ('op-direct', 1, '2025-01-01T09:00:00+00:00', 'queued', NULL, NULL, NULL, 0, NULL);
+INSERT INTO operation_events VALUES ('op-direct', 2, '2025-01-01T09:05:00+00:00', 'completed', NULL, NULL, '1 results', 0, NULL);
+INSERT INTO result_profiles VALUES ('operation', 'op-direct', 0, 'carol', NULL);
+
+-- A todo that failed once and completed on its retry
+INSERT INTO operations VALUES ('op-failed', 'nike', 2, '2025-01-02T09:00:00+00:00', 'failed', 'Backend unavailable', 'exec-failed', NULL);
+INSERT INTO operations VALUES ('op-done', 'nike', 2, '2025-01-02T10:00:00+00:00', 'completed', NULL, 'exec-done', 2);
+INSERT INTO result_profiles VALUES ('operation', 'op-done', 0, 'alice', NULL);
+INSERT INTO result_profiles VALUES ('operation', 'op-done', 1, 'bob', NULL);
+INSERT INTO todos VALUES ('todo-done', 'nike', 2, 1, 5, 'completed', '2025-01-02T08:00:00+00:00', '2025-01-02T10:00:00+00:00',
+    '2025-01-02T10:10:00+00:00', 'op-done', 'exec-done', NULL, 0, NULL, NULL, 2, NULL);
+INSERT INTO result_profiles VALUES ('todo', 'todo-done', 0, 'alice', NULL);
+INSERT INTO result_profiles VALUES ('todo', 'todo-done', 1, 'bob', NULL);
+INSERT INTO todo_attempts VALUES ('todo-done', 1, '2025-01-02T09:00:00+00:00', '2025-01-02T09:01:00+00:00', 'failed',
+    'op-failed', 'exec-failed', 'Backend unavailable', 1);
+INSERT INTO todo_attempts VALUES ('todo-done', 2, '2025-01-02T10:00:00+00:00', '2025-01-02T10:10:00+00:00', 'completed',
+    'op-done', 'exec-done', NULL, 0);
+
+-- A todo still marked running whose operation already completed
+INSERT INTO operations VALUES ('op-late', 'puma', 2, '2025-01-03T09:00:00+00:00', 'completed', NULL, 'exec-late', 1);
+INSERT INTO result_profiles VALUES ('operation', 'op-late', 0, 'dave', NULL);
+INSERT INTO todos VALUES ('todo-late', 'puma', 2, 2, 10, 'running', '2025-01-03T08:00:00+00:00', '2025-01-03T09:00:00+00:00',
+    NULL, 'op-late', 'exec-late', NULL, 0, NULL, NULL, NULL, NULL);
+
+-- A todo that never ran
+INSERT INTO todos VALUES ('todo-pending', 'reebok', 3, 1, 5, 'pending', '2025-01-04T08:00:00+00:00', NULL,
+    NULL, NULL, NULL, NULL, 0, NULL, NULL, NULL, NULL);
+
+INSERT INTO profiles VALUES ('alice', '{"username":"alice"}', '2025-01-02T10:00:00+00:00', '2025-01-02T10:00:00+00:00');
+INSERT INTO profiles VALUES ('bob', '{"username":"bob"}', '2025-01-02T10:00:00+00:00', '2025-01-02T10:00:00+00:00');
+INSERT INTO profiles VALUES ('carol', '{"username":"carol"}', '2025-01-01T09:00:00+00:00', '2025-01-01T09:00:00+00:00');
+INSERT INTO profiles VALUES ('dave', '{"username":"dave"}', '2025-01-03T09:00:00+00:00', '2025-01-03T09:00:00+00:00');
+
+PRAGMA user_version = 5;
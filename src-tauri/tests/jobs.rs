@@ -0,0 +1,128 @@
+mod common;
+
+use common::todo_request;
+use instagrap_lib::results::ProfileResult;
+use instagrap_lib::state::AppState;
+use instagrap_lib::status::{OperationStatus, TodoStatus};
+use instagrap_lib::store::Store;
+use rusqlite::Connection;
+use serde_json::json;
+use std::path::PathBuf;
+
+fn v5_database(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("instagrap-{}-{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(include_str!("fixtures/store_v5.sql")).unwrap();
+    path
+}
+
+fn usernames(results: Option<Vec<ProfileResult>>) -> Vec<String> {
+    results.unwrap_or_default().into_iter().map(|p| p.username).collect()
+}
+
+#[test]
+fn migrates_todos_and_operations_into_jobs() {
+    let path = v5_database("jobs-v5");
+    let state = AppState::from_store(Store::open(&path).unwrap()).unwrap();
+
+    let todos = state.get_todos();
+    let ids: Vec<&str> = todos.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, ["todo-done", "todo-late", "todo-pending"]);
+    assert_eq!(state.jobs.len(), 4);
+
+    // Attempts and the current run are the same runs, not copies
+    let done = state.get_todo("todo-done").unwrap();
+    assert_eq!(done.status, TodoStatus::Completed);
+    assert_eq!(done.operation_id.as_deref(), Some("op-done"));
+    assert_eq!(usernames(done.results), ["alice", "bob"]);
+    let attempts: Vec<(TodoStatus, Option<&str>)> = done.attempts.iter().map(|a| (a.status, a.operation_id.as_deref())).collect();
+    assert_eq!(attempts, [(TodoStatus::Failed, Some("op-failed")), (TodoStatus::Completed, Some("op-done"))]);
+    assert!(done.attempts[0].retryable);
+
+    // A todo left running takes the outcome of its finished operation
+    let late = state.get_todo("todo-late").unwrap();
+    assert_eq!(late.status, TodoStatus::Completed);
+    assert_eq!(late.completed_at.as_deref(), Some("2025-01-03T09:00:00+00:00"));
+    assert_eq!(usernames(late.results), ["dave"]);
+
+    let pending = state.get_todo("todo-pending").unwrap();
+    assert_eq!((pending.status, pending.started_at, pending.attempts.len()), (TodoStatus::Pending, None, 0));
+
+    let operations: Vec<String> = state.operations().into_iter().map(|op| op.operation_id).collect();
+    assert_eq!(operations, ["op-direct", "op-failed", "op-done", "op-late"]);
+    let direct = state.get_operation("op-direct").unwrap();
+    assert_eq!(direct.status, OperationStatus::Completed);
+    assert_eq!(usernames(direct.results), ["carol"]);
+    assert_eq!(direct.events.len(), 2);
+    assert!(state.get_todo("op-direct").is_none());
+
+    let version: u32 = Connection::open(&path).unwrap().query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+    assert_eq!(version, 7);
+    drop(state);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn todo_runs_are_its_operations() {
+    let mut state = AppState::new();
+    let id = state.create_todo(todo_request("nike", 2)).unwrap();
+    state.update_todo_status(&id, "running", Some("op-1".into()), Some("exec-1".into()), None, None).unwrap();
+    state.update_todo_status(&id, "failed", None, None, None, Some("Backend unavailable".into())).unwrap();
+    state.retry_todo(&id).unwrap();
+    state.update_todo_status(&id, "running", Some("op-2".into()), None, None, None).unwrap();
+
+    // Results reported for the operation show up on the todo
+    let results = Some(vec![serde_json::from_value(json!({ "username": "alice" })).unwrap()]);
+    state.update_operation("op-2", OperationStatus::Completed, results, None).unwrap();
+    let todo = state.get_todo(&id).unwrap();
+    assert_eq!(todo.status, TodoStatus::Completed);
+    assert_eq!(usernames(todo.results), ["alice"]);
+    assert_eq!(todo.attempts.len(), 2);
+
+    let history: Vec<(String, OperationStatus)> = state.get_target_history("nike").into_iter().map(|op| (op.operation_id, op.status)).collect();
+    assert_eq!(history.len(), 2);
+    assert!(history.contains(&("op-1".to_string(), OperationStatus::Failed)));
+    assert!(history.contains(&("op-2".to_string(), OperationStatus::Completed)));
+
+    // The current run goes away with the todo only
+    assert!(state.remove_operation("op-2").is_err());
+    state.delete_todo(&id).unwrap();
+    assert!(state.operations().is_empty());
+}
+
+#[test]
+fn merges_profiles_that_differ_in_case() {
+    let path = v5_database("profiles-v5");
+    Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            r#"
+            INSERT INTO profiles (username, data, first_seen_at, last_seen_at) VALUES
+                ('Anna', '{"username":"Anna","bio":"old"}', '2025-01-01T00:00:00+00:00', '2025-01-02T00:00:00+00:00'),
+                ('anna', '{"username":"anna","bio":"new"}', '2025-01-03T00:00:00+00:00', '2025-01-04T00:00:00+00:00');
+            INSERT INTO profile_sightings (username, operation_id, target_account, seen_at) VALUES
+                ('Anna', 'op-1', 'nike', '2025-01-01T00:00:00+00:00'),
+                ('anna', 'op-1', 'nike', '2025-01-02T00:00:00+00:00'),
+                ('anna', 'op-2', 'adidas', '2025-01-04T00:00:00+00:00');
+            "#,
+        )
+        .unwrap();
+
+    let store = Store::open(&path).unwrap();
+    let (profiles, total) = store.search_profiles(Some("anna"), None, 10, 0).unwrap();
+    assert_eq!(total, 1);
+    let anna = store.get_profile("Anna").unwrap().unwrap();
+    assert_eq!(profiles[0].username, anna.username);
+    assert_eq!(anna.username, "anna");
+    assert_eq!(anna.data.bio.as_deref(), Some("new"));
+    assert_eq!(
+        (anna.first_seen_at.as_str(), anna.last_seen_at.as_str()),
+        ("2025-01-01T00:00:00+00:00", "2025-01-04T00:00:00+00:00")
+    );
+    let sightings: Vec<&str> = anna.sightings.iter().map(|s| s.operation_id.as_str()).collect();
+    assert_eq!(sightings.len(), 2);
+    assert!(sightings.contains(&"op-1") && sightings.contains(&"op-2"));
+    drop(store);
+    let _ = std::fs::remove_file(&path);
+}
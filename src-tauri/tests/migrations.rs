@@ -0,0 +1,88 @@
+use instagrap_lib::migrations::{migrate, schema_version, CURRENT_SCHEMA_VERSION};
+use serde_json::{json, Value};
+
+fn fixture(name: &str) -> Value {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {}", path, e));
+    serde_json::from_str(&text).unwrap()
+}
+
+/// Asserts a migrated document has every field the current AppState requires.
+fn assert_current_shape(doc: &Value) {
+    assert_eq!(schema_version(doc).unwrap(), CURRENT_SCHEMA_VERSION);
+    for key in ["scraping_operations", "todos", "saved_criteria"] {
+        assert!(doc[key].is_array(), "`{}` should be an array", key);
+    }
+    assert!(doc.get("last_login_gcs_uri").is_some());
+    assert!(doc.get("active_criteria_id").is_some());
+    for op in doc["scraping_operations"].as_array().unwrap() {
+        for key in ["exec_id", "results", "error_message"] {
+            assert!(op.get(key).is_some(), "operation missing `{}`", key);
+        }
+    }
+    for todo in doc["todos"].as_array().unwrap() {
+        assert!(todo["manually_completed"].is_boolean());
+        for key in ["exec_id", "criteria_preset_id", "criteria_preset_name"] {
+            assert!(todo.get(key).is_some(), "todo missing `{}`", key);
+        }
+    }
+}
+
+#[test]
+fn migrates_v0_document() {
+    let doc = fixture("state_v0.json");
+    assert_eq!(schema_version(&doc).unwrap(), 0);
+
+    let migrated = migrate(doc).unwrap();
+    assert_current_shape(&migrated);
+    assert_eq!(migrated["todos"], json!([]));
+    assert_eq!(migrated["scraping_operations"][0]["exec_id"], Value::Null);
+    assert_eq!(migrated["scraping_operations"][0]["results"][0]["username"], "runner_anna");
+}
+
+#[test]
+fn migrates_v1_document() {
+    let migrated = migrate(fixture("state_v1.json")).unwrap();
+    assert_current_shape(&migrated);
+    let todo = &migrated["todos"][0];
+    assert_eq!(todo["manually_completed"], false);
+    assert_eq!(todo["operation_id"], "op-1");
+    assert_eq!(migrated["scraping_operations"][0]["exec_id"], "exec-1");
+}
+
+#[test]
+fn migrates_v2_document() {
+    let migrated = migrate(fixture("state_v2.json")).unwrap();
+    assert_current_shape(&migrated);
+    assert_eq!(migrated["todos"][0]["manually_completed"], true);
+    assert_eq!(migrated["saved_criteria"], json!([]));
+    assert_eq!(migrated["active_criteria_id"], Value::Null);
+}
+
+#[test]
+fn current_document_is_unchanged() {
+    let doc = fixture("state_v3.json");
+    let migrated = migrate(doc.clone()).unwrap();
+    assert_current_shape(&migrated);
+    assert_eq!(migrated, doc);
+}
+
+#[test]
+fn migration_is_idempotent() {
+    let once = migrate(fixture("state_v1.json")).unwrap();
+    let twice = migrate(once.clone()).unwrap();
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn rejects_newer_schema() {
+    let doc = json!({ "schema_version": CURRENT_SCHEMA_VERSION + 1, "scraping_operations": [] });
+    assert!(migrate(doc).is_err());
+}
+
+#[test]
+fn rejects_malformed_documents() {
+    assert!(migrate(json!([])).is_err());
+    assert!(migrate(json!({ "schema_version": "two" })).is_err());
+    assert!(migrate(json!({ "todos": { "id": "not-an-array" } })).is_err());
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::{demo, todo_request};
+use instagrap_lib::classifier::{self, HttpClassifierClient};
+use instagrap_lib::mock::{MockScenario, DEFAULT_MOCK_CRITERIA};
+use instagrap_lib::operations;
+use instagrap_lib::state::AppStateManager;
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::todos;
+use std::time::{Duration, Instant};
+
+fn queue_todo(state: &AppStateManager, target: &str) -> String {
+    state.lock().unwrap().create_todo(todo_request(target, 3)).unwrap()
+}
+
+#[tokio::test]
+async fn todo_runs_from_queued_to_completed() {
+    let (mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 1 });
+    let id = queue_todo(&state, "nike");
+
+    let response = todos::run_todo(&state, &id, None).await.unwrap();
+    assert_eq!(response["status"], "queued");
+    assert_eq!(state.lock().unwrap().get_todo(&id).unwrap().exec_id.as_deref(), Some("mock-exec-1"));
+
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Running);
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Completed);
+    let app_state = state.lock().unwrap();
+    let todo = app_state.get_todo(&id).unwrap();
+    assert_eq!(todo.results.as_ref().unwrap().len(), 3);
+    assert_eq!(app_state.get_operation("mock-op-1").unwrap().results.as_ref().unwrap().len(), 3);
+    assert!(mock.requests().contains(&"POST /remote-scrape".to_string()));
+}
+
+#[tokio::test]
+async fn backend_failure_marks_todo_failed() {
+    let (_mock, state) = demo(MockScenario::Failure { message: "Target account is private".into() });
+    let id = queue_todo(&state, "private_account");
+
+    todos::run_todo(&state, &id, None).await.unwrap();
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Failed);
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_todo(&id).unwrap().error_message.as_deref(), Some("Target account is private"));
+}
+
+#[tokio::test]
+async fn missing_exec_status_falls_back_to_legacy_endpoint() {
+    let (mock, state) = demo(MockScenario::LegacyFallback);
+    let request = operations::ScrapeRequest {
+        target: "adidas".into(),
+        target_yes: 2,
+        batch_size: 5,
+        num_bio_pages: 1,
+        criteria_preset_id: None,
+        criteria_text: None,
+        on_conflict: None,
+    };
+    let response = operations::start_remote_scrape(&state, request).await.unwrap();
+    assert_eq!(response["operation"], "mock-op-1");
+
+    let status = operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    assert_eq!(status["status"], "completed");
+    assert_eq!(status["count"], 2);
+    let requests = mock.requests();
+    assert!(requests.contains(&"GET /scrape-status".to_string()));
+    assert!(requests.contains(&"GET /legacy-scrape-status".to_string()));
+}
+
+#[tokio::test]
+async fn slow_backend_still_answers_and_classifier_is_served() {
+    let (mock, state) = demo(MockScenario::Slow { delay: Duration::from_millis(200) });
+    let id = queue_todo(&state, "puma");
+    let started = Instant::now();
+    todos::run_todo(&state, &id, None).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Completed);
+
+    mock.set_scenario(MockScenario::default());
+    let client = HttpClassifierClient::new(&mock.url()).unwrap();
+    let prompt = classifier::update_classification_prompt(&client, " Bio mentions yoga ").await.unwrap();
+    assert!(prompt.prompt.contains("Bio mentions yoga"));
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, "Bio mentions yoga");
+    classifier::reset_classification_prompt(&client).await.unwrap();
+    assert_eq!(classifier::get_classification_criteria(&client).await.unwrap().criteria, DEFAULT_MOCK_CRITERIA);
+}
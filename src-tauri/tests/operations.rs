@@ -0,0 +1,198 @@
+mod common;
+
+use common::{demo, todo_request};
+use instagrap_lib::mock::{MockBackend, MockScenario};
+use instagrap_lib::operations::{self, ConflictResolution, ScrapeRequest};
+use instagrap_lib::state::{AppState, AppStateManager};
+use instagrap_lib::status::{OperationStatus, TodoStatus};
+use instagrap_lib::todos;
+use serde_json::json;
+use std::time::Duration;
+
+async fn running_todo(scenario: MockScenario) -> (MockBackend, AppStateManager, String) {
+    let (mock, state) = demo(scenario);
+    let id = state.lock().unwrap().create_todo(todo_request("nike", 500)).unwrap();
+    todos::run_todo(&state, &id, None).await.unwrap();
+    (mock, state, id)
+}
+
+#[tokio::test]
+async fn cancel_keeps_partial_results() {
+    let (mock, state, id) = running_todo(MockScenario::QueuedThenCompleted { polls: 10 }).await;
+    for _ in 0..2 {
+        operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    }
+
+    let response = operations::cancel_operation(&state, "mock-op-1", false).await.unwrap();
+    assert_eq!(response["status"], "cancelled");
+    assert_eq!(response["count"], 2);
+    assert_eq!(response["todo_ids"][0], id.as_str());
+    assert_eq!(response["artifacts_deleted"], false);
+    assert!(!mock.requests().contains(&"DELETE /scrape-artifacts".to_string()));
+
+    {
+        let app_state = state.lock().unwrap();
+        let operation = app_state.get_operation("mock-op-1").unwrap();
+        assert_eq!(operation.status, OperationStatus::Cancelled);
+        assert_eq!(operation.results.as_ref().unwrap().len(), 2);
+        let todo = app_state.get_todo(&id).unwrap();
+        assert_eq!(todo.status, TodoStatus::Cancelled);
+        assert_eq!(todo.results.as_ref().unwrap().len(), 2);
+        assert!(todo.completed_at.is_some());
+    }
+
+    // A finished operation can't be cancelled again
+    assert!(operations::cancel_operation(&state, "mock-op-1", false).await.is_err());
+    assert!(operations::cancel_operation(&state, "missing", false).await.is_err());
+}
+
+#[tokio::test]
+async fn cancel_can_delete_artifacts() {
+    let (mock, state, _id) = running_todo(MockScenario::QueuedThenCompleted { polls: 10 }).await;
+
+    let response = operations::cancel_operation(&state, "mock-op-1", true).await.unwrap();
+    assert_eq!(response["count"], 0);
+    assert_eq!(response["artifacts_deleted"], true);
+    let requests = mock.requests();
+    let cancel = requests.iter().position(|r| r == "POST /cancel-scrape").unwrap();
+    let delete = requests.iter().position(|r| r == "DELETE /scrape-artifacts").unwrap();
+    assert!(cancel < delete);
+}
+
+#[tokio::test]
+async fn cancel_after_completion_records_the_results() {
+    let (_mock, state, id) = running_todo(MockScenario::QueuedThenCompleted { polls: 0 }).await;
+
+    let response = operations::cancel_operation(&state, "mock-op-1", false).await.unwrap();
+    assert_eq!(response["status"], "completed");
+    assert_eq!(response["count"], 500);
+    assert_eq!(state.lock().unwrap().get_operation("mock-op-1").unwrap().status, OperationStatus::Completed);
+    // The todo settles from the completed operation as usual
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Completed);
+}
+
+#[tokio::test]
+async fn refresh_picks_up_a_cancel_from_elsewhere() {
+    let (_mock, state, id) = running_todo(MockScenario::QueuedThenCompleted { polls: 10 }).await;
+    operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    state.api().unwrap().cancel_scrape("nike", Some("mock-exec-1"), "mock-op-1").await.unwrap();
+
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Cancelled);
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_operation("mock-op-1").unwrap().status, OperationStatus::Cancelled);
+    assert_eq!(app_state.get_todo(&id).unwrap().results.as_ref().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn failed_run_keeps_partial_results() {
+    let failure = MockScenario::FailsAfter { polls: 3, message: "Instagram rate limit".into() };
+    let (_mock, state, id) = running_todo(failure).await;
+    for _ in 0..3 {
+        operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    }
+    {
+        let app_state = state.lock().unwrap();
+        let operation = app_state.get_operation("mock-op-1").unwrap();
+        assert_eq!(operation.status, OperationStatus::Running);
+        assert_eq!(operation.results.as_ref().unwrap().len(), 3);
+        assert_eq!(operation.latest_progress().unwrap().yes_count, Some(3));
+    }
+
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Failed);
+    let mut app_state = state.lock().unwrap();
+    let operation = app_state.get_operation("mock-op-1").unwrap();
+    assert_eq!(operation.status, OperationStatus::Failed);
+    assert_eq!(operation.error_message.as_deref(), Some("Instagram rate limit"));
+    assert_eq!(operation.results.as_ref().unwrap().len(), 3);
+    let todo = app_state.get_todo(&id).unwrap();
+    assert_eq!(todo.results.as_ref().unwrap()[2].username, "nike_fan_3");
+
+    let reloaded = AppState::from_store(std::mem::take(&mut app_state.store)).unwrap();
+    let operation = reloaded.get_operation("mock-op-1").unwrap();
+    assert_eq!(operation.results.as_ref().unwrap().len(), 3);
+    assert_eq!(operation.latest_progress().unwrap().scanned, Some(75));
+}
+
+fn scrape_request(target: &str, on_conflict: Option<ConflictResolution>) -> ScrapeRequest {
+    ScrapeRequest {
+        target: target.to_string(),
+        target_yes: 2,
+        batch_size: 5,
+        num_bio_pages: 1,
+        criteria_preset_id: None,
+        criteria_text: None,
+        on_conflict,
+    }
+}
+
+#[tokio::test]
+async fn refuses_a_second_scrape_of_a_running_target() {
+    let (mock, state, id) = running_todo(MockScenario::QueuedThenCompleted { polls: 10 }).await;
+
+    let error = operations::start_remote_scrape(&state, scrape_request("@Nike", None)).await.unwrap_err();
+    assert_eq!(error.code(), "conflict");
+    let details = error.details();
+    assert_eq!(details["operation_id"], "mock-op-1");
+    assert_eq!(details["exec_id"], "mock-exec-1");
+    assert_eq!(details["todo_id"], id.as_str());
+    assert_eq!(details["options"], json!(["attach", "cancel", "force"]));
+    assert_eq!(mock.requests().iter().filter(|r| *r == "POST /remote-scrape").count(), 1);
+
+    // Following the running scrape starts nothing new
+    let attached = operations::start_remote_scrape(&state, scrape_request("nike", Some(ConflictResolution::Attach))).await.unwrap();
+    assert_eq!((attached["operation"].as_str(), attached["attached"].as_bool()), (Some("mock-op-1"), Some(true)));
+    assert_eq!(state.lock().unwrap().operations().len(), 1);
+
+    // Other targets are not affected
+    operations::start_remote_scrape(&state, scrape_request("adidas", None)).await.unwrap();
+}
+
+#[tokio::test]
+async fn conflicting_scrape_can_cancel_or_run_in_parallel() {
+    let (_mock, state, id) = running_todo(MockScenario::QueuedThenCompleted { polls: 10 }).await;
+
+    let forced = operations::start_remote_scrape(&state, scrape_request("nike", Some(ConflictResolution::Force))).await.unwrap();
+    let forced_id = forced["operation"].as_str().unwrap().to_string();
+    {
+        let app_state = state.lock().unwrap();
+        let history: Vec<String> = app_state.get_target_history("nike").into_iter().map(|op| op.operation_id).collect();
+        assert_eq!(history.len(), 2);
+        assert!(history.contains(&forced_id));
+        assert_eq!(app_state.get_operation("mock-op-1").unwrap().exec_id.as_deref(), Some("mock-exec-1"));
+    }
+
+    operations::cancel_operation(&state, &forced_id, false).await.unwrap();
+    let replaced = operations::start_remote_scrape(&state, scrape_request("nike", Some(ConflictResolution::Cancel))).await.unwrap();
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_operation("mock-op-1").unwrap().status, OperationStatus::Cancelled);
+    assert_eq!(app_state.get_todo(&id).unwrap().status, TodoStatus::Cancelled);
+    let replacement = app_state.get_operation(replaced["operation"].as_str().unwrap()).unwrap();
+    assert_eq!(replacement.status, OperationStatus::Running);
+}
+
+#[tokio::test]
+async fn concurrent_scrapes_of_one_target_start_once() {
+    let (mock, state) = demo(MockScenario::Slow { delay: Duration::from_millis(200) });
+
+    let (first, second) = tokio::join!(
+        operations::start_remote_scrape(&state, scrape_request("nike", None)),
+        operations::start_remote_scrape(&state, scrape_request("@NIKE", None)),
+    );
+    let conflicts: Vec<_> = [&first, &second].into_iter().filter_map(|r| r.as_ref().err()).collect();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].code(), "conflict");
+    assert_eq!(conflicts[0].details()["options"], json!(["force"]));
+    assert_eq!(mock.requests().iter().filter(|r| *r == "POST /remote-scrape").count(), 1);
+    assert!(state.lock().unwrap().starting_scrapes.is_empty());
+}
+
+#[tokio::test]
+async fn failed_sends_release_the_target() {
+    let (mock, state) = demo(MockScenario::default());
+    drop(mock);
+
+    let error = operations::start_remote_scrape(&state, scrape_request("nike", None)).await.unwrap_err();
+    assert_ne!(error.code(), "conflict");
+    let error = operations::start_remote_scrape(&state, scrape_request("nike", None)).await.unwrap_err();
+    assert_ne!(error.code(), "conflict");
+}
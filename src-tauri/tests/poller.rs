@@ -0,0 +1,83 @@
+mod common;
+
+use common::{demo, running_todo};
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::operations;
+use instagrap_lib::poller::{Poller, PollerConfig, OPERATION_UPDATED, TODO_UPDATED};
+use instagrap_lib::state::AppStateManager;
+use instagrap_lib::status::TodoStatus;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type Events = Arc<Mutex<Vec<(String, Value)>>>;
+
+fn poller(state: &AppStateManager, config: PollerConfig) -> (Poller<impl Fn(&str, Value) + Send + Sync>, Events) {
+    let events: Events = Arc::default();
+    let sink = events.clone();
+    let poller = Poller::new(state.clone(), config, move |event: &str, payload: Value| {
+        sink.lock().unwrap().push((event.to_string(), payload));
+    });
+    (poller, events)
+}
+
+#[tokio::test]
+async fn settles_todos_and_emits_updates() {
+    let (mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 1 });
+    let id = running_todo(&state, "nike").await;
+    let config = PollerConfig { min_interval: Duration::ZERO, max_interval: Duration::ZERO };
+    let (mut poller, events) = poller(&state, config);
+
+    poller.tick().await;
+    assert_eq!(state.lock().unwrap().get_todo(&id).unwrap().status, TodoStatus::Running);
+    poller.tick().await;
+    assert_eq!(state.lock().unwrap().get_todo(&id).unwrap().status, TodoStatus::Completed);
+    assert!(mock.requests().contains(&"DELETE /scrape-artifacts".to_string()));
+
+    let events = events.lock().unwrap();
+    let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, [OPERATION_UPDATED, OPERATION_UPDATED, TODO_UPDATED]);
+    assert_eq!(events[0].1["progress"]["polls"], 1);
+    assert_eq!(events[2].1["id"], id.as_str());
+    assert_eq!(events[2].1["results"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn only_polls_operations_that_are_due() {
+    let (mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 10 });
+    running_todo(&state, "adidas").await;
+    let config = PollerConfig { min_interval: Duration::from_secs(1), max_interval: Duration::from_secs(4) };
+    let (mut poller, _events) = poller(&state, config);
+
+    // A changed answer schedules the next check after min_interval
+    assert!(poller.tick().await <= Duration::from_secs(1));
+    poller.tick().await;
+    let polls = mock.requests().iter().filter(|r| *r == "GET /scrape-status").count();
+    assert_eq!(polls, 1);
+}
+
+#[tokio::test]
+async fn reports_todos_that_finished_with_their_operation() {
+    let (mock, state) = demo(MockScenario::default());
+    let id = running_todo(&state, "puma").await;
+    let config = PollerConfig { min_interval: Duration::ZERO, max_interval: Duration::ZERO };
+    let (mut poller, events) = poller(&state, config);
+
+    poller.tick().await;
+    // The operation is the todo's run and goes away with the todo only
+    assert!(state.lock().unwrap().remove_operation("mock-op-1").is_err());
+    // Checked outside the poller, e.g. by an open window
+    operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    {
+        let app_state = state.lock().unwrap();
+        let todo = app_state.get_todo(&id).unwrap();
+        assert_eq!(todo.status, TodoStatus::Completed);
+        assert_eq!(todo.results, app_state.get_operation("mock-op-1").unwrap().results);
+    }
+
+    poller.tick().await;
+    let events = events.lock().unwrap();
+    let (name, payload) = events.last().unwrap();
+    assert_eq!((name.as_str(), payload["status"].as_str()), (TODO_UPDATED, Some("completed")));
+    assert!(mock.requests().contains(&"DELETE /scrape-artifacts".to_string()));
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{add_todo, demo};
+use instagrap_lib::api::ScrapeStatus;
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::queue;
+use instagrap_lib::state::{AppState, AppStateManager};
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::todos::{self, CreateTodoRequest};
+use serde_json::json;
+
+fn status(state: &AppStateManager, todo_id: &str) -> TodoStatus {
+    state.lock().unwrap().get_todo(todo_id).unwrap().status
+}
+
+#[tokio::test]
+async fn run_all_respects_the_concurrency_limit() {
+    let (_mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 0 });
+    let ids: Vec<String> = ["a", "b", "c"].iter().map(|t| add_todo(&state, t)).collect();
+    state.lock().unwrap().set_max_concurrent(2).unwrap();
+    assert!(state.lock().unwrap().set_max_concurrent(0).is_err());
+
+    let response = queue::run_all(&state).await.unwrap();
+    assert_eq!(response["started"], json!(ids[..2]));
+    assert_eq!(response["queued"], json!([ids[2]]));
+    assert_eq!(response["running"], 2);
+    assert_eq!(status(&state, &ids[2]), TodoStatus::Pending);
+
+    // A finished job frees a slot for the queued todo
+    todos::refresh_todo(&state, &ids[0]).await.unwrap();
+    assert_eq!(queue::fill_slots(&state).await.unwrap().started, [ids[2].clone()]);
+    assert_eq!(status(&state, &ids[2]), TodoStatus::Running);
+    assert!(queue::run_next(&state).await.is_err());
+}
+
+#[tokio::test]
+async fn todos_that_fail_to_start_are_not_counted_as_started() {
+    let (mock, state) = demo(MockScenario::default());
+    let ids: Vec<String> = ["a", "b"].iter().map(|t| add_todo(&state, t)).collect();
+    drop(mock);
+
+    let response = queue::run_all(&state).await.unwrap();
+    assert_eq!(response["started"], json!([]));
+    assert_eq!(response["failed"], json!(ids));
+    assert_eq!(status(&state, &ids[0]), TodoStatus::Failed);
+}
+
+#[tokio::test]
+async fn todos_wait_for_a_running_scrape_of_their_target() {
+    let (_mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 0 });
+    let ids: Vec<String> = ["nike", "@Nike", "adidas"].iter().map(|t| add_todo(&state, t)).collect();
+    state.lock().unwrap().set_max_concurrent(3).unwrap();
+    state.lock().unwrap().enqueue_todos(&ids).unwrap();
+
+    let filled = queue::fill_slots(&state).await.unwrap();
+    assert_eq!(filled.started, [ids[0].clone(), ids[2].clone()]);
+    assert!(filled.failed.is_empty());
+    assert_eq!(state.lock().unwrap().todo_queue.queued, [ids[1].clone()]);
+    assert_eq!(status(&state, &ids[1]), TodoStatus::Pending);
+
+    // Starting it by hand is refused the same way
+    assert_eq!(todos::run_todo(&state, &ids[1], None).await.unwrap_err().code(), "conflict");
+    assert_eq!(status(&state, &ids[1]), TodoStatus::Pending);
+
+    todos::refresh_todo(&state, &ids[0]).await.unwrap();
+    assert_eq!(queue::fill_slots(&state).await.unwrap().started, [ids[1].clone()]);
+    assert!(state.lock().unwrap().todo_queue.queued.is_empty());
+}
+
+#[tokio::test]
+async fn run_next_links_operation_and_exec_id() {
+    let (_mock, state) = demo(MockScenario::default());
+    let first = add_todo(&state, "nike");
+    add_todo(&state, "adidas");
+
+    let response = queue::run_next(&state).await.unwrap();
+    assert_eq!(response["todo_id"], first.as_str());
+    let mut app_state = state.lock().unwrap();
+    let todo = app_state.get_todo(&first).unwrap();
+    assert_eq!(todo.operation_id.as_deref(), Some("mock-op-1"));
+    assert_eq!(todo.exec_id.as_deref(), Some("mock-exec-1"));
+
+    // Both halves of the link are on disk
+    let reloaded = AppState::from_store(std::mem::take(&mut app_state.store)).unwrap();
+    assert_eq!(reloaded.get_todo(&first).unwrap().exec_id.as_deref(), Some("mock-exec-1"));
+    assert!(reloaded.get_operation("mock-op-1").is_some());
+}
+
+#[test]
+fn queue_survives_a_restart() {
+    let mut state = AppState::new();
+    let req = |target: &str| CreateTodoRequest {
+        target_account: target.to_string(),
+        target_count: 5,
+        bio_agents: 1,
+        batch_size: 5,
+        criteria_preset_id: None,
+    };
+    let a = state.create_todo(req("a")).unwrap();
+    let b = state.create_todo(req("b")).unwrap();
+    state.set_max_concurrent(3).unwrap();
+    assert_eq!(state.enqueue_todos(&[a.clone(), b.clone(), a.clone()]).unwrap(), 2);
+
+    // Todos that are not pending can't be queued
+    let queued: ScrapeStatus = serde_json::from_value(json!({ "status": "queued", "operation": "op-1" })).unwrap();
+    state.update_todo(&a, TodoStatus::Running, None, None, None).unwrap();
+    state.link_todo_scrape(&a, &queued).unwrap();
+    assert!(state.enqueue_todos(std::slice::from_ref(&a)).is_err());
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    assert_eq!(reloaded.todo_queue.max_concurrent, 3);
+    assert_eq!(reloaded.todo_queue.queued, [a.clone(), b]);
+    assert_eq!(reloaded.get_todo(&a).unwrap().operation_id.as_deref(), Some("op-1"));
+    assert_eq!(reloaded.running_jobs(), 1);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::{add_todo, demo, running_todo};
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::poller::{Poller, PollerConfig, TODO_UPDATED};
+use instagrap_lib::reconcile::{self, RECONCILIATION_FINISHED, UNREACHABLE};
+use instagrap_lib::status::{OperationStatus, TodoStatus};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+fn outcome(items: &[reconcile::ReconciledItem], id: &str) -> String {
+    items.iter().find(|item| item.id == id).unwrap().outcome.clone()
+}
+
+#[tokio::test]
+async fn finished_and_forgotten_jobs_are_settled() {
+    let (mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 0 });
+    let completed = running_todo(&state, "nike").await;
+    mock.set_scenario(MockScenario::Failure { message: "Target account is private".into() });
+    let failed = running_todo(&state, "private").await;
+
+    // Left over from a session the backend has forgotten, and a todo that never got an operation
+    let gone = add_todo(&state, "adidas");
+    let never_started = add_todo(&state, "puma");
+    {
+        let mut app_state = state.lock().unwrap();
+        app_state.update_todo(&gone, TodoStatus::Running, Some("gone-op".into()), None, None).unwrap();
+        app_state.set_todo_exec_id(&gone, "gone-exec".into()).unwrap();
+        app_state.update_todo(&never_started, TodoStatus::Running, None, None, None).unwrap();
+    }
+
+    let summary = reconcile::reconcile(&state).await.unwrap();
+    assert_eq!((summary.count("completed"), summary.count("failed"), summary.count("lost")), (1, 1, 1));
+    assert_eq!(summary.message(), "3 running jobs checked: 1 completed, 1 failed, 1 lost");
+    assert_eq!(outcome(&summary.todos, &completed), "completed");
+    assert_eq!(outcome(&summary.todos, &failed), "failed");
+    assert_eq!(outcome(&summary.todos, &gone), "lost");
+    assert_eq!(outcome(&summary.todos, &never_started), "lost");
+
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_todo(&completed).unwrap().results.as_ref().unwrap().len(), 2);
+    assert_eq!(app_state.get_operation("gone-op").unwrap().status, OperationStatus::Lost);
+    let lost = app_state.get_todo(&gone).unwrap();
+    assert!(lost.error_message.as_deref().unwrap().contains("no longer knows"));
+    assert!(lost.status.retry().is_ok());
+    assert_eq!(app_state.last_reconciliation.as_ref(), Some(&summary));
+}
+
+#[tokio::test]
+async fn unreachable_backend_changes_nothing() {
+    let (mock, state) = demo(MockScenario::default());
+    let id = running_todo(&state, "nike").await;
+    drop(mock);
+
+    let summary = reconcile::reconcile(&state).await.unwrap();
+    assert_eq!(summary.count(UNREACHABLE), 1);
+    assert!(summary.todos.is_empty());
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_todo(&id).unwrap().status, TodoStatus::Running);
+    assert_eq!(app_state.get_operation("mock-op-1").unwrap().status, OperationStatus::Running);
+}
+
+#[tokio::test]
+async fn poller_reconciles_once_at_launch() {
+    let (_mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 5 });
+    let never_started = add_todo(&state, "puma");
+    state.lock().unwrap().update_todo(&never_started, TodoStatus::Running, None, None, None).unwrap();
+    state.lock().unwrap().reconciliation_pending = true;
+
+    let events: Arc<Mutex<Vec<(String, Value)>>> = Arc::default();
+    let sink = events.clone();
+    let mut poller = Poller::new(state.clone(), PollerConfig::default(), move |event: &str, payload: Value| {
+        sink.lock().unwrap().push((event.to_string(), payload));
+    });
+    poller.tick().await;
+    poller.tick().await;
+
+    let events = events.lock().unwrap();
+    let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, [TODO_UPDATED, RECONCILIATION_FINISHED]);
+    assert_eq!(events[0].1["status"], "lost");
+    assert_eq!(events[1].1["todos"][0]["id"], never_started.as_str());
+    assert!(!state.lock().unwrap().reconciliation_pending);
+}
+
+#[tokio::test]
+async fn status_errors_do_not_fall_back_to_the_legacy_endpoint() {
+    let (mock, state) = demo(MockScenario::StatusError);
+    let id = running_todo(&state, "nike").await;
+
+    // The legacy endpoint does not know the job, but the primary one never said it was gone
+    let summary = reconcile::reconcile(&state).await.unwrap();
+    assert_eq!((summary.count(UNREACHABLE), summary.count("lost")), (1, 0));
+    assert!(!mock.requests().contains(&"GET /legacy-scrape-status".to_string()));
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_todo(&id).unwrap().status, TodoStatus::Running);
+    assert_eq!(app_state.get_operation("mock-op-1").unwrap().status, OperationStatus::Running);
+}
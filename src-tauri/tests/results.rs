@@ -0,0 +1,91 @@
+use instagrap_lib::results::{merge_results, parse_results, ProfileResult};
+use serde_json::json;
+
+#[test]
+fn parses_known_fields_and_aliases() {
+    let result = ProfileResult::parse(json!({
+        "username": " @runner_anna ",
+        "fullName": "Anna Runner",
+        "biography": "Marathons and coffee",
+        "followers": 1200,
+        "following_count": 300,
+        "is_private": false,
+        "verified": true,
+        "verdict": "yes",
+        "reason": "Mentions running",
+        "confidence": 0.92
+    }))
+    .unwrap();
+
+    assert_eq!(result.username, "runner_anna");
+    assert_eq!(result.full_name.as_deref(), Some("Anna Runner"));
+    assert_eq!(result.bio.as_deref(), Some("Marathons and coffee"));
+    assert_eq!(result.follower_count, Some(1200));
+    assert_eq!(result.following_count, Some(300));
+    assert_eq!(result.is_private, Some(false));
+    assert_eq!(result.is_verified, Some(true));
+    assert_eq!(result.confidence, Some(0.92));
+    assert!(result.extras.is_empty());
+    assert_eq!(result.profile_url(), "https://www.instagram.com/runner_anna/");
+}
+
+#[test]
+fn keeps_unknown_fields_in_extras() {
+    let raw = json!({ "username": "a", "url": "https://instagram.com/a", "batch": 3, "tags": ["x"] });
+    let result = ProfileResult::parse(raw).unwrap();
+    assert_eq!(result.extras["batch"], 3);
+
+    let written = serde_json::to_value(&result).unwrap();
+    assert_eq!(written, json!({ "username": "a", "url": "https://instagram.com/a", "batch": 3, "tags": ["x"] }));
+}
+
+#[test]
+fn rejects_invalid_results() {
+    assert!(ProfileResult::parse(json!({ "url": "https://instagram.com/x" })).is_err());
+    assert!(ProfileResult::parse(json!({ "username": "  " })).is_err());
+    assert!(ProfileResult::parse(json!({ "username": 42 })).is_err());
+    assert!(ProfileResult::parse(json!("a")).is_err());
+}
+
+#[test]
+fn bad_fields_are_kept_in_extras() {
+    let raw = json!({ "username": "a", "followers": "12k", "confidence": 1.5, "is_private": false, "bio": null });
+    let result = ProfileResult::parse(raw).unwrap();
+    assert_eq!((result.follower_count, result.confidence, result.is_private), (None, None, Some(false)));
+    assert_eq!(result.extras["followers"], "12k");
+    assert_eq!(result.extras["confidence"], 1.5);
+
+    // Stored results read back the same way
+    let written = serde_json::to_value(&result).unwrap();
+    assert_eq!(written, json!({ "username": "a", "followers": "12k", "confidence": 1.5, "is_private": false }));
+    assert_eq!(serde_json::from_value::<ProfileResult>(written).unwrap(), result);
+}
+
+#[test]
+fn batch_parse_drops_only_invalid_entries() {
+    let parsed = parse_results(vec![
+        json!({ "username": "a" }),
+        json!({ "bio": "no username" }),
+        json!({ "username": "b" }),
+    ]);
+    let usernames: Vec<_> = parsed.profiles.iter().map(|p| p.username.as_str()).collect();
+    assert_eq!(usernames, ["a", "b"]);
+    assert_eq!(parsed.rejected.len(), 1);
+    assert!(parsed.rejected[0].starts_with("#1"));
+}
+
+#[test]
+fn merging_keeps_one_profile_per_username() {
+    let profile = |username: &str, verdict: &str| ProfileResult {
+        username: username.into(),
+        verdict: Some(verdict.into()),
+        ..Default::default()
+    };
+    let mut results = vec![profile("runner_1", "maybe"), profile("runner_2", "yes")];
+    let added = merge_results(&mut results, vec![profile("Runner_1", "yes"), profile("runner_3", "yes"), profile("runner_3", "yes")]);
+
+    assert_eq!(added, 1);
+    let usernames: Vec<&str> = results.iter().map(|r| r.username.as_str()).collect();
+    assert_eq!(usernames, ["Runner_1", "runner_2", "runner_3"]);
+    assert_eq!(results[0].verdict.as_deref(), Some("yes"));
+}
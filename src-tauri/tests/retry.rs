@@ -0,0 +1,135 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::{demo, todo_request};
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::queue;
+use instagrap_lib::retry::RetryPolicy;
+use instagrap_lib::state::{AppState, AppStateManager};
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::todos::{self, CreateTodoRequest};
+
+fn add_todo(state: &AppStateManager) -> String {
+    let req = CreateTodoRequest { criteria_preset_id: Some("preset-1".to_string()), ..todo_request("nike", 2) };
+    state.lock().unwrap().create_todo(req).unwrap()
+}
+
+fn retry_at(state: &AppStateManager, todo_id: &str) -> Option<DateTime<Utc>> {
+    let app_state = state.lock().unwrap();
+    let at = app_state.get_todo(todo_id).unwrap().next_retry_at.clone()?;
+    Some(DateTime::parse_from_rfc3339(&at).unwrap().with_timezone(&Utc))
+}
+
+#[tokio::test]
+async fn retry_keeps_earlier_attempts() {
+    let (mock, state) = demo(MockScenario::Failure { message: "Target account is private".into() });
+    let id = add_todo(&state);
+    todos::run_todo(&state, &id, None).await.unwrap();
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Failed);
+
+    state.lock().unwrap().retry_todo(&id).unwrap();
+    {
+        let app_state = state.lock().unwrap();
+        let todo = app_state.get_todo(&id).unwrap();
+        assert_eq!(todo.status, TodoStatus::Pending);
+        assert!(todo.error_message.is_none() && todo.operation_id.is_none());
+        assert_eq!(todo.criteria_preset_id.as_deref(), Some("preset-1"));
+        assert_eq!(app_state.todo_queue.queued, [id.as_str()]);
+        let attempt = &todo.attempts[0];
+        assert_eq!(attempt.attempt, 1);
+        assert_eq!(attempt.status, TodoStatus::Failed);
+        assert_eq!(attempt.operation_id.as_deref(), Some("mock-op-1"));
+        assert_eq!(attempt.exec_id.as_deref(), Some("mock-exec-1"));
+        assert_eq!(attempt.error_message.as_deref(), Some("Target account is private"));
+        assert!(attempt.started_at.is_some() && !attempt.retryable);
+    }
+
+    mock.set_scenario(MockScenario::QueuedThenCompleted { polls: 0 });
+    queue::fill_slots(&state).await.unwrap();
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Completed);
+    assert!(state.lock().unwrap().retry_todo(&id).is_err());
+
+    let mut app_state = state.lock().unwrap();
+    let attempts = app_state.get_todo(&id).unwrap().attempts.clone();
+    let statuses: Vec<TodoStatus> = attempts.iter().map(|a| a.status).collect();
+    assert_eq!(statuses, [TodoStatus::Failed, TodoStatus::Completed]);
+    assert_eq!(attempts[1].operation_id.as_deref(), Some("mock-op-2"));
+
+    let reloaded = AppState::from_store(std::mem::take(&mut app_state.store)).unwrap();
+    assert_eq!(reloaded.get_todo(&id).unwrap().attempts, attempts);
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_with_backoff() {
+    // Nothing listens once the mock is gone, so starting a scrape hits a network error
+    let (mock, state) = demo(MockScenario::default());
+    drop(mock);
+    let id = add_todo(&state);
+    let policy = RetryPolicy { max_attempts: 2, backoff_seconds: 60 };
+    state.lock().unwrap().set_retry_policy(Some(policy)).unwrap();
+
+    assert!(todos::run_todo(&state, &id, None).await.unwrap_err().retryable());
+    let due = retry_at(&state, &id).unwrap();
+    assert!(due > Utc::now() + Duration::seconds(50));
+    assert!(state.lock().unwrap().get_todo(&id).unwrap().attempts[0].retryable);
+
+    assert!(state.lock().unwrap().queue_due_retries(due - Duration::seconds(1)).unwrap().is_empty());
+    assert_eq!(state.lock().unwrap().queue_due_retries(due).unwrap(), [id.as_str()]);
+    assert_eq!(state.lock().unwrap().get_todo(&id).unwrap().status, TodoStatus::Pending);
+
+    // The second failure in a row uses up the policy
+    assert_eq!(queue::fill_slots(&state).await.unwrap().failed, [id.as_str()]);
+    let app_state = state.lock().unwrap();
+    let todo = app_state.get_todo(&id).unwrap();
+    assert_eq!(todo.status, TodoStatus::Failed);
+    assert_eq!(todo.consecutive_failures(), 2);
+    assert!(todo.next_retry_at.is_none());
+}
+
+#[tokio::test]
+async fn transient_failures_reported_by_polling_are_retried() {
+    let (mock, state) = demo(MockScenario::FailsAfter { polls: 1, message: "Instagram rate limit hit, try again later".into() });
+    let id = add_todo(&state);
+    state.lock().unwrap().set_retry_policy(Some(RetryPolicy { max_attempts: 3, backoff_seconds: 60 })).unwrap();
+
+    todos::run_todo(&state, &id, None).await.unwrap();
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Running);
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Failed);
+    let due = retry_at(&state, &id).unwrap();
+    assert!(state.lock().unwrap().get_todo(&id).unwrap().attempts[0].retryable);
+
+    // A failure caused by the target itself is left for the user
+    mock.set_scenario(MockScenario::FailsAfter { polls: 0, message: "Target account is private".into() });
+    assert_eq!(state.lock().unwrap().queue_due_retries(due).unwrap(), [id.as_str()]);
+    queue::fill_slots(&state).await.unwrap();
+    assert_eq!(todos::refresh_todo(&state, &id).await.unwrap(), TodoStatus::Failed);
+    assert!(retry_at(&state, &id).is_none());
+    assert!(!state.lock().unwrap().get_todo(&id).unwrap().attempts[1].retryable);
+}
+
+#[tokio::test]
+async fn failures_are_not_retried_without_a_policy() {
+    let (mock, state) = demo(MockScenario::default());
+    drop(mock);
+    let id = add_todo(&state);
+
+    assert!(todos::run_todo(&state, &id, None).await.is_err());
+    assert!(retry_at(&state, &id).is_none());
+    assert!(state.lock().unwrap().queue_due_retries(Utc::now() + Duration::days(1)).unwrap().is_empty());
+}
+
+#[test]
+fn retry_policy_is_validated_and_saved() {
+    let policy = RetryPolicy { max_attempts: 3, backoff_seconds: 30 };
+    assert_eq!(policy.backoff(1), Duration::seconds(30));
+    assert_eq!(policy.backoff(3), Duration::seconds(120));
+    assert_eq!(policy.backoff(20), Duration::hours(1));
+
+    let mut state = AppState::new();
+    assert!(state.set_retry_policy(Some(RetryPolicy { max_attempts: 0, backoff_seconds: 30 })).is_err());
+    assert!(state.set_retry_policy(Some(RetryPolicy { max_attempts: 3, backoff_seconds: 0 })).is_err());
+    state.set_retry_policy(Some(policy.clone())).unwrap();
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    assert_eq!(reloaded.retry_policy, Some(policy));
+}
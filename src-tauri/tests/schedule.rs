@@ -0,0 +1,150 @@
+mod common;
+
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use common::{demo, todo_request};
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::queue;
+use instagrap_lib::schedule::{CronExpr, QuietHours, Schedule};
+use instagrap_lib::state::AppState;
+use instagrap_lib::status::TodoStatus;
+use instagrap_lib::todos;
+
+fn utc(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn time(s: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+}
+
+#[test]
+fn cron_expressions_find_the_next_run() {
+    // Friday 2026-10-16 09:30
+    let now = utc("2026-10-16T09:30:00Z");
+    let next = |expr: &str| CronExpr::parse(expr).unwrap().next_after(&now).unwrap();
+
+    assert_eq!(next("*/15 * * * *"), utc("2026-10-16T09:45:00Z"));
+    assert_eq!(next("0 9 * * *"), utc("2026-10-17T09:00:00Z"));
+    assert_eq!(next("0 9 * * 1-5"), utc("2026-10-19T09:00:00Z"));
+    assert_eq!(next("30 8 1 * *"), utc("2026-11-01T08:30:00Z"));
+    assert_eq!(next("0 0 * * 7"), utc("2026-10-18T00:00:00Z"));
+    assert_eq!(next("0 12 29 2 *"), utc("2028-02-29T12:00:00Z"));
+
+    for invalid in ["* * * *", "60 * * * *", "* * * 13 *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+        assert!(CronExpr::parse(invalid).is_err(), "{} should not parse", invalid);
+    }
+}
+
+#[test]
+fn quiet_hours_wrap_past_midnight() {
+    let quiet = QuietHours { start: "22:00".to_string(), end: "07:00".to_string() };
+    assert!(quiet.contains(time("23:30")));
+    assert!(quiet.contains(time("06:59")));
+    assert!(!quiet.contains(time("07:00")));
+    assert!(!quiet.contains(time("12:00")));
+
+    let late = Utc.with_ymd_and_hms(2026, 10, 16, 23, 0, 0).unwrap();
+    assert_eq!(quiet.defer(late), utc("2026-10-17T07:00:00Z"));
+    let early = Utc.with_ymd_and_hms(2026, 10, 17, 3, 0, 0).unwrap();
+    assert_eq!(quiet.defer(early), utc("2026-10-17T07:00:00Z"));
+    let noon = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+    assert_eq!(quiet.defer(noon), noon);
+
+    let mut state = AppState::new();
+    let same = QuietHours { start: "08:00".to_string(), end: "08:00".to_string() };
+    assert!(state.set_quiet_hours(Some(same)).is_err());
+    assert!(state.set_quiet_hours(Some(QuietHours { start: "25:00".to_string(), end: "07:00".to_string() })).is_err());
+}
+
+#[tokio::test]
+async fn due_runs_start_a_new_operation_each_time() {
+    let (_mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 0 });
+    let id = state.lock().unwrap().create_todo(todo_request("nike", 2)).unwrap();
+    todos::run_todo(&state, &id, None).await.unwrap();
+    todos::refresh_todo(&state, &id).await.unwrap();
+    assert_eq!(state.lock().unwrap().get_todo(&id).unwrap().status, TodoStatus::Completed);
+
+    let schedule = state.lock().unwrap().set_todo_schedule(&id, Schedule::Interval { minutes: 60 }).unwrap();
+    let due = schedule.next_run_at.unwrap();
+    assert!(state.lock().unwrap().queue_due_runs(due - Duration::minutes(1)).unwrap().is_empty());
+    assert_eq!(state.lock().unwrap().queue_due_runs(due).unwrap(), [id.as_str()]);
+    {
+        let app_state = state.lock().unwrap();
+        let todo = app_state.get_todo(&id).unwrap();
+        assert_eq!(todo.status, TodoStatus::Pending);
+        assert!(todo.operation_id.is_none());
+        assert_eq!(app_state.todo_schedules[0].next_run_at, Some(due + Duration::minutes(60)));
+        // The previous run keeps its results
+        assert!(app_state.get_operation("mock-op-1").unwrap().results.is_some());
+    }
+
+    assert_eq!(queue::fill_slots(&state).await.unwrap().started, [id.as_str()]);
+    let app_state = state.lock().unwrap();
+    assert_eq!(app_state.get_todo(&id).unwrap().operation_id.as_deref(), Some("mock-op-2"));
+    assert_eq!(app_state.operations().len(), 2);
+}
+
+#[test]
+fn running_todos_skip_their_run() {
+    let mut state = AppState::new();
+    let id = state.create_todo(todo_request("adidas", 2)).unwrap();
+    let at = utc("2026-10-16T09:00:00Z");
+    state.set_todo_schedule(&id, Schedule::Once { at }).unwrap();
+    state.update_todo(&id, TodoStatus::Running, None, None, None).unwrap();
+
+    assert!(state.queue_due_runs(at).unwrap().is_empty());
+    assert_eq!(state.get_todo(&id).unwrap().status, TodoStatus::Running);
+    // A one-off schedule is spent either way
+    assert_eq!(state.todo_schedules[0].next_run_at, None);
+    assert!(state.upcoming_runs(10, at).is_empty());
+}
+
+#[test]
+fn paused_schedules_are_not_queued_or_listed() {
+    let mut state = AppState::new();
+    let a = state.create_todo(todo_request("a", 2)).unwrap();
+    let b = state.create_todo(todo_request("b", 2)).unwrap();
+    let now = Utc::now();
+    state.set_todo_schedule(&a, Schedule::Interval { minutes: 30 }).unwrap();
+    state.set_todo_schedule(&b, Schedule::Once { at: now + Duration::minutes(45) }).unwrap();
+
+    let runs = state.upcoming_runs(3, now);
+    let ids: Vec<&str> = runs.iter().map(|r| r["todo_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, [a.as_str(), b.as_str(), a.as_str()]);
+    assert_eq!(runs[1]["target_account"], "b");
+    assert_eq!(runs[1]["delayed_by_quiet_hours"], false);
+
+    state.set_schedule_paused(&a, true).unwrap();
+    assert_eq!(state.upcoming_runs(3, now).len(), 1);
+    assert_eq!(state.queue_due_runs(now + Duration::hours(2)).unwrap(), [b.as_str()]);
+    assert_eq!(state.get_todo(&a).unwrap().status, TodoStatus::Pending);
+    assert!(!state.todo_queue.queued.contains(&a));
+
+    // Resuming counts the next run from now rather than catching up
+    let resumed = state.set_schedule_paused(&a, false).unwrap();
+    assert!(resumed.next_run_at.unwrap() > Utc::now());
+    assert!(state.set_schedule_paused("missing", true).is_err());
+}
+
+#[test]
+fn schedules_survive_a_restart() {
+    let mut state = AppState::new();
+    let a = state.create_todo(todo_request("a", 2)).unwrap();
+    let b = state.create_todo(todo_request("b", 2)).unwrap();
+    state.set_todo_schedule(&a, Schedule::Cron { expression: "0 9 * * 1-5".to_string() }).unwrap();
+    state.set_todo_schedule(&b, Schedule::Interval { minutes: 90 }).unwrap();
+    state.set_schedule_paused(&b, true).unwrap();
+    let quiet = QuietHours { start: "22:00".to_string(), end: "07:00".to_string() };
+    state.set_quiet_hours(Some(quiet.clone())).unwrap();
+    assert!(state.set_todo_schedule(&a, Schedule::Interval { minutes: 0 }).is_err());
+    assert!(state.set_todo_schedule("missing", Schedule::Interval { minutes: 5 }).is_err());
+
+    // Deleting a todo drops its schedule
+    state.delete_todo(&a).unwrap();
+    let expected = state.todo_schedules.clone();
+    assert_eq!(expected.len(), 1);
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    assert_eq!(reloaded.todo_schedules, expected);
+    assert_eq!(reloaded.quiet_hours, Some(quiet));
+}
@@ -0,0 +1,52 @@
+use instagrap_lib::settings::{Environment, Settings};
+use instagrap_lib::state::AppState;
+use instagrap_lib::store::Store;
+
+fn local(api_base: &str) -> Environment {
+    Environment {
+        name: "local".into(),
+        api_base: api_base.into(),
+        classifier_base: "http://127.0.0.1:8081".into(),
+        gcp_project: "dev-project".into(),
+        default_bucket: "gs://dev-bucket/".into(),
+    }
+}
+
+#[test]
+fn defaults_are_valid() {
+    let settings = Settings::default();
+    assert!(settings.problems().is_empty());
+    assert_eq!(settings.active().unwrap().name, "prod");
+    assert!(settings.environment("staging").is_some() && settings.environment("local").is_some());
+}
+
+#[test]
+fn reports_every_problem() {
+    let mut bad = local("not a url");
+    bad.default_bucket = "s3://nope".into();
+    let settings = Settings { active_environment: "qa".into(), environments: vec![bad, local("http://127.0.0.1:9000")] };
+    let problems = settings.problems();
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+    assert_eq!(settings.validate().unwrap_err().code(), "validation");
+}
+
+#[test]
+fn switching_environment_repoints_clients_and_persists() {
+    let mut state = AppState::from_store(Store::open_in_memory().unwrap()).unwrap();
+    let mut settings = Settings::default();
+    settings.environments.retain(|e| e.name != "local");
+    settings.environments.push(local("http://127.0.0.1:9000/"));
+    state.set_settings(settings).unwrap();
+    state.set_active_environment("local").unwrap();
+    assert_eq!(state.api.base_url(), "http://127.0.0.1:9000/");
+    assert_eq!(state.classifier.base_url(), "http://127.0.0.1:8081/");
+
+    // Invalid settings are rejected and leave the current ones in place
+    let invalid = Settings { active_environment: "missing".into(), ..state.settings.clone() };
+    assert!(state.set_settings(invalid).is_err());
+    assert!(state.set_active_environment("missing").is_err());
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    assert_eq!(reloaded.settings.active_environment, "local");
+    assert_eq!(reloaded.api.base_url(), "http://127.0.0.1:9000/");
+}
@@ -0,0 +1,135 @@
+use instagrap_lib::api::ScrapeStatus;
+use instagrap_lib::state::AppState;
+use instagrap_lib::status::{OperationStatus, TodoStatus};
+use instagrap_lib::store::{Store, WhenLocked};
+use instagrap_lib::todos::CreateTodoRequest;
+use serde_json::json;
+
+fn scrape_status(value: serde_json::Value) -> ScrapeStatus {
+    serde_json::from_value(value).unwrap()
+}
+
+fn todo_request(target: &str, preset: Option<String>) -> CreateTodoRequest {
+    CreateTodoRequest {
+        target_account: target.to_string(),
+        target_count: 50,
+        bio_agents: 2,
+        batch_size: 10,
+        criteria_preset_id: preset,
+    }
+}
+
+#[test]
+fn todo_lifecycle_is_persisted() {
+    let mut state = AppState::new();
+    let preset = state.add_criteria_preset("Runners".into(), "Bio mentions running".into()).unwrap();
+    let id = state.create_todo(todo_request("nike", Some(preset))).unwrap();
+    assert_eq!(state.get_todo(&id).unwrap().criteria_preset_name.as_deref(), Some("Runners"));
+
+    state.update_todo_status(&id, "running", Some("op-1".into()), Some("exec-1".into()), None, None).unwrap();
+    let results = vec![json!({ "username": "a" }), json!({ "bio": "dropped" })];
+    state.update_todo_status(&id, "completed", Some("op-1".into()), None, Some(results), None).unwrap();
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    let todo = reloaded.get_todo(&id).unwrap();
+    assert_eq!(todo.status, TodoStatus::Completed);
+    assert_eq!(todo.exec_id.as_deref(), Some("exec-1"));
+    assert_eq!(todo.results.as_ref().unwrap().len(), 1);
+    assert!(todo.started_at.is_some() && todo.completed_at.is_some());
+}
+
+#[test]
+fn rejects_invalid_todo_updates() {
+    let mut state = AppState::new();
+    let id = state.create_todo(todo_request("nike", None)).unwrap();
+    assert!(state.update_todo_status(&id, "done", None, None, None, None).is_err());
+    assert!(state.update_todo_status(&id, "completed", None, None, None, None).is_err());
+    assert_eq!(state.get_todo(&id).unwrap().status, TodoStatus::Pending);
+}
+
+#[test]
+fn records_scrape_responses_and_prunes_history() {
+    let mut state = AppState::from_store(Store::open_in_memory().unwrap()).unwrap();
+    state
+        .record_scrape_response("nike", 10, &scrape_status(json!({ "status": "queued", "operation": "op-1", "exec_id": "e1" })))
+        .unwrap();
+    state
+        .record_scrape_response("@Nike", 10, &scrape_status(json!({ "status": "completed", "results": [{ "username": "a" }] })))
+        .unwrap();
+    state.record_scrape_response("nike", 10, &scrape_status(json!({ "status": "failed" }))).unwrap();
+
+    let history = state.get_target_history("nike");
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].status, OperationStatus::Completed);
+    assert_eq!(state.get_running_operations().len(), 1);
+
+    // The running operation is never pruned
+    assert_eq!(state.prune_target_history("nike", 0).unwrap(), 1);
+    assert_eq!(state.get_operation("op-1").unwrap().exec_id.as_deref(), Some("e1"));
+}
+
+#[test]
+fn deleting_active_preset_falls_back_to_default() {
+    let mut state = AppState::new();
+    let id = state.add_criteria_preset("A".into(), "criteria".into()).unwrap();
+    state.set_active_criteria(Some(id.clone())).unwrap();
+    assert!(state.set_active_criteria(Some("missing".into())).is_err());
+
+    state.delete_criteria_preset(&id).unwrap();
+    assert_eq!(state.active_criteria_id, None);
+    assert_eq!(state.criteria_for_job(Some(id), None), (None, None));
+}
+
+#[test]
+fn profile_search_matches_wildcards_literally() {
+    let mut state = AppState::from_store(Store::open_in_memory().unwrap()).unwrap();
+    let results = json!([{ "username": "run_anna" }, { "username": "runxanna" }, { "username": "100%_runner" }]);
+    state
+        .record_scrape_response("nike", 10, &scrape_status(json!({ "status": "completed", "results": results })))
+        .unwrap();
+
+    let search = |query: &str| {
+        let (profiles, total) = state.store.search_profiles(Some(query), None, 10, 0).unwrap();
+        let mut usernames: Vec<String> = profiles.into_iter().map(|p| p.username).collect();
+        usernames.sort();
+        (usernames, total)
+    };
+    assert_eq!(search("n_a"), (vec!["run_anna".to_string()], 1));
+    assert_eq!(search("%"), (vec!["100%_runner".to_string()], 1));
+    assert_eq!(search("runner").1, 1);
+    assert_eq!(search("").1, 3);
+}
+
+#[test]
+fn state_database_is_used_by_one_process_at_a_time() {
+    let dir = std::env::temp_dir().join(format!("instagrap-lock-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let store = Store::open_in_dir(&dir, WhenLocked::Fail).unwrap();
+    let error = Store::open_in_dir(&dir, WhenLocked::Fail).err().unwrap();
+    assert!(error.to_string().contains("in use"));
+    drop(store);
+    Store::open_in_dir(&dir, WhenLocked::Fail).unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn profiles_are_keyed_by_lowercased_username() {
+    let mut state = AppState::from_store(Store::open_in_memory().unwrap()).unwrap();
+    let id = state.create_todo(todo_request("nike", None)).unwrap();
+    state.update_todo_status(&id, "running", Some("op-1".into()), None, None, None).unwrap();
+    let first = vec![json!({ "username": "Foo" })];
+    state.update_todo_status(&id, "running", None, None, Some(first), None).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    let second = vec![json!({ "username": "Foo" }), json!({ "username": "@foo " })];
+    state.update_todo_status(&id, "running", None, None, Some(second), None).unwrap();
+
+    let (profiles, total) = state.store.search_profiles(None, None, 10, 0).unwrap();
+    assert_eq!((profiles[0].username.as_str(), total), ("foo", 1));
+    // Saving another batch of the same run doesn't count as a new sighting
+    let started_at = state.get_todo(&id).unwrap().started_at.unwrap();
+    assert_eq!(profiles[0].last_seen_at, started_at);
+    assert_eq!(profiles[0].sightings.len(), 1);
+    assert!(state.store.get_profile(" @FOO").unwrap().is_some());
+}
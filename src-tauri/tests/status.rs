@@ -0,0 +1,37 @@
+use instagrap_lib::status::{OperationStatus, TodoStatus};
+
+#[test]
+fn statuses_serialize_to_legacy_strings() {
+    assert_eq!(serde_json::to_string(&TodoStatus::Running).unwrap(), "\"running\"");
+    assert_eq!(serde_json::to_string(&OperationStatus::Cancelled).unwrap(), "\"cancelled\"");
+    let parsed: TodoStatus = serde_json::from_str("\"completed\"").unwrap();
+    assert_eq!(parsed, TodoStatus::Completed);
+    assert!("complete".parse::<TodoStatus>().is_err());
+}
+
+#[test]
+fn forward_lifecycle_is_allowed() {
+    let running = TodoStatus::Pending.transition(TodoStatus::Running).unwrap();
+    for end in [TodoStatus::Completed, TodoStatus::Failed, TodoStatus::Cancelled] {
+        assert_eq!(running.transition(end).unwrap(), end);
+    }
+    assert_eq!(running.transition(TodoStatus::Running).unwrap(), TodoStatus::Running);
+}
+
+#[test]
+fn finished_jobs_cannot_move_backwards() {
+    let err = TodoStatus::Completed.transition(TodoStatus::Running).unwrap_err();
+    assert_eq!((err.entity, err.from.as_str(), err.to.as_str()), ("todo", "completed", "running"));
+    assert!(OperationStatus::Failed.transition(OperationStatus::Completed).is_err());
+    assert!(TodoStatus::Pending.transition(TodoStatus::Completed).is_err());
+}
+
+#[test]
+fn retry_and_reopen_are_explicit() {
+    assert_eq!(TodoStatus::Failed.retry().unwrap(), TodoStatus::Pending);
+    assert_eq!(OperationStatus::Cancelled.retry().unwrap(), OperationStatus::Pending);
+    assert!(TodoStatus::Completed.retry().is_err());
+    assert_eq!(TodoStatus::Completed.reopen().unwrap(), TodoStatus::Pending);
+    assert!(TodoStatus::Failed.reopen().is_err());
+    assert!(TodoStatus::Running.complete_manually().is_err());
+}
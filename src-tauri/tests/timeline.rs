@@ -0,0 +1,117 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::demo;
+use instagrap_lib::api::ScrapeStatus;
+use instagrap_lib::mock::MockScenario;
+use instagrap_lib::operations::{self, ScrapeRequest, ScrapingOperation};
+use instagrap_lib::state::AppState;
+use instagrap_lib::status::OperationStatus;
+use instagrap_lib::timeline::{EventKind, Progress};
+use serde_json::json;
+
+fn running_operation(id: &str) -> ScrapingOperation {
+    ScrapingOperation {
+        operation_id: id.into(),
+        target_account: "nike".into(),
+        target_count: 2,
+        started_at: Utc::now().to_rfc3339(),
+        status: OperationStatus::Running,
+        results: None,
+        error_message: None,
+        exec_id: None,
+        events: Vec::new(),
+    }
+}
+
+fn running(scanned: u64) -> ScrapeStatus {
+    let mut status = ScrapeStatus { status: "running".into(), ..Default::default() };
+    status.extra.insert("progress".into(), json!({ "scanned": scanned, "yes": 1 }));
+    status
+}
+
+fn kinds(state: &AppState, id: &str) -> Vec<EventKind> {
+    state.get_operation(id).unwrap().events.iter().map(|e| e.kind).collect()
+}
+
+#[tokio::test]
+async fn records_polls_messages_and_completion() {
+    let (_mock, state) = demo(MockScenario::QueuedThenCompleted { polls: 3 });
+    let req = ScrapeRequest {
+        target: "nike".into(),
+        target_yes: 2,
+        batch_size: 5,
+        num_bio_pages: 1,
+        criteria_preset_id: None,
+        criteria_text: None,
+        on_conflict: None,
+    };
+    operations::start_remote_scrape(&state, req).await.unwrap();
+    for _ in 0..4 {
+        operations::check_operation_status(&state, "mock-op-1").await.unwrap();
+    }
+
+    let app_state = state.lock().unwrap();
+    use EventKind::*;
+    assert_eq!(kinds(&app_state, "mock-op-1"), [Queued, Poll, Message, Poll, Poll, Poll, Completed]);
+    let events = &app_state.get_operation("mock-op-1").unwrap().events;
+    assert_eq!(events[1].status.as_deref(), Some("running"));
+    assert_eq!(events[1].progress, Some(Progress { scanned: Some(25), classified: Some(20), yes_count: Some(1) }));
+    assert_eq!(events[2].message.as_deref(), Some("Scanning followers of nike"));
+    assert_eq!(events[5].status.as_deref(), Some("completed"));
+    assert_eq!(events[6].message.as_deref(), Some("2 results"));
+    assert_eq!(events.iter().map(|e| e.seq).collect::<Vec<_>>(), [1, 2, 3, 4, 5, 6, 7]);
+}
+
+#[test]
+fn operations_without_progress_stall_and_resume() {
+    let mut state = AppState::new();
+    let start = Utc::now();
+    state.add_operation(running_operation("op-1")).unwrap();
+    state.record_poll("op-1", &running(10), start).unwrap();
+    state.record_poll("op-1", &running(10), start + Duration::minutes(5)).unwrap();
+
+    let operation = state.get_operation("op-1").unwrap();
+    assert_eq!(operation.events.len(), 2);
+    assert_eq!(operation.events[1].polls, 2);
+    assert_eq!(operation.last_activity_at(), start.to_rfc3339());
+
+    assert!(state.detect_stalled_operations(start + Duration::minutes(10)).unwrap().is_empty());
+    assert_eq!(state.detect_stalled_operations(start + Duration::minutes(16)).unwrap(), ["op-1"]);
+    assert!(state.detect_stalled_operations(start + Duration::minutes(30)).unwrap().is_empty());
+    let operation = state.get_operation("op-1").unwrap();
+    assert_eq!(operation.status, OperationStatus::Stalled);
+    assert_eq!(operation.events[2].message.as_deref(), Some("No progress for 16 minutes"));
+    // Still polled and counted against the concurrency limit
+    assert_eq!(state.get_running_operations().len(), 1);
+    assert_eq!(state.running_jobs(), 1);
+
+    // The same answer again changes nothing, new counters resume it
+    state.record_poll("op-1", &running(10), start + Duration::minutes(17)).unwrap();
+    assert_eq!(state.get_operation("op-1").unwrap().status, OperationStatus::Stalled);
+    state.record_poll("op-1", &running(40), start + Duration::minutes(18)).unwrap();
+    use EventKind::*;
+    assert_eq!(kinds(&state, "op-1"), [Queued, Poll, Stalled, Poll, Resumed]);
+    assert_eq!(state.get_operation("op-1").unwrap().status, OperationStatus::Running);
+
+    // A stalled operation can still finish
+    state.detect_stalled_operations(start + Duration::minutes(40)).unwrap();
+    state.update_operation("op-1", OperationStatus::Completed, Some(Vec::new()), None).unwrap();
+    assert_eq!(kinds(&state, "op-1").last(), Some(&Completed));
+}
+
+#[test]
+fn timelines_and_threshold_are_persisted() {
+    let mut state = AppState::new();
+    assert!(state.set_stall_after_minutes(0).is_err());
+    state.set_stall_after_minutes(45).unwrap();
+    state.add_operation(running_operation("op-1")).unwrap();
+    state.record_poll("op-1", &running(10), Utc::now()).unwrap();
+    state.record_poll("op-1", &running(10), Utc::now()).unwrap();
+    assert!(state.detect_stalled_operations(Utc::now() + Duration::minutes(30)).unwrap().is_empty());
+
+    let reloaded = AppState::from_store(std::mem::take(&mut state.store)).unwrap();
+    assert_eq!(reloaded.stall_after_minutes, 45);
+    assert_eq!(reloaded.get_operation("op-1").unwrap().events, state.get_operation("op-1").unwrap().events);
+    assert_eq!(reloaded.get_operation("op-1").unwrap().events[1].polls, 2);
+}
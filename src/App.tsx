@@ -16,15 +16,20 @@ function App() {
     initializing,
     todos,
     loadingTodos,
+    reconciliation,
 
     // Setters
     setLoginState,
     setTodos,
+    setReconciliation,
 
     // Actions
     checkExistingLogin,
     addTodo,
     runTodoById,
+    runAllPendingTodos,
+    cancelRunningTodo,
+    retryTodoById,
     toggleTodoManualCompleteById,
     deleteTodoById,
     downloadTodoResults,
@@ -83,6 +88,22 @@ function App() {
         />
       </div>
 
+      {/* Jobs the startup check finished or found lost while the app was closed */}
+      {reconciliation && reconciliation.todos.length > 0 && (
+        <div style={{ background: '#eff6ff', border: '1px solid #bfdbfe', borderRadius: 8, padding: '10px 14px', marginBottom: 12, display: 'flex', justifyContent: 'space-between', alignItems: 'center', gap: 12 }}>
+          <span style={{ color: '#1e3a8a' }}>
+            Checked jobs left running since the last session:{' '}
+            {reconciliation.todos.map(t => `@${t.target_account} ${t.outcome}`).join(', ')}
+          </span>
+          <button
+            onClick={() => setReconciliation(null)}
+            style={{ background: 'none', border: 'none', color: '#1e3a8a', cursor: 'pointer', fontWeight: 600 }}
+          >
+            Dismiss
+          </button>
+        </div>
+      )}
+
       {/* Two-column responsive layout */}
       <div style={{ display: 'grid', gridTemplateColumns: isWide ? '2fr 1fr' : '1fr', gap: '24px' }}>
         {/* Left column: Todos */}
@@ -93,6 +114,9 @@ function App() {
             loginState={loginState}
             onAddTodo={addTodo}
             onRunTodo={runTodoById}
+            onRunAllTodos={runAllPendingTodos}
+            onCancelTodo={cancelRunningTodo}
+            onRetryTodo={retryTodoById}
             onToggleManualComplete={toggleTodoManualCompleteById}
             onDeleteTodo={deleteTodoById}
             onDownloadResults={downloadTodoResults}
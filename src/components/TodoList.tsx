@@ -9,6 +9,9 @@ export const TodoList: React.FC<TodoListProps> = ({
   loginState,
   onAddTodo,
   onRunTodo,
+  onRunAllTodos,
+  onCancelTodo,
+  onRetryTodo,
   onToggleManualComplete,
   onDeleteTodo,
   onDownloadResults,
@@ -153,8 +156,9 @@ export const TodoList: React.FC<TodoListProps> = ({
       const bt = toTime(b.completed_at) || toTime(b.started_at) || toTime(b.created_at);
       return bt - at;
     });
+  // Cancelled and lost todos sit with the failed ones; all can only be deleted or retried
   const failedTodos = todos
-    .filter(t => t.status === 'failed')
+    .filter(t => t.status === 'failed' || t.status === 'cancelled' || t.status === 'lost')
     .sort((a, b) => {
       const at = toTime(a.completed_at) || toTime(a.started_at) || toTime(a.created_at);
       const bt = toTime(b.completed_at) || toTime(b.started_at) || toTime(b.created_at);
@@ -248,6 +252,19 @@ export const TodoList: React.FC<TodoListProps> = ({
           <h3 style={styles.sectionTitle}>Pending</h3>
           <div style={{ display: 'flex', gap: '8px', alignItems: 'center' }}>
             <span style={styles.pillCounter}>{pendingTodos.length}</span>
+            <button
+              type="button"
+              onClick={() => onRunAllTodos().catch(() => {})}
+              disabled={loginState !== 'done' || pendingTodos.length === 0}
+              title="Queue every pending todo; they start as running jobs finish"
+              style={{
+                ...styles.button,
+                ...styles.smallButton,
+                ...(loginState === 'done' && pendingTodos.length > 0 ? styles.buttonSuccess : styles.buttonDisabled),
+              }}
+            >
+              Run all
+            </button>
             <button
               type="button"
               onClick={() => setOpenGroups(g => ({ ...g, pending: !g.pending }))}
@@ -429,6 +446,18 @@ export const TodoList: React.FC<TodoListProps> = ({
                       </svg>
                       Running
                     </span>
+                    {todo.operation_id && (
+                      <button
+                        onClick={() => onCancelTodo(todo).catch(() => {})}
+                        style={{ ...styles.button, ...styles.smallButton, ...styles.buttonDanger, display: 'inline-flex', alignItems: 'center', gap: 8 }}
+                        title="Stop the scrape; profiles found so far are kept"
+                      >
+                        <svg width="16" height="16" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" aria-hidden>
+                          <path d="M18 6L6 18M6 6l12 12" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round" />
+                        </svg>
+                        Cancel
+                      </button>
+                    )}
                     <button
                       onClick={() => onToggleManualComplete(todo.id)}
                       style={{ ...styles.button, ...styles.smallButton, ...(todo.manually_completed ? styles.buttonSecondary : styles.buttonSuccess), display: 'inline-flex', alignItems: 'center', gap: 8 }}
@@ -580,8 +609,22 @@ export const TodoList: React.FC<TodoListProps> = ({
                         <strong>Error:</strong> {todo.error_message}
                       </div>
                     )}
+                    <div style={styles.todoDates}>
+                      {(todo.attempts?.length ?? 0) > 1 && <small>Attempts: {todo.attempts!.length}</small>}
+                      {todo.next_retry_at && <small>Retrying at {formatDate(todo.next_retry_at)}</small>}
+                    </div>
                   </div>
                   <div style={styles.todoActions}>
+                    <button
+                      onClick={() => onRetryTodo(todo.id).catch(() => {})}
+                      style={{ ...styles.button, ...styles.smallButton, ...styles.buttonSecondary, display: 'inline-flex', alignItems: 'center', gap: 8 }}
+                      title="Queue this todo again with the same settings"
+                    >
+                      <svg width="16" height="16" viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg" aria-hidden>
+                        <path d="M4 4v6h6M20 20v-6h-6M4 10a8 8 0 0 1 14-3M20 14a8 8 0 0 1-14 3" stroke="currentColor" strokeWidth="2" strokeLinecap="round" strokeLinejoin="round"/>
+                      </svg>
+                      Retry
+                    </button>
                     <button
                       onClick={() => onDeleteTodo(todo.id)}
                       style={{ ...styles.button, ...styles.smallButton, ...styles.buttonDanger, display: 'inline-flex', alignItems: 'center', gap: 8 }}
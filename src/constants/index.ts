@@ -1,5 +1,4 @@
-// API Configuration
-export const API_BASE = "https://instagram-api-672383441505.europe-west1.run.app";
+// Service endpoints come from the active environment, see getSettings()
 
 // Default Values
 export const DEFAULT_FOLLOWER_COUNT = 50;
@@ -16,9 +15,6 @@ export const DEFAULT_BATCH_SIZE = 30;
 export const MAX_BATCH_SIZE = 100;
 export const MIN_BATCH_SIZE = 10;
 
-// Polling Configuration
-export const POLLING_INTERVAL = 5000; // 5 seconds
-
 // File Download Configuration
 export const DOWNLOAD_FILENAME_PREFIX = "instagram_profiles";
 export const CSV_HEADERS = ["Username", "Profile URL"];
@@ -40,6 +36,10 @@ export const STATUS_COLORS = {
     background: "#fef3c7",
     color: "#92400e"
   },
+  stalled: {
+    background: "#ffedd5",
+    color: "#c2410c"
+  },
   completed: {
     background: "#dcfce7",
     color: "#166534"
@@ -47,5 +47,13 @@ export const STATUS_COLORS = {
   failed: {
     background: "#fee2e2",
     color: "#dc2626"
+  },
+  cancelled: {
+    background: "#f3f4f6",
+    color: "#4b5563"
+  },
+  lost: {
+    background: "#ede9fe",
+    color: "#5b21b6"
   }
 } as const; 
\ No newline at end of file
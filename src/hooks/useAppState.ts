@@ -1,22 +1,24 @@
 import { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { 
   LoginState, 
   InstagramProfile, 
-  ScrapingTodo
+  ScrapingTodo,
+  ReconcileSummary
 } from '../types';
 import { 
   checkLoginStatus, 
-  checkScrapeStatus,
   createTodo,
   getTodos,
-  updateTodoStatus,
   toggleTodoManualComplete,
   deleteTodo,
   runTodo,
-  deleteGcsArtifacts,
+  runAllTodos,
+  cancelOperation,
+  retryTodo,
+  getReconciliationSummary,
 } from '../utils/api';
 import { downloadCSV } from '../utils/download';
-import { POLLING_INTERVAL } from '../constants';
 
 export const useAppState = () => {
   // Core state
@@ -26,6 +28,8 @@ export const useAppState = () => {
   // Todo system state
   const [todos, setTodos] = useState<ScrapingTodo[]>([]);
   const [loadingTodos, setLoadingTodos] = useState(false);
+  // What the startup check found among jobs left running
+  const [reconciliation, setReconciliation] = useState<ReconcileSummary | null>(null);
 
   // Check existing login
   const checkExistingLogin = async () => {
@@ -74,6 +78,7 @@ export const useAppState = () => {
       try {
         await checkExistingLogin();
         await loadTodos();
+        setReconciliation(await getReconciliationSummary());
       } finally {
         setInitializing(false);
       }
@@ -81,158 +86,29 @@ export const useAppState = () => {
     initializeApp();
   }, []);
 
-  // Poll for todo status updates
+  // The Rust poller refreshes running todos and pushes every change
   useEffect(() => {
-    const runningTodos = todos.filter(todo => todo.status === "running" && todo.operation_id);
-    if (runningTodos.length === 0) return;
-
-    const intervals: Record<string, ReturnType<typeof setInterval>> = {};
-    // Per-todo in-flight guard to avoid overlapping polls
-    const inFlight: Record<string, boolean> = {};
-    // Per-todo backoff in ms for transient failures/timeouts
-    const backoffMs: Record<string, number> = {};
-
-    runningTodos.forEach((todo) => {
-      if (!todo.operation_id) return;
-      
-      intervals[todo.id] = setInterval(async () => {
-        try {
-          // Skip this tick if a previous request has not finished yet
-          if (inFlight[todo.id]) {
-            // console.log("⏸️ [DEBUG] Skipping poll tick (in-flight)", todo.id);
-            return;
-          }
-          inFlight[todo.id] = true;
-
-          const response = await checkScrapeStatus(todo.operation_id!);
-          // Reset backoff on success
-          backoffMs[todo.id] = 0;
-          if (response.status === "completed") {
-            console.log("✅ [DEBUG] Todo completed, stopping polling for:", todo.id);
-            // Clear the interval for this specific todo
-            if (intervals[todo.id]) {
-              clearInterval(intervals[todo.id]);
-              delete intervals[todo.id];
-            }
-            
-            const execIdResolved: string | undefined = (todo as any).exec_id;
-            await updateTodoStatus(
-              todo.id,
-              "completed",
-              todo.operation_id,
-              execIdResolved,
-              response.results,
-              undefined
-            );
-            // After persisting locally, clean up GCS artifacts
-            try {
-              const execId = execIdResolved || undefined;
-              if (execId) {
-                await deleteGcsArtifacts(todo.target_account, execId);
-                console.log("🧹 [DEBUG] Deleted GCS artifacts for exec_id:", execId);
-              } else {
-                console.warn("⚠️ [DEBUG] No exec_id found on todo; skipping artifact deletion");
-              }
-            } catch (cleanupErr) {
-              console.warn("⚠️ [DEBUG] Failed to delete GCS artifacts:", cleanupErr);
-            }
-            await loadTodos();
-          } else if (response.status === "failed") {
-            console.log("❌ [DEBUG] Todo failed, stopping polling for:", todo.id);
-            // Clear the interval for this specific todo
-            if (intervals[todo.id]) {
-              clearInterval(intervals[todo.id]);
-              delete intervals[todo.id];
-            }
-            
-            const execIdResolved: string | undefined = (todo as any).exec_id;
-            await updateTodoStatus(
-              todo.id,
-              "failed",
-              todo.operation_id,
-              execIdResolved,
-              undefined,
-              response.message
-            );
-            await loadTodos();
-          }
-        } catch (e: any) {
-          console.log("❌ [DEBUG] Todo polling error for:", todo.id);
-          console.log("❌ [DEBUG] Todo operation_id:", todo.operation_id);
-          console.log("❌ [DEBUG] Todo status:", todo.status);
-          console.log("❌ [DEBUG] Error details:", {
-            message: e.message,
-            name: e.name,
-            stack: e.stack,
-            fullError: e
-          });
-          
-          // Handle "Operation not found" error specifically
-          if (e.message && e.message.includes("Operation not found")) {
-            console.log("🔄 [DEBUG] Operation not found, marking todo as failed:", todo.id);
-            console.log("🔄 [DEBUG] Clearing operation_id:", todo.operation_id);
-            
-            // Clear the interval for this specific todo
-            if (intervals[todo.id]) {
-              clearInterval(intervals[todo.id]);
-              delete intervals[todo.id];
-            }
-            
-            // Mark todo as failed with a user-friendly error message
-            await updateTodoStatus(
-              todo.id,
-              "failed",
-              undefined, // Clear the operation_id since it's no longer valid
-              undefined,
-              undefined,
-              "The scraping operation was not found. It may have been deleted or expired. You can safely delete this todo and try again."
-            );
-            await loadTodos();
-          } else if (!e.message?.includes("network error")) {
-            // For non-network errors, also stop polling and mark as failed
-            console.log("🔄 [DEBUG] Non-network error, marking todo as failed:", todo.id);
-            
-            if (intervals[todo.id]) {
-              clearInterval(intervals[todo.id]);
-              delete intervals[todo.id];
-            }
-            
-            const execIdResolved: string | undefined = (todo as any).exec_id;
-            await updateTodoStatus(
-              todo.id,
-              "failed",
-              todo.operation_id,
-              execIdResolved,
-              undefined,
-              `Error checking status: ${e.message || "Unknown error"}`
-            );
-            await loadTodos();
-          } else {
-            // Network errors/timeouts: apply simple backoff by skipping next ticks
-            const current = backoffMs[todo.id] || 0;
-            const next = Math.min(30000, current > 0 ? current * 2 : 2000);
-            backoffMs[todo.id] = next;
-            console.log(`⏳ [DEBUG] Network error, backing off ${next}ms for todo ${todo.id}`);
-            // Temporarily set inFlight true and release after backoff period to pause interval loop
-            setTimeout(() => {
-              inFlight[todo.id] = false;
-            }, next);
-            return; // keep inFlight true for now
-          }
-          // For non-network handled branches, continue as normal
-        } finally {
-          // Release only if not in a backoff hold
-          if (!(backoffMs[todo.id] && backoffMs[todo.id] > 0)) {
-            inFlight[todo.id] = false;
-          }
-        }
-      }, POLLING_INTERVAL);
+    const unlisten = listen<ScrapingTodo>("todo-updated", (event) => {
+      const updated = event.payload;
+      console.log("🔄 [DEBUG] Todo updated:", updated.id, updated.status);
+      setTodos(prev => prev.map(t => t.id === updated.id ? updated : t));
     });
+    return () => {
+      unlisten.then(stop => stop());
+    };
+  }, []);
 
+  // Sent once after launch, when the poller has checked jobs left running
+  useEffect(() => {
+    const unlisten = listen<ReconcileSummary>("reconciliation-finished", (event) => {
+      console.log("🔎 [DEBUG] Reconciliation finished:", event.payload);
+      setReconciliation(event.payload);
+      loadTodos();
+    });
     return () => {
-      Object.values(intervals).forEach(clearInterval);
+      unlisten.then(stop => stop());
     };
-  }, [todos]);
+  }, []);
 
   // Todo system functions
   const addTodo = async (todoData: Omit<ScrapingTodo, 'id' | 'created_at' | 'status' | 'manually_completed'>) => {
@@ -272,6 +148,45 @@ export const useAppState = () => {
     }
   };
 
+  // Queue every pending todo; the backend starts them as slots free up
+  const runAllPendingTodos = async () => {
+    try {
+      const queue = await runAllTodos();
+      console.log("✅ [DEBUG] Queued todos, started:", queue.started.length, "waiting:", queue.queued.length);
+    } catch (err: any) {
+      console.error("❌ [DEBUG] Error running all todos:", err);
+      throw err;
+    } finally {
+      await loadTodos();
+    }
+  };
+
+  // Stop a running todo's scrape; partial results stay on the todo
+  const cancelRunningTodo = async (todo: ScrapingTodo) => {
+    if (!todo.operation_id) return;
+    try {
+      const result = await cancelOperation(todo.operation_id);
+      console.log("🛑 [DEBUG] Cancelled todo", todo.id, "kept", result.count, "results");
+    } catch (err: any) {
+      console.error("❌ [DEBUG] Error cancelling todo:", err);
+      throw err;
+    } finally {
+      await loadTodos();
+    }
+  };
+
+  // Put a failed or cancelled todo back in the queue; earlier attempts are kept
+  const retryTodoById = async (todoId: string) => {
+    try {
+      await retryTodo(todoId);
+    } catch (err: any) {
+      console.error("❌ [DEBUG] Error retrying todo:", err);
+      throw err;
+    } finally {
+      await loadTodos();
+    }
+  };
+
   const toggleTodoManualCompleteById = async (todoId: string) => {
     try {
       await toggleTodoManualComplete(todoId);
@@ -323,16 +238,21 @@ export const useAppState = () => {
     initializing,
     todos,
     loadingTodos,
+    reconciliation,
     
     // Setters
     setLoginState,
     setTodos,
+    setReconciliation,
     
     // Actions
     checkExistingLogin,
     loadTodos,
     addTodo,
     runTodoById,
+    runAllPendingTodos,
+    cancelRunningTodo,
+    retryTodoById,
     toggleTodoManualCompleteById,
     deleteTodoById,
     downloadTodoResults,
@@ -7,20 +7,74 @@ export interface LoginStatusResponse {
 }
 
 export interface ScrapeResponse {
-  status: "queued" | "completed" | "failed";
+  // "running"/"stalled" when attached to a scrape that was already going
+  status: "queued" | "completed" | "failed" | "running" | "stalled";
   operation?: string;
   results?: InstagramProfile[];
   count?: number;
   message?: string;
   exec_id?: string;
+  attached?: boolean;
+}
+
+// How to start a scrape for a target that already has one running
+export type ConflictResolution = "attach" | "cancel" | "force";
+
+// `details` of a "conflict" error
+export interface ScrapeConflict {
+  target_account: string;
+  operation_id: string | null;
+  exec_id: string | null;
+  todo_id: string | null;
+  started_at: string;
+  options: ConflictResolution[];
 }
 
 export interface InstagramProfile {
   username: string;
-  url: string;
+  url?: string;
+  full_name?: string;
+  bio?: string;
+  follower_count?: number;
+  following_count?: number;
+  is_private?: boolean;
+  is_verified?: boolean;
+  // Classifier output
+  verdict?: string;
+  reason?: string;
+  confidence?: number;
+  // Any other fields the backend sent
+  [extra: string]: unknown;
 }
 
 // Todo System Types
+export type TodoStatus = "pending" | "running" | "completed" | "failed" | "cancelled" | "lost";
+
+// Structured error returned by every command (and the automation API)
+export type InstagrapErrorCode =
+  | "network"
+  | "timeout"
+  | "backend_http"
+  | "invalid_response"
+  | "login_expired"
+  | "not_found"
+  | "validation"
+  | "invalid_transition"
+  | "conflict"
+  | "persistence"
+  | "browser"
+  | "internal";
+
+export interface InstagrapError {
+  code: InstagrapErrorCode;
+  message: string;
+  // True when repeating the same call may succeed (network blips, 5xx, 429)
+  retryable: boolean;
+  // backend_http: { status, body }; invalid_transition: { entity, from, to };
+  // conflict: ScrapeConflict
+  details: Record<string, unknown> | null;
+}
+
 export interface ScrapingTodo {
   id: string;
   target_account: string;
@@ -29,7 +83,7 @@ export interface ScrapingTodo {
   batch_size: number;
   criteria_preset_id?: string | null;
   criteria_preset_name?: string | null;
-  status: "pending" | "running" | "completed" | "failed";
+  status: TodoStatus;
   created_at: string;
   started_at?: string;
   completed_at?: string;
@@ -38,13 +92,187 @@ export interface ScrapingTodo {
   results?: InstagramProfile[];
   error_message?: string;
   manually_completed: boolean; // Allow manual override
+  attempts?: TodoAttempt[]; // Earlier runs, oldest first
+  next_retry_at?: string | null; // Set while an automatic retry is pending
+}
+
+export interface TodoAttempt {
+  attempt: number;
+  started_at: string | null;
+  finished_at: string;
+  status: TodoStatus;
+  operation_id: string | null;
+  exec_id: string | null;
+  error_message: string | null;
+  retryable: boolean;
+}
+
+// Automatic retries of transient backend failures; null turns them off
+export interface RetryPolicy {
+  max_attempts: number;
+  backoff_seconds: number;
 }
 
 export interface TodoListResponse {
   todos: ScrapingTodo[];
 }
 
-// (Removed) Persistent Operation Types – no longer used
+// Scraping run history (one entry per run, kept across re-scrapes)
+// Operations also go "stalled" while they make no progress
+export type OperationStatus = TodoStatus | "stalled";
+
+export interface ScrapingOperation {
+  operation_id: string;
+  target_account: string;
+  target_count: number;
+  started_at: string;
+  status: OperationStatus;
+  results?: InstagramProfile[] | null;
+  error_message?: string | null;
+  exec_id?: string | null;
+  // Latest backend progress counters (raw on operation-updated events)
+  progress?: Record<string, unknown>;
+}
+
+// Per-operation event log (see README "Operation Timeline")
+export type OperationEventKind =
+  | "queued"
+  | "poll"
+  | "message"
+  | "stalled"
+  | "resumed"
+  | "completed"
+  | "failed"
+  | "cancelled"
+  | "lost";
+
+export interface OperationProgress {
+  scanned?: number;
+  classified?: number;
+  yes_count?: number;
+}
+
+export interface OperationEvent {
+  seq: number;
+  at: string;
+  kind: OperationEventKind;
+  status?: string;
+  progress?: OperationProgress;
+  message?: string;
+  // Identical polls folded into this event
+  polls: number;
+  last_polled_at?: string;
+}
+
+export interface OperationTimeline {
+  operation_id: string;
+  target_account: string;
+  status: OperationStatus;
+  started_at: string;
+  last_activity_at: string;
+  events: OperationEvent[];
+}
+
+export interface TargetHistoryResponse {
+  target_account: string;
+  operations: ScrapingOperation[];
+}
+
+// Global profile store
+export interface ProfileSighting {
+  operation_id: string;
+  target_account: string;
+  seen_at: string;
+}
+
+export interface StoredProfile {
+  username: string;
+  data: InstagramProfile;
+  first_seen_at: string;
+  last_seen_at: string;
+  sightings: ProfileSighting[];
+}
+
+export interface ProfileSearchResponse {
+  profiles: StoredProfile[];
+  total: number;
+}
+
+// Runtime settings: named environments and the active one
+export interface Environment {
+  name: string;
+  api_base: string;
+  classifier_base: string;
+  gcp_project: string;
+  default_bucket: string;
+}
+
+export interface Settings {
+  active_environment: string;
+  environments: Environment[];
+}
+
+export interface SettingsValidation {
+  valid: boolean;
+  problems: string[];
+}
+
+// Backend todo queue; `running` counts remote jobs in flight
+export interface TodoQueue {
+  max_concurrent: number;
+  queued: string[];
+  running: number;
+}
+
+// Todo schedules; times are ISO strings in UTC, cron runs in local time
+export type Schedule =
+  | { kind: "once"; at: string }
+  | { kind: "cron"; expression: string }
+  | { kind: "interval"; minutes: number };
+
+export interface TodoSchedule {
+  todo_id: string;
+  schedule: Schedule;
+  paused: boolean;
+  next_run_at: string | null;
+  last_run_at: string | null;
+}
+
+// Local "HH:MM" window in which nothing starts, may wrap midnight
+export interface QuietHours {
+  start: string;
+  end: string;
+}
+
+export interface UpcomingRun {
+  todo_id: string;
+  target_account: string;
+  scheduled_at: string;
+  starts_at: string;
+  delayed_by_quiet_hours: boolean;
+}
+
+// Startup check of jobs left running; `outcome` is the status afterwards or "unreachable"
+export interface ReconciledItem {
+  id: string;
+  target_account: string;
+  outcome: string;
+}
+
+export interface ReconcileSummary {
+  checked_at: string;
+  operations: ReconciledItem[];
+  todos: ReconciledItem[];
+}
+
+// Localhost automation API (see README "Automation API")
+export interface AutomationSettings {
+  enabled: boolean;
+  port: number;
+  token: string;
+  running: boolean;
+  url: string | null;
+}
 
 // UI State Types
 export type LoginState = "idle" | "running" | "done" | "fail" | "checking" | "none";
@@ -72,6 +300,9 @@ export interface TodoListProps {
   loginState: LoginState;
   onAddTodo: (todo: Omit<ScrapingTodo, 'id' | 'created_at' | 'status' | 'manually_completed'>) => void;
   onRunTodo: (todoId: string, criteriaPresetId?: string | null) => Promise<void>;
+  onRunAllTodos: () => Promise<void>;
+  onCancelTodo: (todo: ScrapingTodo) => Promise<void>;
+  onRetryTodo: (todoId: string) => Promise<void>;
   onToggleManualComplete: (todoId: string) => void;
   onDeleteTodo: (todoId: string) => void;
   onDownloadResults: (todo: ScrapingTodo) => void;
@@ -103,4 +334,14 @@ export interface CriteriaPreset {
 export interface SavedCriteriaResponse {
   presets: CriteriaPreset[];
   active_id?: string | null;
-}
\ No newline at end of file
+}
+// State Backups
+export interface StateBackup {
+  name: string;
+  created_at: string;
+  size_bytes: number;
+}
+
+export interface StateBackupListResponse {
+  backups: StateBackup[];
+}
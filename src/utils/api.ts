@@ -2,9 +2,27 @@ import { invoke } from '@tauri-apps/api/core';
 import { 
   LoginStatusResponse, 
   ScrapeResponse, 
+  ConflictResolution,
   ScrapingTodo,
   TodoListResponse,
-  SavedCriteriaResponse
+  SavedCriteriaResponse,
+  StateBackup,
+  StateBackupListResponse,
+  ScrapingOperation,
+  OperationTimeline,
+  TargetHistoryResponse,
+  StoredProfile,
+  ProfileSearchResponse,
+  AutomationSettings,
+  Settings,
+  SettingsValidation,
+  TodoQueue,
+  Schedule,
+  TodoSchedule,
+  QuietHours,
+  UpcomingRun,
+  RetryPolicy,
+  ReconcileSummary
 } from '../types';
 
 /**
@@ -19,9 +37,8 @@ export const checkLoginStatus = async (): Promise<LoginStatusResponse> => {
  */
 export const startLogin = async (): Promise<string> => {
   console.log("🔍 [DEBUG] Starting Instagram login...");
-  const gcsUri: string = await invoke("login_and_upload", {
-    bucketUrl: "gs://insta-state/"
-  });
+  // Uploads to the active environment's default bucket
+  const gcsUri: string = await invoke("login_and_upload");
   console.log("gcsUri", gcsUri);
   
   // Register the state with the backend API
@@ -33,7 +50,8 @@ export const startLogin = async (): Promise<string> => {
 };
 
 /**
- * Start a scraping operation
+ * Start a scraping operation. Rejects with a "conflict" error if the target
+ * already has one running, unless `onConflict` says what to do about it.
  */
 export const startScrape = async (
   target: string,
@@ -41,7 +59,8 @@ export const startScrape = async (
   batchSize: number,
   bioAgents: number,
   criteriaPresetId?: string | null,
-  criteriaText?: string | null
+  criteriaText?: string | null,
+  onConflict?: ConflictResolution | null
 ): Promise<ScrapeResponse> => {
   console.log("🔍 [DEBUG] Starting scrape for:", target);
   console.log("🔍 [DEBUG] Criteria preset ID:", criteriaPresetId);
@@ -51,7 +70,8 @@ export const startScrape = async (
     batchSize: batchSize,
     numBioPages: bioAgents,
     criteriaPresetId: criteriaPresetId ?? null,
-    criteriaText: criteriaText ?? null
+    criteriaText: criteriaText ?? null,
+    onConflict: onConflict ?? null
   });
   
   console.log("🔍 [DEBUG] Proxy response received:", response);
@@ -86,6 +106,124 @@ export const deleteGcsArtifacts = async (target: string, execId: string): Promis
  */
 // Deprecated: persistent operations UI has been removed
 
+/**
+ * Get every run for a target account, newest first
+ */
+export const getTargetHistory = async (targetAccount: string): Promise<TargetHistoryResponse> => {
+  return await invoke<TargetHistoryResponse>("get_target_history", { targetAccount });
+};
+
+/**
+ * Get a single run by operation id
+ */
+export const getOperationRun = async (operationId: string): Promise<ScrapingOperation> => {
+  return await invoke<ScrapingOperation>("get_operation_run", { operationId });
+};
+
+/**
+ * Delete all but the newest `keep` finished runs of a target; returns how many were removed
+ */
+export const pruneTargetHistory = async (targetAccount: string, keep: number): Promise<number> => {
+  return await invoke<number>("prune_target_history", { targetAccount, keep });
+};
+
+/**
+ * Search the global profile store, optionally limited to one target account
+ */
+export const searchProfiles = async (
+  query?: string,
+  targetAccount?: string,
+  limit?: number,
+  offset?: number
+): Promise<ProfileSearchResponse> => {
+  return await invoke<ProfileSearchResponse>("search_profiles", { query, targetAccount, limit, offset });
+};
+
+/**
+ * Get one stored profile with every run that produced it
+ */
+export const getProfile = async (username: string): Promise<StoredProfile> => {
+  return await invoke<StoredProfile>("get_profile", { username });
+};
+
+/**
+ * Return which of the given usernames were already collected by an earlier run
+ */
+export const checkKnownProfiles = async (usernames: string[]): Promise<string[]> => {
+  const response = await invoke<{ known: string[] }>("check_known_profiles", { usernames });
+  return response.known;
+};
+
+/**
+ * Environments (prod, staging, local) and which one the backend clients use
+ */
+export const getSettings = async (): Promise<Settings> => {
+  return await invoke<Settings>("get_settings");
+};
+
+export const setSettings = async (settings: Settings): Promise<Settings> => {
+  return await invoke<Settings>("set_settings", { settings });
+};
+
+export const validateSettings = async (settings: Settings): Promise<SettingsValidation> => {
+  return await invoke<SettingsValidation>("validate_settings", { settings });
+};
+
+export const setActiveEnvironment = async (name: string): Promise<Settings> => {
+  return await invoke<Settings>("set_active_environment", { name });
+};
+
+/**
+ * Localhost automation API settings and whether the server is running
+ */
+export const getAutomationSettings = async (): Promise<AutomationSettings> => {
+  return await invoke<AutomationSettings>("get_automation_settings");
+};
+
+export const setAutomationServer = async (enabled: boolean, port?: number): Promise<AutomationSettings> => {
+  return await invoke<AutomationSettings>("set_automation_server", { enabled, port });
+};
+
+export const regenerateAutomationToken = async (): Promise<AutomationSettings> => {
+  return await invoke<AutomationSettings>("regenerate_automation_token");
+};
+
+/**
+ * Stop a running operation on the backend. Partial results are kept; with
+ * deleteArtifacts the backend copy of the run is removed as well.
+ */
+export const cancelOperation = async (
+  operationId: string,
+  deleteArtifacts = false
+): Promise<{ operation_id: string; status: string; count: number; todo_ids: string[]; artifacts_deleted: boolean }> => {
+  return await invoke("cancel_operation", { operationId, deleteArtifacts });
+};
+
+/**
+ * Event log of an operation: polls with progress, backend messages and how it ended
+ */
+export const getOperationTimeline = async (operationId: string): Promise<OperationTimeline> => {
+  return await invoke<OperationTimeline>("get_operation_timeline", { operationId });
+};
+
+/**
+ * Minutes without progress after which a running operation is marked stalled
+ */
+export const getStallThreshold = async (): Promise<number> => {
+  return await invoke<number>("get_stall_threshold");
+};
+
+export const setStallThreshold = async (minutes: number): Promise<void> => {
+  await invoke("set_stall_threshold", { minutes });
+};
+
+/**
+ * Outcome of the startup check of jobs left running, null until it has run
+ */
+export const getReconciliationSummary = async (): Promise<ReconcileSummary | null> => {
+  return await invoke<ReconcileSummary | null>("get_reconciliation_summary");
+};
+
 /**
  * Remove persistent operation
  */
@@ -259,82 +397,100 @@ export const setTodoCriteriaPreset = async (todoId: string, presetId?: string |
   });
 };
 
+// State Backup API Functions
+
+export const listStateBackups = async (): Promise<StateBackupListResponse> => {
+  return await invoke<StateBackupListResponse>("list_state_backups");
+};
+
+export const createStateBackup = async (): Promise<StateBackup> => {
+  return await invoke<StateBackup>("create_state_backup");
+};
+
+export const restoreStateBackup = async (name: string): Promise<void> => {
+  await invoke("restore_state_backup", { name });
+};
+
 /**
- * Run a todo (start scraping)
+ * Run a todo (start scraping). The backend links the operation to the todo,
+ * so a reload mid-run can't lose it.
  */
 export const runTodo = async (
   todo: ScrapingTodo,
   criteriaPresetIdOverride?: string | null
 ): Promise<string | undefined> => {
   console.log("🔍 [DEBUG] Running todo:", todo.id);
-  
-  try {
-    // Update todo status to running
-    await updateTodoStatus(todo.id, "running");
-    
-    // Start the scraping operation
-    console.log("🔍 [DEBUG] Starting scrape with criteria preset:", criteriaPresetIdOverride);
-    console.log("🔍 [DEBUG] Todo criteria preset:", todo.criteria_preset_id);
-    const response = await startScrape(
-      todo.target_account,
-      todo.target_count,
-      todo.batch_size,
-      todo.bio_agents,
-      // Use override if provided; otherwise fallback to the todo's value
-      (criteriaPresetIdOverride ?? todo.criteria_preset_id ?? null)
-    );
-    
-    console.log("🔍 [DEBUG] Scrape response for todo:", response);
-    
-    if (response.status === "queued") {
-      // Update todo with operation ID
-    await updateTodoStatus(
-        todo.id,
-        "running",
-      response.operation,
-      response.exec_id,
-        undefined,
-        undefined
-      );
-    console.log("✅ Todo queued with operation ID:", response.operation);
-    if (response.exec_id) {
-      // Attach exec_id to our local todo object (frontend state) if needed by UI later
-      (todo as any).exec_id = response.exec_id;
-      console.log("✅ Todo queued with exec_id:", response.exec_id);
-    }
-      return response.exec_id;
-    } else if (response.status === "completed") {
-      // Update todo as completed
-      await updateTodoStatus(
-        todo.id,
-        "completed",
-        undefined,
-        undefined,
-        undefined
-      );
-      console.log("✅ Todo completed immediately");
-      return undefined;
-    } else if (response.status === "failed") {
-      // Update todo as failed
-      await updateTodoStatus(
-        todo.id,
-        "failed",
-        undefined,
-        undefined,
-        undefined
-      );
-      console.log("❌ Todo failed:", response.message);
-      return undefined;
-    }
-  } catch (error: any) {
-    console.error("❌ [DEBUG] Error running todo:", error);
-    await updateTodoStatus(
-      todo.id,
-      "failed",
-      undefined,
-    undefined,
-      error.message || "Unknown error"
-    );
-    throw error;
-  }
- };
\ No newline at end of file
+  const response = await invoke<ScrapeResponse>("run_todo", {
+    todoId: todo.id,
+    criteriaPresetId: criteriaPresetIdOverride ?? null,
+  });
+  console.log("🔍 [DEBUG] Scrape response for todo:", response);
+  return response.exec_id;
+};
+
+/**
+ * Queue a failed or cancelled todo for another attempt
+ */
+export const retryTodo = async (todoId: string): Promise<ScrapingTodo> => {
+  return await invoke<ScrapingTodo>("retry_todo", { todoId });
+};
+
+export const getRetryPolicy = async (): Promise<RetryPolicy | null> => {
+  return await invoke<RetryPolicy | null>("get_retry_policy");
+};
+
+export const setRetryPolicy = async (policy: RetryPolicy | null): Promise<void> => {
+  await invoke("set_retry_policy", { policy });
+};
+
+// Todo queue: run all / run next with a concurrency limit
+
+export const getTodoQueue = async (): Promise<TodoQueue> => {
+  return await invoke<TodoQueue>("get_todo_queue");
+};
+
+export const runAllTodos = async (): Promise<TodoQueue & { started: string[] }> => {
+  return await invoke<TodoQueue & { started: string[] }>("run_all_todos");
+};
+
+export const runNextTodo = async (): Promise<{ todo_id: string; response: ScrapeResponse }> => {
+  return await invoke<{ todo_id: string; response: ScrapeResponse }>("run_next_todo");
+};
+
+export const setQueueConcurrency = async (maxConcurrent: number): Promise<TodoQueue> => {
+  return await invoke<TodoQueue>("set_queue_concurrency", { maxConcurrent });
+};
+
+// Todo schedules and quiet hours
+
+export const getTodoSchedules = async (): Promise<TodoSchedule[]> => {
+  return await invoke<TodoSchedule[]>("get_todo_schedules");
+};
+
+export const setTodoSchedule = async (todoId: string, schedule: Schedule): Promise<TodoSchedule> => {
+  return await invoke<TodoSchedule>("set_todo_schedule", { todoId, schedule });
+};
+
+export const removeTodoSchedule = async (todoId: string): Promise<void> => {
+  await invoke("remove_todo_schedule", { todoId });
+};
+
+export const pauseTodoSchedule = async (todoId: string): Promise<TodoSchedule> => {
+  return await invoke<TodoSchedule>("pause_todo_schedule", { todoId });
+};
+
+export const resumeTodoSchedule = async (todoId: string): Promise<TodoSchedule> => {
+  return await invoke<TodoSchedule>("resume_todo_schedule", { todoId });
+};
+
+export const getUpcomingRuns = async (limit?: number): Promise<UpcomingRun[]> => {
+  return await invoke<UpcomingRun[]>("get_upcoming_runs", { limit: limit ?? null });
+};
+
+export const getQuietHours = async (): Promise<QuietHours | null> => {
+  return await invoke<QuietHours | null>("get_quiet_hours");
+};
+
+export const setQuietHours = async (quietHours: QuietHours | null): Promise<void> => {
+  await invoke("set_quiet_hours", { quietHours });
+};